@@ -18,10 +18,13 @@ use cw_croncat::contract::{
     GAS_NUMERATOR_DEFAULT, GAS_QUERY_FEE, GAS_WASM_QUERY_FEE,
 };
 use cw_croncat_core::{
-    msg::{TaskRequest, TaskResponse, TaskWithQueriesResponse},
+    msg::{GetTasksResponse, GetTasksWithQueriesResponse, TaskRequest, TaskWithQueriesResponse},
     types::{Action, GasPrice},
 };
-use cw_rules_core::{msg::QueryResponse, types::CroncatQuery};
+use cw_rules_core::{
+    msg::QueryResponse,
+    types::{query_leaves, CroncatQuery},
+};
 
 const fn add_agent_fee(num: u64) -> u64 {
     num + (num * 5 / 100)
@@ -35,12 +38,14 @@ fn min_gas_for_actions(actions: &[Action]) -> u64 {
 
 fn min_gas_for_queries(queries: Option<&Vec<CroncatQuery>>) -> u64 {
     if let Some(queries) = queries {
-        queries.iter().fold(GAS_WASM_QUERY_FEE, |acc, query| {
-            acc + match query {
-                CroncatQuery::HasBalanceGte(_) => GAS_QUERY_FEE,
-                _ => GAS_WASM_QUERY_FEE,
-            }
-        })
+        query_leaves(queries)
+            .into_iter()
+            .fold(GAS_WASM_QUERY_FEE, |acc, query| {
+                acc + match query {
+                    CroncatQuery::HasBalanceGte(_) | CroncatQuery::RawQuery { .. } => GAS_QUERY_FEE,
+                    _ => GAS_WASM_QUERY_FEE,
+                }
+            })
     } else {
         0
     }
@@ -92,6 +97,8 @@ pub(crate) fn init_contracts(
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
         gas_base_fee: None,
     };
 
@@ -232,11 +239,12 @@ where
     let query_res = orc.query(
         CRONCAT_NAME,
         &cw_croncat_core::msg::QueryMsg::GetTasks {
-            from_index: None,
+            start_after: None,
             limit: None,
         },
     )?;
-    let tasks: Vec<TaskResponse> = query_res.data()?;
+    let tasks: GetTasksResponse = query_res.data()?;
+    let tasks = tasks.tasks;
     if tasks.len() != tasks.len() {
         return Err(anyhow::anyhow!("{} tasks finihsed too early", tasks.len()));
     }
@@ -255,11 +263,12 @@ where
     let query_res = orc.query(
         CRONCAT_NAME,
         &cw_croncat_core::msg::QueryMsg::GetTasks {
-            from_index: None,
+            start_after: None,
             limit: None,
         },
     )?;
-    let tasks: Vec<TaskResponse> = query_res.data()?;
+    let tasks: GetTasksResponse = query_res.data()?;
+    let tasks = tasks.tasks;
     if !tasks.is_empty() {
         return Err(anyhow::anyhow!("{} tasks not finihsed", tasks.len()));
     }
@@ -357,14 +366,14 @@ pub(crate) fn proxy_call_with_hash(
 }
 
 pub(crate) fn query_tasks_with_rules(orc: &CosmOrc) -> Result<Vec<TaskWithQueriesResponse>> {
-    let tasks = orc
+    let tasks: GetTasksWithQueriesResponse = orc
         .query(
             CRONCAT_NAME,
             &cw_croncat::QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )?
         .data()?;
-    Ok(tasks)
+    Ok(tasks.tasks)
 }
@@ -206,8 +206,14 @@ fn main() -> Result<()> {
                 gas_wasm_query_fee: None,
                 gas_price: None,
                 proxy_callback_gas: None,
+                ibc_transfer_timeout: None,
+                gov_msgs_enabled: None,
+                stargate_type_urls: None,
+                admin_allowlist: None,
                 min_tasks_per_agent: None,
                 agents_eject_threshold: None,
+                fee_tokens: None,
+                gas_price_source: None,
             },
             &admin_key,
             vec![],
@@ -40,6 +40,12 @@ where
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     let msg = cw_croncat_core::msg::ExecuteMsg::CreateTask { task };
     orc.execute(
@@ -86,6 +92,12 @@ pub(crate) fn send_to_bob_recurring(denom: &str) -> TaskRequest {
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -114,6 +126,12 @@ pub(crate) fn send_to_bob_and_alice_recurring(denom: &str) -> TaskRequest {
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -144,6 +162,12 @@ pub(crate) fn send_cw20_to_bob_recurring(cw20_addr: &str, times: u128) -> TaskRe
             address: cw20_addr.to_owned(),
             amount: (times * amount).into(),
         }],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -188,6 +212,12 @@ pub(crate) fn send_cw20_to_bob_and_alice_recurring(cw20_addr: &str, times: u128)
             address: cw20_addr.to_owned(),
             amount: (times * amount).into(),
         }],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -206,6 +236,12 @@ pub(crate) fn delegate_to_bob_recurring(denom: &str) -> TaskRequest {
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -232,6 +268,12 @@ pub(crate) fn delegate_to_bob_and_alice_recurring(denom: &str) -> TaskRequest {
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -254,6 +296,12 @@ pub(crate) fn delegate_to_validator(denom: &str) -> TaskRequest {
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -276,6 +324,12 @@ pub(crate) fn delegate_to_validator_twice(denom: &str) -> TaskRequest {
         queries: None,
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
 
@@ -326,5 +380,11 @@ pub(crate) fn send_cw20_to_insertable_addr(
             address: cw20_addr.to_owned(),
             amount: amount.into(),
         }],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
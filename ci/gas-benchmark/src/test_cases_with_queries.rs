@@ -4,7 +4,7 @@ use cosm_orc::{
 };
 use cosmwasm_std::coins;
 use cw_croncat_core::{
-    msg::TaskWithQueriesResponse,
+    msg::GetTasksWithQueriesResponse,
     types::{Action, Interval},
 };
 use cw_rules_core::types::{CroncatQuery, HasBalanceGte};
@@ -41,6 +41,12 @@ where
         })]),
         transforms: None,
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     let msg = cw_croncat_core::msg::ExecuteMsg::CreateTask { task };
     orc.execute(
@@ -55,15 +61,16 @@ where
     )?;
 
     orc.poll_for_n_blocks(1, std::time::Duration::from_millis(20_000), false)?;
-    let mut active_tasks: Vec<TaskWithQueriesResponse> = orc
+    let active_tasks: GetTasksWithQueriesResponse = orc
         .query(
             CRONCAT_NAME,
             &cw_croncat_core::msg::QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )?
         .data()?;
+    let mut active_tasks = active_tasks.tasks;
     let before_pc = query_balance(orc, agent_addr.clone(), denom.clone())?;
     let res = orc.execute(
         CRONCAT_NAME,
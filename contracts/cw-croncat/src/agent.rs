@@ -1,19 +1,24 @@
 use crate::balancer::Balancer;
 use crate::error::ContractError;
-use crate::helpers::{send_tokens, GenericBalance};
+use crate::helpers::{page_limit, send_tokens, GenericBalance};
 use crate::state::{Config, CwCroncat};
 use cosmwasm_std::{
-    has_coins, Addr, Coin, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
-    SubMsg,
+    has_coins, Addr, Coin, CustomMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, SubMsg,
 };
 use cw_storage_plus::Bound;
+use serde::de::DeserializeOwned;
 use std::ops::Div;
 
 use crate::ContractError::*;
+use cw_croncat_core::events::{AgentActivated, AgentEjected, AgentRegistered, CroncatEvent};
 use cw_croncat_core::msg::{AgentResponse, AgentTaskResponse, GetAgentIdsResponse};
 use cw_croncat_core::types::{gas_amount_with_agent_fee, Agent, AgentStatus};
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     /// Get a single agent details
     /// Check's status as well, in case this agent needs to be considered for election
     pub(crate) fn query_get_agent(
@@ -49,14 +54,41 @@ impl<'a> CwCroncat<'a> {
     }
 
     /// Get a list of agent addresses
-    pub(crate) fn query_get_agent_ids(&self, deps: Deps) -> StdResult<GetAgentIdsResponse> {
+    /// Pages go through the active agents, then the pending ones in the queue order
+    /// The cursor is the position of the agent, a page reads only its own pending agents
+    pub(crate) fn query_get_agent_ids(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    ) -> StdResult<GetAgentIdsResponse> {
+        let max_limit = self.config.load(deps.storage)?.limit;
+        let limit = page_limit(limit, max_limit) as u64;
+        let start = start_after.map_or(0, |pos| pos.saturating_add(1));
+        // Active agents are stored as a single item
         let active: Vec<Addr> = self.agent_active_queue.load(deps.storage)?;
-        let pending: Vec<Addr> = self
-            .agent_pending_queue
-            .iter(deps.storage)?
+        let active_len = active.len() as u64;
+        let total = active_len + self.agent_pending_queue.len(deps.storage)? as u64;
+        let end = start.saturating_add(limit).min(total);
+
+        let active = active
+            .into_iter()
+            .skip(start as usize)
+            .take(end.saturating_sub(start) as usize)
+            .collect();
+        let pending = (start.max(active_len)..end)
+            .map(|pos| {
+                self.agent_pending_queue
+                    .get(deps.storage, (pos - active_len) as u32)?
+                    .ok_or_else(|| StdError::not_found("agent"))
+            })
             .collect::<StdResult<Vec<Addr>>>()?;
 
-        Ok(GetAgentIdsResponse { active, pending })
+        Ok(GetAgentIdsResponse {
+            active,
+            pending,
+            next_cursor: (end < total).then(|| end - 1),
+        })
     }
 
     // TODO: Change this to solid round-table implementation. Setup this simple version for PoC
@@ -123,7 +155,7 @@ impl<'a> CwCroncat<'a> {
         info: MessageInfo,
         env: Env,
         payable_account_id: Option<String>,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         if !info.funds.is_empty() {
             return Err(ContractError::CustomError {
                 val: "Do not attach funds".to_string(),
@@ -192,11 +224,22 @@ impl<'a> CwCroncat<'a> {
             },
         )?;
 
-        Ok(Response::new()
+        let registered = AgentRegistered {
+            agent: account.clone(),
+            payable_account_id: agent.payable_account_id.clone(),
+            status: agent_status.clone(),
+        };
+        let res = Response::new()
             .add_attribute("method", "register_agent")
             .add_attribute("agent_status", format!("{:?}", agent_status))
             .add_attribute("register_start", agent.register_start.nanos().to_string())
-            .add_attribute("payable_account_id", agent.payable_account_id))
+            .add_attribute("payable_account_id", agent.payable_account_id)
+            .add_event(registered.into());
+        if agent_status == AgentStatus::Active {
+            Ok(res.add_event(AgentActivated { agent: account }.into()))
+        } else {
+            Ok(res)
+        }
     }
 
     /// Update agent details, specifically the payable account id for an agent.
@@ -206,7 +249,7 @@ impl<'a> CwCroncat<'a> {
         info: MessageInfo,
         _env: Env,
         payable_account_id: String,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         let payable_account_id = deps.api.addr_validate(&payable_account_id)?;
         let c: Config = self.config.load(deps.storage)?;
         if c.paused {
@@ -240,7 +283,7 @@ impl<'a> CwCroncat<'a> {
         &self,
         storage: &mut dyn Storage,
         agent_id: &Addr,
-    ) -> Result<Vec<SubMsg>, ContractError> {
+    ) -> Result<Vec<SubMsg<T>>, ContractError> {
         let mut agent = self
             .agents
             .may_load(storage, agent_id)?
@@ -250,9 +293,7 @@ impl<'a> CwCroncat<'a> {
         let (messages, balances) = send_tokens(&agent.payable_account_id, &agent.balance)?;
         agent.balance.checked_sub_generic(&balances)?;
         let mut config = self.config.load(storage)?;
-        config
-            .available_balance
-            .checked_sub_native(&balances.native)?;
+        config.available_balance.checked_sub_generic(&balances)?;
         self.agents.save(storage, agent_id, &agent)?;
         self.config.save(storage, &config)?;
 
@@ -264,7 +305,7 @@ impl<'a> CwCroncat<'a> {
         &self,
         deps: DepsMut,
         agent_id: &Addr,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         let messages = self.withdraw_balances(deps.storage, agent_id)?;
 
         Ok(Response::new()
@@ -279,7 +320,7 @@ impl<'a> CwCroncat<'a> {
         deps: DepsMut,
         info: MessageInfo,
         env: Env,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         // Compare current time and Config's agent_nomination_begin_time to see if agent can join
         let c: Config = self.config.load(deps.storage)?;
 
@@ -312,7 +353,8 @@ impl<'a> CwCroncat<'a> {
                     self.agent_nomination_begin_time.save(deps.storage, &None)?;
                     return Ok(Response::new()
                         .add_attribute("method", "accept_nomination_agent")
-                        .add_attribute("new_agent", info.sender.as_str()));
+                        .add_attribute("new_agent", info.sender.as_str())
+                        .add_event(AgentActivated { agent: info.sender }.into()));
                 } else {
                     // No agents can join yet
                     return Err(ContractError::CustomError {
@@ -357,11 +399,30 @@ impl<'a> CwCroncat<'a> {
                 val: "Must wait longer before accepting nomination".to_string(),
             });
         };
+        // Agents in front of this one lose their place in the pending queue
+        let ejected = kicked_agents
+            .iter()
+            .filter(|agent| **agent != info.sender)
+            .map(|agent| {
+                AgentEjected {
+                    agent: agent.clone(),
+                    status: AgentStatus::Pending,
+                }
+                .into_event()
+            })
+            .collect::<Vec<_>>();
         // Find difference
         Ok(Response::new()
             .add_attribute("method", "accept_nomination_agent")
             .add_attribute("new_agent", info.sender.as_str())
-            .add_attribute("kicked_agents: ", format!("{kicked_agents:?}")))
+            .add_attribute("kicked_agents: ", format!("{kicked_agents:?}"))
+            .add_event(
+                AgentActivated {
+                    agent: info.sender.clone(),
+                }
+                .into(),
+            )
+            .add_events(ejected))
     }
 
     /// Removes the agent from the active set of agents.
@@ -372,7 +433,7 @@ impl<'a> CwCroncat<'a> {
         storage: &mut dyn Storage,
         agent_id: &Addr,
         from_behind: Option<bool>,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         // Get withdraw messages, if any
         // NOTE: Since this also checks if agent exists, safe to not have redundant logic
         let messages = self.withdraw_balances(storage, agent_id)?;
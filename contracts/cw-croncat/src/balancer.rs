@@ -29,13 +29,13 @@ pub trait Balancer<'a> {
         active_agents: &Item<'a, Vec<Addr>>,
         agent_id: Addr,
     ) -> Result<(), ContractError>;
-    fn on_task_completed(
+    fn on_task_completed<T>(
         &self,
         storage: &'a mut dyn Storage,
         _env: &Env,
         config: &Item<'a, Config>,
         active_agents: &Item<'a, Vec<Addr>>,
-        task_info: &TaskInfo,
+        task_info: &TaskInfo<T>,
     ) -> Result<(), ContractError>;
 }
 
@@ -262,13 +262,13 @@ impl<'a> Balancer<'a> for RoundRobinBalancer {
         Ok(())
     }
 
-    fn on_task_completed(
+    fn on_task_completed<T>(
         &self,
         storage: &'a mut dyn Storage,
         _env: &Env,
         config: &Item<'a, Config>,
         active_agents: &Item<'a, Vec<Addr>>,
-        task_info: &TaskInfo,
+        task_info: &TaskInfo<T>,
     ) -> Result<(), ContractError> {
         if !task_info.task_is_extra.unwrap_or(false) && self.mode == BalancerMode::ActivationOrder {
             return Ok(());
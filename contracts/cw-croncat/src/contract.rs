@@ -1,13 +1,13 @@
 use crate::error::ContractError;
 use crate::helpers::GenericBalance;
-use crate::state::{Config, CwCroncat};
-#[cfg(not(feature = "library"))]
+use crate::state::{Config, CwCroncat, TaskMigration, TaskMigrationStage};
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+    to_binary, Binary, CustomMsg, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
 };
-use cw2::set_contract_version;
-use cw_croncat_core::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use cw_croncat_core::types::{GasPrice, SlotType};
+use cw2::{get_contract_version, set_contract_version};
+use cw_croncat_core::msg::{GenericExecuteMsg, GenericQueryMsg, InstantiateMsg, MigrateMsg};
+use cw_croncat_core::types::{GasPrice, GasPriceSource, SlotType, TASK_HASH_VERSION};
+use serde::de::DeserializeOwned;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw-croncat";
@@ -23,6 +23,12 @@ pub const GAS_ACTION_FEE: u64 = 130_000;
 pub const GAS_QUERY_FEE: u64 = 5_000;
 /// Gas needed for single wasm query
 pub const GAS_WASM_QUERY_FEE: u64 = 60_000;
+/// Seconds an IBC transfer action stays valid after the execution block
+pub const IBC_TRANSFER_TIMEOUT: u64 = 600;
+/// Reply id of the task callback, never used by the reply queue
+pub const TASK_CALLBACK_REPLY_ID: u64 = u64::MAX;
+/// Executions kept in the history of every task
+pub const TASK_HISTORY_SIZE: u64 = 10;
 /// We can't store gas_price as floats inside cosmwasm
 /// so insted of having 0.04 we use GasFraction{4/100}
 /// and after that multiply Gas by `gas_adjustment` {150/100} (1.5)
@@ -33,14 +39,17 @@ pub mod gas_price_defaults {
 }
 pub use gas_price_defaults::*;
 // #[cfg(not(feature = "library"))]
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     pub fn instantiate(
         &self,
         deps: DepsMut,
         _env: Env,
         info: MessageInfo,
         msg: InstantiateMsg,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         // keep tally of balances initialized
         let available_balance = GenericBalance {
             native: info.funds,
@@ -78,6 +87,12 @@ impl<'a> CwCroncat<'a> {
                 .map(Into::into)
                 .unwrap_or(GAS_WASM_QUERY_FEE),
             slot_granularity_time: 10_000_000_000, // 10 seconds
+            ibc_transfer_timeout: msg.ibc_transfer_timeout.unwrap_or(IBC_TRANSFER_TIMEOUT),
+            gov_msgs_enabled: msg.gov_msgs_enabled.unwrap_or(false),
+            stargate_type_urls: vec![],
+            admin_allowlist: vec![],
+            fee_tokens: vec![],
+            gas_price_source: GasPriceSource::Fixed,
             native_denom: msg.denom,
             cw20_whitelist: vec![],
             // TODO: ????
@@ -96,6 +111,8 @@ impl<'a> CwCroncat<'a> {
         self.reply_index.save(deps.storage, &Default::default())?;
         self.agent_nomination_begin_time.save(deps.storage, &None)?;
         self.tasks_with_queries_total.save(deps.storage, &0)?;
+        self.task_hash_version
+            .save(deps.storage, &TASK_HASH_VERSION.to_string())?;
 
         // all instantiated data
         Ok(Response::new()
@@ -139,85 +156,190 @@ impl<'a> CwCroncat<'a> {
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        msg: ExecuteMsg,
-    ) -> Result<Response, ContractError> {
+        msg: GenericExecuteMsg<T>,
+    ) -> Result<Response<T>, ContractError> {
         match msg {
-            ExecuteMsg::UpdateSettings { .. } => self.update_settings(deps, info, msg),
-            ExecuteMsg::MoveBalances {
+            GenericExecuteMsg::UpdateSettings { .. } => self.update_settings(deps, info, msg),
+            GenericExecuteMsg::MoveBalances {
                 balances,
                 account_id,
             } => self.move_balances(deps, info, env, balances, account_id),
 
-            ExecuteMsg::RegisterAgent { payable_account_id } => {
+            GenericExecuteMsg::RegisterAgent { payable_account_id } => {
                 self.register_agent(deps, info, env, payable_account_id)
             }
-            ExecuteMsg::UpdateAgent { payable_account_id } => {
+            GenericExecuteMsg::UpdateAgent { payable_account_id } => {
                 self.update_agent(deps, info, env, payable_account_id)
             }
-            ExecuteMsg::UnregisterAgent { from_behind } => {
+            GenericExecuteMsg::UnregisterAgent { from_behind } => {
                 self.unregister_agent(deps.storage, &info.sender, from_behind)
             }
-            ExecuteMsg::WithdrawReward {} => self.withdraw_agent_balance(deps, &info.sender),
-            ExecuteMsg::CheckInAgent {} => self.accept_nomination_agent(deps, info, env),
+            GenericExecuteMsg::WithdrawReward {} => self.withdraw_agent_balance(deps, &info.sender),
+            GenericExecuteMsg::CheckInAgent {} => self.accept_nomination_agent(deps, info, env),
 
-            ExecuteMsg::CreateTask { task } => self.create_task(deps, info, env, task),
-            ExecuteMsg::RemoveTask { task_hash } => {
+            GenericExecuteMsg::CreateTask { task } => self.create_task(deps, info, env, task),
+            GenericExecuteMsg::UpdateTask { .. } => self.update_task(deps, info, env, msg),
+            GenericExecuteMsg::PauseTask { task_hash } => self.pause_task(deps, info, task_hash),
+            GenericExecuteMsg::ResumeTask { task_hash } => {
+                self.resume_task(deps, info, env, task_hash)
+            }
+            GenericExecuteMsg::RemoveTask { task_hash } => {
                 self.remove_task(deps.storage, &task_hash, Some(info))
             }
-            ExecuteMsg::RefillTaskBalance { task_hash } => self.refill_task(deps, info, task_hash),
-            ExecuteMsg::RefillTaskCw20Balance {
+            GenericExecuteMsg::RefillTaskBalance { task_hash } => {
+                self.refill_task(deps, info, task_hash)
+            }
+            GenericExecuteMsg::RefillTaskCw20Balance {
                 task_hash,
                 cw20_coins,
             } => self.refill_task_cw20(deps, info, task_hash, cw20_coins),
-            ExecuteMsg::ProxyCall {
+            GenericExecuteMsg::ProxyCall {
                 task_hash: Some(task_hash),
             } => self.proxy_call_with_queries(deps, info, env, task_hash),
-            ExecuteMsg::ProxyCall { task_hash: None } => self.proxy_call(deps, info, env),
-            ExecuteMsg::Receive(msg) => self.receive_cw20(deps, info, msg),
-            ExecuteMsg::WithdrawWalletBalance {
+            GenericExecuteMsg::ProxyCall { task_hash: None } => self.proxy_call(deps, info, env),
+            GenericExecuteMsg::Receive(msg) => self.receive_cw20(deps, info, msg),
+            GenericExecuteMsg::DepositWalletBalance {} => self.deposit_wallet_balance(deps, info),
+            GenericExecuteMsg::WithdrawWalletBalance {
                 cw20_amounts: cw20_balances,
-            } => self.withdraw_wallet_balances(deps, info, cw20_balances),
-            ExecuteMsg::Tick {} => self.tick(deps, env),
+                native_amounts,
+            } => self.withdraw_wallet_balances(deps, info, cw20_balances, native_amounts),
+            GenericExecuteMsg::Tick {} => self.tick(deps, env),
+            GenericExecuteMsg::MigrateTasks { limit } => self.migrate_tasks(deps, limit),
+            GenericExecuteMsg::PruneTaskHistory { task_hashes } => {
+                self.prune_task_history(deps, info, task_hashes)
+            }
         }
     }
 
-    pub fn query(&mut self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    pub fn query(&mut self, deps: Deps, env: Env, msg: GenericQueryMsg<T>) -> StdResult<Binary> {
         match msg {
-            QueryMsg::GetConfig {} => to_binary(&self.query_config(deps)?),
-            QueryMsg::GetBalances {} => to_binary(&self.query_balances(deps)?),
+            GenericQueryMsg::GetConfig {} => to_binary(&self.query_config(deps)?),
+            GenericQueryMsg::GetBalances {} => to_binary(&self.query_balances(deps)?),
 
-            QueryMsg::GetAgent { account_id } => {
+            GenericQueryMsg::GetAgent { account_id } => {
                 to_binary(&self.query_get_agent(deps, env, account_id)?)
             }
-            QueryMsg::GetAgentIds {} => to_binary(&self.query_get_agent_ids(deps)?),
-            QueryMsg::GetAgentTasks { account_id } => {
+            GenericQueryMsg::GetAgentIds { start_after, limit } => {
+                to_binary(&self.query_get_agent_ids(deps, start_after, limit)?)
+            }
+            GenericQueryMsg::GetAgentTasks { account_id } => {
                 to_binary(&self.query_get_agent_tasks(deps, env, account_id)?)
             }
 
-            QueryMsg::GetTasks { from_index, limit } => {
-                to_binary(&self.query_get_tasks(deps, from_index, limit)?)
+            GenericQueryMsg::GetTasks { start_after, limit } => {
+                to_binary(&self.query_get_tasks(deps, start_after, limit)?)
             }
-            QueryMsg::GetTasksWithQueries { from_index, limit } => {
-                to_binary(&self.query_get_tasks_with_queries(deps, from_index, limit)?)
+            GenericQueryMsg::GetTasksWithQueries { start_after, limit } => {
+                to_binary(&self.query_get_tasks_with_queries(deps, start_after, limit)?)
             }
-            QueryMsg::GetTasksByOwner { owner_id } => {
-                to_binary(&self.query_get_tasks_by_owner(deps, owner_id)?)
+            GenericQueryMsg::GetTasksByOwner {
+                owner_id,
+                start_after,
+                limit,
+            } => to_binary(&self.query_get_tasks_by_owner(deps, owner_id, start_after, limit)?),
+            GenericQueryMsg::GetTasksByTarget {
+                address,
+                start_after,
+                limit,
+            } => to_binary(&self.query_get_tasks_by_target(deps, address, start_after, limit)?),
+            GenericQueryMsg::GetTask { task_hash } => {
+                to_binary(&self.query_get_task(deps, task_hash)?)
             }
-            QueryMsg::GetTask { task_hash } => to_binary(&self.query_get_task(deps, task_hash)?),
-            QueryMsg::ValidateInterval { interval } => {
+            GenericQueryMsg::GetTaskDependencies {
+                owner_id,
+                start_after,
+                limit,
+            } => {
+                to_binary(&self.query_get_task_dependencies(deps, owner_id, start_after, limit)?)
+            }
+            GenericQueryMsg::GetTaskHistory { task_hash } => {
+                to_binary(&self.query_get_task_history(deps, task_hash)?)
+            }
+            GenericQueryMsg::ValidateInterval { interval } => {
                 to_binary(&self.query_validate_interval(interval)?)
             }
-            QueryMsg::GetSlotHashes { slot } => to_binary(&self.query_slot_tasks(deps, slot)?),
-            QueryMsg::GetSlotIds {} => to_binary(&self.query_slot_ids(deps)?),
-            QueryMsg::GetWalletBalances { wallet } => {
+            GenericQueryMsg::PreviewSchedule {
+                interval,
+                boundary,
+                count,
+            } => to_binary(&self.query_preview_schedule(deps, env, interval, boundary, count)?),
+            GenericQueryMsg::GetSlotHashes { slot } => {
+                to_binary(&self.query_slot_tasks(deps, slot)?)
+            }
+            GenericQueryMsg::GetSlotIds {} => to_binary(&self.query_slot_ids(deps)?),
+            GenericQueryMsg::GetWalletBalances { wallet } => {
                 to_binary(&self.query_wallet_balances(deps, wallet)?)
             }
 
-            QueryMsg::GetTaskHash { task } => to_binary(&self.query_get_task_hash(deps, *task)?),
+            GenericQueryMsg::GetTaskHash { task, owner_id } => {
+                to_binary(&self.query_get_task_hash(deps, *task, owner_id)?)
+            }
+            GenericQueryMsg::SimulateTask { task, owner_id } => {
+                to_binary(&self.query_simulate_task(deps, env, *task, owner_id)?)
+            }
         }
     }
 
-    pub fn reply(&self, deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    pub fn migrate(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        _msg: MigrateMsg,
+    ) -> Result<Response<T>, ContractError> {
+        let version = get_contract_version(deps.storage)?;
+        if version.contract != CONTRACT_NAME {
+            return Err(ContractError::CustomError {
+                val: format!("Can't migrate from {}", version.contract),
+            });
+        }
+        if version.version == CONTRACT_VERSION {
+            return Err(ContractError::CustomError {
+                val: "Contract already migrated".to_string(),
+            });
+        }
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        // Stored tasks are keyed by the hashes of the older encoding and miss the newer indexes,
+        // they get migrated in pages by `MigrateTasks` while the contract is paused
+        let hash_version = self.task_hash_version.may_load(deps.storage)?;
+        let migrating = self.task_migration.may_load(deps.storage)?.is_some();
+        if hash_version.as_deref() != Some(TASK_HASH_VERSION) && !migrating {
+            let mut cfg = self.config.load(deps.storage)?;
+            self.task_migration.save(
+                deps.storage,
+                &TaskMigration {
+                    paused: cfg.paused,
+                    stage: TaskMigrationStage::Tasks {
+                        with_queries: false,
+                        start_after: None,
+                    },
+                },
+            )?;
+            cfg.paused = true;
+            self.config.save(deps.storage, &cfg)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "migrate")
+            .add_attribute("version", CONTRACT_VERSION)
+            .add_attribute(
+                "task_migration",
+                self.task_migration
+                    .may_load(deps.storage)?
+                    .is_some()
+                    .to_string(),
+            ))
+    }
+
+    pub fn reply(&self, deps: DepsMut, env: Env, msg: Reply) -> Result<Response<T>, ContractError> {
+        // Failed task callback shouldn't revert the task execution
+        if msg.id == TASK_CALLBACK_REPLY_ID {
+            let failure = msg.result.into_result().err().unwrap_or_default();
+            return Ok(Response::new()
+                .add_attribute("reply", "task_callback")
+                .add_attribute("task_callback_failure", failure));
+        }
+
         // Route the next fns with the reply queue id meta
         let queue_item = self
             .reply_queue
@@ -3,19 +3,27 @@ use crate::state::{Config, QueueItem};
 // use cosmwasm_std::StdError;
 // use thiserror::Error;
 
+use crate::contract::TASK_CALLBACK_REPLY_ID;
 use crate::ContractError::AgentNotRegistered;
 use crate::{ContractError, CwCroncat};
 use cosmwasm_std::{
-    coin, to_binary, Addr, Api, BankMsg, Coin, CosmosMsg, Env, StdResult, Storage, SubMsg, WasmMsg,
+    coin, to_binary, Addr, Api, BankMsg, CosmosMsg, CustomMsg, Env, IbcMsg, IbcTimeout,
+    QuerierWrapper, StdError, StdResult, Storage, SubMsg, WasmMsg,
+};
+use cw20::{Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use cw_croncat_core::actions::AllowanceUsage;
+use cw_croncat_core::msg::{
+    CroncatTaskCallback, ExecuteMsg, GasPriceOracleQueryMsg, GasPriceOracleResponse,
+};
+use cw_croncat_core::types::{
+    gas_amount_with_agent_fee, Action, ActionFailure, AgentStatus, FeeToken, FeeTokenRate,
+    GasPrice, GasPriceSource, GenericTask, TASK_CALLBACK_GAS_LIMIT,
 };
-use cw20::{Cw20CoinVerified, Cw20ExecuteMsg};
-use cw_croncat_core::msg::ExecuteMsg;
-use cw_croncat_core::traits::{BalancesOperations, FindAndMutate};
-use cw_croncat_core::types::{gas_amount_with_agent_fee, AgentStatus};
 pub use cw_croncat_core::types::{GenericBalance, Task};
 //use regex::Regex;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::cmp;
 use std::ops::Div;
 //use std::str::FromStr;
@@ -39,13 +47,13 @@ pub(crate) fn vect_difference<T: std::clone::Clone + std::cmp::PartialEq>(
 // }
 
 // Helper to distribute funds/tokens
-pub(crate) fn send_tokens(
+pub(crate) fn send_tokens<T>(
     to: &Addr,
     balance: &GenericBalance,
-) -> StdResult<(Vec<SubMsg>, GenericBalance)> {
+) -> StdResult<(Vec<SubMsg<T>>, GenericBalance)> {
     let native_balance = &balance.native;
     let mut coins: GenericBalance = GenericBalance::default();
-    let mut msgs: Vec<SubMsg> = if native_balance.is_empty() {
+    let mut msgs: Vec<SubMsg<T>> = if native_balance.is_empty() {
         vec![]
     } else {
         coins.native = native_balance.to_vec();
@@ -76,6 +84,43 @@ pub(crate) fn send_tokens(
     Ok((msgs, coins))
 }
 
+/// Page size of the list queries, at least one item and at most the configured limit
+/// An empty page would end the paging with the next cursor unset
+pub(crate) fn page_limit(limit: Option<u64>, max_limit: u64) -> usize {
+    limit.unwrap_or(max_limit).min(max_limit).max(1) as usize
+}
+
+/// Truncates the page, queried with one extra item, to `limit` items
+/// Returns the cursor of the next page if there are more items
+pub(crate) fn next_cursor<T>(
+    items: &mut Vec<T>,
+    limit: usize,
+    cursor: impl Fn(&T) -> String,
+) -> Option<String> {
+    if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(cursor)
+    } else {
+        None
+    }
+}
+
+/// Validate the token addresses of the cw20 coins
+pub(crate) fn validate_cw20_coins(
+    api: &dyn Api,
+    coins: &[Cw20Coin],
+) -> StdResult<Vec<Cw20CoinVerified>> {
+    coins
+        .iter()
+        .map(|coin| {
+            Ok(Cw20CoinVerified {
+                address: api.addr_validate(&coin.address)?,
+                amount: coin.amount,
+            })
+        })
+        .collect()
+}
+
 /// has_cw_coins returns true if the list of CW20 coins has at least the required amount
 pub(crate) fn has_cw_coins(coins: &[Cw20CoinVerified], required: &Cw20CoinVerified) -> bool {
     coins
@@ -85,7 +130,10 @@ pub(crate) fn has_cw_coins(coins: &[Cw20CoinVerified], required: &Cw20CoinVerifi
         .unwrap_or(false)
 }
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     pub fn get_agent_status(
         &self,
         storage: &dyn Storage,
@@ -191,7 +239,7 @@ impl<'a> CwCroncat<'a> {
         api: &dyn Api,
         queue_item: QueueItem,
         ok: bool,
-    ) -> Result<Task, ContractError> {
+    ) -> Result<GenericTask<T>, ContractError> {
         let task_hash = queue_item.task_hash.unwrap();
         let mut task = self.get_task_by_hash(storage, &task_hash)?;
         if ok {
@@ -200,13 +248,13 @@ impl<'a> CwCroncat<'a> {
             let action = &task.actions[action_idx as usize];
 
             // update task balances and contract balances
-            if let Some(sent) = action.bank_sent() {
-                task.total_deposit.native.checked_sub_coins(sent)?;
-                config.available_balance.checked_sub_native(sent)?;
-            } else if let Some(sent) = action.cw20_sent(api) {
-                task.total_deposit.cw20.find_checked_sub(&sent)?;
-                config.available_balance.cw20.find_checked_sub(&sent)?;
-            };
+            // Everything reserved for the action leaves the contract, delegations included:
+            // they were reserved on creation but never charged, overstating the task deposit
+            let usage = action.usage(api)?;
+            task.total_deposit.checked_sub_generic(&usage.deposit)?;
+            config
+                .available_balance
+                .checked_sub_generic(&usage.deposit)?;
             self.config.save(storage, &config)?;
             if task.with_queries() {
                 self.tasks_with_queries.save(storage, &task_hash, &task)?;
@@ -218,24 +266,182 @@ impl<'a> CwCroncat<'a> {
     }
 }
 
+/// Gas price of this execution, read from the oracle if the config has one
+/// The oracle price is clamped to its bounds, falling back to the fixed `gas_price` if the oracle
+/// can't answer, then capped by the task `max_gas_price`
+pub(crate) fn current_gas_price(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    max_gas_price: Option<u64>,
+) -> GasPrice {
+    let numerator = match &cfg.gas_price_source {
+        GasPriceSource::Fixed => cfg.gas_price.numerator,
+        GasPriceSource::Oracle {
+            contract_addr,
+            min_numerator,
+            max_numerator,
+        } => querier
+            .query_wasm_smart(contract_addr, &GasPriceOracleQueryMsg::GasPrice {})
+            .map_or(cfg.gas_price.numerator, |res: GasPriceOracleResponse| {
+                res.numerator
+            })
+            .clamp(*min_numerator, *max_numerator),
+    };
+    GasPrice {
+        numerator: max_gas_price.map_or(numerator, |max| numerator.min(max)),
+        ..cfg.gas_price.clone()
+    }
+}
+
+/// Highest gas price the task can pay, reserved in the task deposit for each execution
+pub(crate) fn reserved_gas_price(
+    cfg: &Config,
+    max_gas_price: Option<u64>,
+) -> Result<GasPrice, ContractError> {
+    let (min_numerator, max_numerator) = cfg.gas_price_source.numerator_bounds(&cfg.gas_price);
+    let numerator = match max_gas_price {
+        Some(max) if max < min_numerator => {
+            return Err(ContractError::CustomError {
+                val: "Max gas price too low".to_string(),
+            });
+        }
+        Some(max) => max.min(max_numerator),
+        None => max_numerator,
+    };
+    Ok(GasPrice {
+        numerator,
+        ..cfg.gas_price.clone()
+    })
+}
+
+/// Agent fee for the gas of one execution, paid in the fee token or the native denom
+/// Fee token removed from the registry has no rate, tasks paying in it get ended instead
+pub(crate) fn agent_fee_price(
+    cfg: &Config,
+    gas_price: &GasPrice,
+    fee_token: Option<&FeeToken>,
+    gas_amount: u64,
+) -> Result<GenericBalance, ContractError> {
+    let gas_amount_with_agent_fee = gas_amount_with_agent_fee(gas_amount, cfg.agent_fee)?;
+    let price_amount = gas_price.calculate(gas_amount_with_agent_fee)?;
+    match fee_token {
+        Some(token) => {
+            let rate = fee_token_rate(cfg, token).ok_or_else(|| ContractError::CustomError {
+                val: "Fee token not allowed".to_string(),
+            })?;
+            Ok(rate.fee(price_amount)?)
+        }
+        None => Ok(GenericBalance {
+            native: vec![coin(price_amount, &cfg.native_denom)],
+            cw20: vec![],
+        }),
+    }
+}
+
+/// Registry rate of the fee token
+pub(crate) fn fee_token_rate<'c>(cfg: &'c Config, token: &FeeToken) -> Option<&'c FeeTokenRate> {
+    cfg.fee_tokens.iter().find(|rate| rate.token == *token)
+}
+
+/// Whether the task owner owns every NFT the actions move, a burned NFT has no owner
+/// Checked on execution as well, the NFT could be transferred after the task creation
+pub(crate) fn owns_nfts<T>(
+    querier: &QuerierWrapper,
+    api: &dyn Api,
+    owner_id: &Addr,
+    actions: &[Action<T>],
+) -> Result<bool, ContractError> {
+    for action in actions {
+        for allowance in action.usage(api)?.allowances {
+            if let AllowanceUsage::Cw721 {
+                contract_addr,
+                token_id,
+            } = allowance
+            {
+                let nft_owner: StdResult<OwnerOfResponse> = querier.query_wasm_smart(
+                    contract_addr,
+                    &Cw721QueryMsg::OwnerOf {
+                        token_id,
+                        include_expired: None,
+                    },
+                );
+                match nft_owner {
+                    Ok(nft_owner) if nft_owner.owner == owner_id.as_str() => (),
+                    Ok(_) => return Ok(false),
+                    Err(err) if is_not_found(&err) => return Ok(false),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// cw721 contracts answer the queries about a burned NFT with a not found error,
+/// the querier passes it on as the message of a generic error
+fn is_not_found(err: &StdError) -> bool {
+    match err {
+        StdError::NotFound { .. } => true,
+        StdError::GenericErr { msg } => msg.ends_with(" not found"),
+        _ => false,
+    }
+}
+
 /// Generate submsgs for this proxy call and the price for it
-pub(crate) fn proxy_call_submsgs_price(
-    task: &Task,
+pub(crate) fn proxy_call_submsgs_price<T: CustomMsg>(
+    task: &GenericTask<T>,
     cfg: Config,
+    gas_price: &GasPrice,
+    env: &Env,
     next_idx: u64,
-) -> Result<(Vec<SubMsg>, Coin), ContractError> {
-    let (sub_msgs, gas_total) = task.get_submsgs_with_total_gas(
+) -> Result<(Vec<SubMsg<T>>, GenericBalance), ContractError> {
+    let (mut sub_msgs, gas_total) = task.get_submsgs_with_total_gas(
         cfg.gas_base_fee,
         cfg.gas_action_fee,
         cfg.gas_query_fee,
         cfg.gas_wasm_query_fee,
         next_idx,
     )?;
-    let gas_amount_with_agent_fee = gas_amount_with_agent_fee(gas_total, cfg.agent_fee)?;
-    let price_amount = cfg.gas_price.calculate(gas_amount_with_agent_fee)?;
-    let price = coin(price_amount, cfg.native_denom);
+    // Recurring transfers can't keep an absolute timeout, so it's relative to the execution block
+    let ibc_timeout =
+        IbcTimeout::with_timestamp(env.block.time.plus_seconds(cfg.ibc_transfer_timeout));
+    for sub_msg in sub_msgs.iter_mut() {
+        if let CosmosMsg::Ibc(IbcMsg::Transfer { timeout, .. }) = &mut sub_msg.msg {
+            *timeout = ibc_timeout.clone();
+        }
+    }
+    let price = agent_fee_price(&cfg, gas_price, task.fee_token.as_ref(), gas_total)?;
     Ok((sub_msgs, price))
 }
+
+/// Generate the callback submsg notifying the task `callback_addr` about the execution
+/// The callback replies on error only, so its failure doesn't revert the task execution
+pub(crate) fn task_callback_submsgs<T>(
+    callback_addr: Option<&Addr>,
+    task_hash: &str,
+    executed_actions: u64,
+    failures: &[ActionFailure],
+    task_ended: bool,
+) -> StdResult<Vec<SubMsg<T>>> {
+    let callback_addr = match callback_addr {
+        Some(addr) => addr,
+        None => return Ok(vec![]),
+    };
+    let msg = WasmMsg::Execute {
+        contract_addr: callback_addr.to_string(),
+        msg: CroncatTaskCallback {
+            task_hash: task_hash.to_owned(),
+            executed_actions,
+            failures: failures.to_vec(),
+            task_ended,
+        }
+        .into_binary()?,
+        funds: vec![],
+    };
+    Ok(vec![
+        SubMsg::reply_on_error(msg, TASK_CALLBACK_REPLY_ID).with_gas_limit(TASK_CALLBACK_GAS_LIMIT)
+    ])
+}
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
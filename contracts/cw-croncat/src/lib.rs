@@ -16,9 +16,11 @@ mod tests;
 
 pub use crate::error::ContractError;
 pub use crate::state::CwCroncat;
+#[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
+#[cfg(not(feature = "library"))]
 use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult};
-pub use cw_croncat_core::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+pub use cw_croncat_core::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 #[cfg(not(feature = "library"))]
 pub mod entry {
@@ -53,6 +55,12 @@ pub mod entry {
         s.query(deps, env, msg)
     }
 
+    #[entry_point]
+    pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        let s = CwCroncat::default();
+        s.migrate(deps, env, msg)
+    }
+
     #[entry_point]
     pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
         let s = CwCroncat::default();
@@ -1,26 +1,39 @@
 use crate::balancer::Balancer;
+use crate::contract::TASK_HISTORY_SIZE;
 use crate::error::ContractError;
-use crate::helpers::proxy_call_submsgs_price;
+use crate::helpers::{
+    current_gas_price, fee_token_rate, owns_nfts, proxy_call_submsgs_price, task_callback_submsgs,
+};
 use crate::state::{Config, CwCroncat, QueueItem, TaskInfo};
 use cosmwasm_std::{
-    from_binary, Addr, Attribute, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
-    Storage,
+    from_binary, Addr, Attribute, CustomMsg, Deps, DepsMut, Env, MessageInfo, Order, Reply,
+    Response, StdResult, Storage,
+};
+use cw_croncat_core::events::{
+    AgentEjected, CroncatEvent, TaskEndReason, TaskEnded, TaskExecuted, TaskRefilled,
+    TaskRescheduled,
+};
+use cw_croncat_core::traits::Intervals;
+use cw_croncat_core::types::{
+    Agent, AgentStatus, ExecutionRecord, GenericTask, Interval, SlotType,
 };
-use cw_croncat_core::traits::{FindAndMutate, Intervals};
-use cw_croncat_core::types::{Agent, Interval, SlotType, Task};
 use cw_rules_core::msg::{QueryConstruct, QueryConstructResponse};
+use serde::de::DeserializeOwned;
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     /// Executes a task based on the current task slot
     /// Computes whether a task should continue further or not
     /// Makes a cross-contract call with the task configuration
     /// Called directly by a registered agent
     pub fn proxy_call(
         &mut self,
-        deps: DepsMut,
+        mut deps: DepsMut,
         info: MessageInfo,
         env: Env,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         self.check_ready_for_proxy_call(deps.as_ref(), &info)?;
         let agent = self.check_agent(deps.as_ref().storage, &info)?;
 
@@ -87,11 +100,16 @@ impl<'a> CwCroncat<'a> {
         // Add submessages for all actions
         let next_idx = self.rq_next_id(deps.storage)?;
         let mut task = self.tasks.load(deps.storage, &hash)?;
+        if let Some(resp) = self.end_invalid_task(deps.branch(), &cfg, &task)? {
+            return Ok(resp.add_attribute("agent", info.sender));
+        }
         let mut agent = agent;
         agent.update(env.block.height);
-        let (sub_msgs, fee_price) = proxy_call_submsgs_price(&task, cfg.clone(), next_idx)?;
-        task.total_deposit.native.find_checked_sub(&fee_price)?;
-        agent.balance.native.find_checked_add(&fee_price)?;
+        let gas_price = current_gas_price(&deps.querier, &cfg, task.max_gas_price);
+        let (sub_msgs, fee_price) =
+            proxy_call_submsgs_price(&task, cfg.clone(), &gas_price, &env, next_idx)?;
+        task.total_deposit.checked_sub_generic(&fee_price)?;
+        agent.balance.checked_add_generic(&fee_price)?;
         self.tasks.save(deps.storage, &hash, &task)?;
         self.agents.save(deps.storage, &info.sender, &agent)?;
         // Keep track for later scheduling
@@ -105,6 +123,8 @@ impl<'a> CwCroncat<'a> {
                 task_is_extra: Some(balancer_result.has_any_slot_extra_tasks(slot_type)),
                 agent_id: Some(info.sender.clone()),
                 failure: None,
+                failures: vec![],
+                fee: fee_price,
             },
         )?;
 
@@ -126,11 +146,11 @@ impl<'a> CwCroncat<'a> {
     /// Called directly by a registered agent
     pub fn proxy_call_with_queries(
         &mut self,
-        deps: DepsMut,
+        mut deps: DepsMut,
         info: MessageInfo,
         env: Env,
         task_hash: String,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         self.check_ready_for_proxy_call(deps.as_ref(), &info)?;
         let agent = self.check_agent(deps.as_ref().storage, &info)?;
         let hash = task_hash.as_bytes();
@@ -140,6 +160,11 @@ impl<'a> CwCroncat<'a> {
             .tasks_with_queries
             .may_load(deps.storage, task_hash.as_bytes())?;
         let mut task = some_task.ok_or(ContractError::NoTaskFound {})?;
+        if task.paused {
+            return Err(ContractError::CustomError {
+                val: "Task paused".to_string(),
+            });
+        }
 
         let task_ready =
             self.task_with_query_ready(task.interval.clone(), deps.as_ref(), hash, &env)?;
@@ -148,6 +173,11 @@ impl<'a> CwCroncat<'a> {
                 val: "Task is not ready".to_string(),
             });
         }
+        if let Some(resp) = self.end_invalid_task(deps.branch(), &cfg, &task)? {
+            return Ok(resp
+                .add_attribute("agent", info.sender)
+                .add_attribute("task_with_queries", true.to_string()));
+        }
         // self.check_bank_msg(deps.as_ref(), &info, &env, &task)?;
         let queries = if let Some(queries) = task.queries.clone() {
             queries
@@ -168,6 +198,7 @@ impl<'a> CwCroncat<'a> {
 
         // Add submessages for all actions
         let next_idx = self.rq_next_id(deps.storage)?;
+        let gas_price = current_gas_price(&deps.querier, &cfg, task.max_gas_price);
         // This may be different to the one we keep in the storage
         // due to the insertable messages
         let (sub_msgs, fee_price) = match task
@@ -178,11 +209,20 @@ impl<'a> CwCroncat<'a> {
                 queries_res.data,
             )
             .map_err(Into::into)
-            .and(proxy_call_submsgs_price(&task, cfg, next_idx))
-        {
+            .and(proxy_call_submsgs_price(
+                &task, cfg, &gas_price, &env, next_idx,
+            )) {
             Ok((sub_msgs, fee_price)) => (sub_msgs, fee_price),
             Err(err) => {
-                let resp = self.remove_task(deps.storage, &task_hash, None)?;
+                let ended = TaskEnded {
+                    task_hash: task_hash.clone(),
+                    owner: task.owner_id.clone(),
+                    reason: TaskEndReason::InvalidActions,
+                    error: Some(err.to_string()),
+                };
+                let mut resp = self.remove_task(deps.storage, &task_hash, None)?;
+                // Ended goes before the removal of the task
+                resp.events.insert(0, ended.into());
                 return Ok(resp
                     .add_attribute("method", "proxy_call")
                     .add_attribute("agent", info.sender)
@@ -194,11 +234,11 @@ impl<'a> CwCroncat<'a> {
 
         let mut agent = agent;
         agent.update(env.block.height);
-        agent.balance.native.find_checked_add(&fee_price)?;
+        agent.balance.checked_add_generic(&fee_price)?;
         self.tasks_with_queries
             .update(deps.storage, hash, |task| -> Result<_, ContractError> {
                 let mut task = task.ok_or(ContractError::NoTaskFound {})?;
-                task.total_deposit.native.find_checked_sub(&fee_price)?;
+                task.total_deposit.checked_sub_generic(&fee_price)?;
                 Ok(task)
             })?;
         self.agents.save(deps.storage, &info.sender, &agent)?;
@@ -212,6 +252,8 @@ impl<'a> CwCroncat<'a> {
                 task_is_extra: Some(false),
                 agent_id: Some(info.sender.clone()),
                 failure: None,
+                failures: vec![],
+                fee: fee_price,
             },
         )?;
         // TODO: Add supported msgs if not a SubMessage?
@@ -225,6 +267,39 @@ impl<'a> CwCroncat<'a> {
         Ok(final_res)
     }
 
+    /// Ends the task that can't be executed anymore: its fee token got removed
+    /// from the registry, or the owner no longer owns every NFT its actions move
+    fn end_invalid_task(
+        &self,
+        deps: DepsMut,
+        cfg: &Config,
+        task: &GenericTask<T>,
+    ) -> Result<Option<Response<T>>, ContractError> {
+        let reason = match &task.fee_token {
+            Some(token) if fee_token_rate(cfg, token).is_none() => TaskEndReason::FeeTokenRemoved,
+            // Other query errors only fail this execution
+            _ if !owns_nfts(&deps.querier, deps.api, &task.owner_id, &task.actions)? => {
+                TaskEndReason::NftOwnerChanged
+            }
+            _ => return Ok(None),
+        };
+        let task_hash = task.to_hash(&cfg.chain_name);
+        let ended = TaskEnded {
+            task_hash: task_hash.clone(),
+            owner: task.owner_id.clone(),
+            reason,
+            error: None,
+        };
+        let mut resp = self.remove_task(deps.storage, &task_hash, None)?;
+        // Ended goes before the removal of the task
+        resp.events.insert(0, ended.into());
+        Ok(Some(
+            resp.add_attribute("method", "proxy_call")
+                .add_attribute("task_hash", task_hash)
+                .add_attribute("task_removed_without_execution", format!("{:?}", reason)),
+        ))
+    }
+
     /// Check that this task can be executed in current slot
     fn task_with_query_ready(
         &mut self,
@@ -234,7 +309,10 @@ impl<'a> CwCroncat<'a> {
         env: &Env,
     ) -> Result<bool, ContractError> {
         let task_ready = match task_interval {
-            Interval::Cron(_) => {
+            Interval::Cron(_)
+            | Interval::CronTz { .. }
+            | Interval::At(_)
+            | Interval::Every { .. } => {
                 let block = self.time_map_queries.load(deps.storage, hash)?;
                 env.block.height >= block
             }
@@ -253,9 +331,9 @@ impl<'a> CwCroncat<'a> {
         deps: DepsMut,
         env: Env,
         _msg: Reply,
-        task: Task,
+        mut task: GenericTask<T>,
         queue_item: QueueItem,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         // TODO: How can we compute gas & fees paid on this txn?
         // let out_of_funds = call_total_balance > task.total_deposit;
         let agent_id = queue_item.agent_id.ok_or(ContractError::Unauthorized {})?;
@@ -274,16 +352,109 @@ impl<'a> CwCroncat<'a> {
             Response::new()
         };
 
+        task.executions = task.executions.saturating_add(1);
+        if queue_item.failure.is_some() {
+            task.failed_executions = task.failed_executions.saturating_add(1);
+        }
+        let record = ExecutionRecord {
+            execution: task.executions,
+            block_height: env.block.height,
+            time: env.block.time,
+            agent: agent_id.clone(),
+            fee: queue_item.fee.clone(),
+            executed_actions: queue_item.action_idx,
+            failures: queue_item.failures.clone(),
+            failure: queue_item.failure.clone(),
+        };
+        self.task_history.save(
+            deps.storage,
+            (task_hash.as_bytes(), record.execution % TASK_HISTORY_SIZE),
+            &record,
+        )?;
+        let executed = TaskExecuted {
+            task_hash: task_hash.clone(),
+            owner: task.owner_id.clone(),
+            agent: agent_id.clone(),
+            execution: record.execution,
+            executed_actions: record.executed_actions,
+            failed_actions: record.failures.len() as u64,
+            failure: record.failure,
+            fee_native: record.fee.native,
+            fee_cw20: record.fee.cw20,
+        };
+        let response = response.add_event(executed.into());
+
+        // Schedule tasks waiting for this one to complete
+        let triggered_tasks = self.trigger_dependent_tasks(
+            deps.storage,
+            &env,
+            task_hash.as_bytes(),
+            queue_item.failure.is_none(),
+            cfg.slot_granularity_time,
+        )?;
+        let response = response
+            .add_attributes(
+                triggered_tasks
+                    .iter()
+                    .map(|triggered| ("triggered_task", triggered.task_hash.clone())),
+            )
+            .add_events(triggered_tasks.into_iter().map(Into::into));
+
         // if non-recurring, exit
-        if task.interval == Interval::Once
-            || (task.stop_on_fail && queue_item.failure.is_some())
-            || task.verify_enough_balances(false).is_err()
+        let end_reason = if matches!(task.interval, Interval::Once | Interval::At(_)) {
+            Some(TaskEndReason::Completed)
+        } else if task.remaining_executions() == Some(0) {
+            // Reached the execution limit
+            Some(TaskEndReason::ExecutionLimit)
+        } else if task.stop_on_fail && queue_item.failure.is_some() {
+            Some(TaskEndReason::Failure)
+        } else if next_id == 0 {
             // If the next interval comes back 0, then this task should not schedule again
-            || next_id == 0
-        // proxy_call_with_rules makes it fail if rules aren't met
-        {
+            Some(TaskEndReason::ScheduleEnded)
+        } else {
+            None
+        };
+        // Keep the task alive with the owner wallet balances
+        let refill = if end_reason.is_none() {
+            self.auto_refill_task(deps.storage, &mut task)?
+        } else {
+            None
+        };
+        let response = if let Some(refill) = refill {
+            let refilled = TaskRefilled {
+                task_hash: task_hash.clone(),
+                owner: task.owner_id.clone(),
+                native: refill.native,
+                cw20: refill.cw20,
+                auto_refill: true,
+            };
+            response
+                .add_attribute("auto_refill", "true")
+                .add_event(refilled.into())
+        } else {
+            response
+        };
+        let end_reason = match end_reason {
+            // proxy_call_with_rules makes it fail if rules aren't met
+            None if task.verify_enough_balances(false).is_err() => Some(TaskEndReason::OutOfFunds),
+            end_reason => end_reason,
+        };
+        if let Some(reason) = end_reason {
+            let callback = task_callback_submsgs(
+                task.callback_addr.as_ref(),
+                &task_hash,
+                queue_item.action_idx,
+                &queue_item.failures,
+                true,
+            )?;
             // Process task exit, if no future task can execute
             // Task has been removed, complete and rebalance internal balancer
+            let ended = TaskEnded {
+                task_hash: task_hash.clone(),
+                owner: task.owner_id.clone(),
+                reason,
+                error: queue_item.failure,
+            };
             let task_info = TaskInfo {
                 task,
                 task_hash: task_hash.as_bytes().to_vec(),
@@ -298,70 +469,83 @@ impl<'a> CwCroncat<'a> {
                 .add_attribute("ended_task", task_hash)
                 .add_attributes(resp.attributes)
                 .add_submessages(resp.messages)
+                .add_submessages(callback)
+                .add_event(ended.into())
                 .add_events(resp.events))
         } else {
-            self.reschedule_task(
-                task.with_queries(),
-                slot_kind,
-                deps.storage,
-                task_hash,
-                next_id,
+            let callback = task_callback_submsgs(
+                task.callback_addr.as_ref(),
+                &task_hash,
+                queue_item.action_idx,
+                &queue_item.failures,
+                false,
             )?;
+            let response = response.add_submessages(callback);
+            let hash = task_hash.as_bytes();
+            if task.with_queries() {
+                self.tasks_with_queries.save(deps.storage, hash, &task)?;
+            } else {
+                self.tasks.save(deps.storage, hash, &task)?;
+            }
+            // Triggered task waits for the next completion of the parent task
+            if task.trigger.is_some() {
+                return Ok(response.add_attribute("method", "proxy_callback"));
+            }
+            self.schedule_task(deps.storage, hash, task.with_queries(), next_id, slot_kind)?;
+            let rescheduled = TaskRescheduled {
+                task_hash: task_hash.clone(),
+                owner: task.owner_id,
+                slot_id: next_id,
+                slot_kind,
+            };
 
             Ok(response
                 .add_attribute("method", "proxy_callback")
                 .add_attribute("slot_id", next_id.to_string())
-                .add_attribute("slot_kind", format!("{:?}", slot_kind)))
+                .add_attribute("slot_kind", format!("{:?}", slot_kind))
+                .add_event(rescheduled.into()))
         }
     }
 
-    /// Update time or block of next time this task should be executed
-    fn reschedule_task(
+    /// Put the tasks triggered by the completion of the parent task into the next slot
+    fn trigger_dependent_tasks(
         &self,
-        task_with_queries: bool,
-        slot_kind: SlotType,
         storage: &mut dyn Storage,
-        task_hash: String,
-        next_id: u64,
-    ) -> Result<(), ContractError> {
-        if task_with_queries {
-            // Based on slot kind, put into block or cron slots
-            match slot_kind {
-                SlotType::Block => {
-                    self.block_map_queries
-                        .save(storage, task_hash.as_bytes(), &next_id)?;
-                }
-                SlotType::Cron => {
-                    self.time_map_queries
-                        .save(storage, task_hash.as_bytes(), &next_id)?;
-                }
+        env: &Env,
+        parent_hash: &[u8],
+        success: bool,
+        slot_granularity_time: u64,
+    ) -> Result<Vec<TaskRescheduled>, ContractError> {
+        let dependents = self
+            .task_dependents
+            .prefix(parent_hash)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut triggered_tasks = Vec::with_capacity(dependents.len());
+        for (hash, condition) in dependents {
+            if !condition.is_met(success) {
+                continue;
             }
-        } else {
-            // Get previous task hashes in slot, add as needed
-            let update_vec_data = |d: Option<Vec<Vec<u8>>>| -> StdResult<Vec<Vec<u8>>> {
-                match d {
-                    // has some data, simply push new hash
-                    Some(data) => {
-                        let mut s = data;
-                        s.push(task_hash.into_bytes());
-                        Ok(s)
-                    }
-                    // No data, push new vec & hash
-                    None => Ok(vec![task_hash.into_bytes()]),
-                }
-            };
-
-            // Based on slot kind, put into block or cron slots
-            match slot_kind {
-                SlotType::Block => {
-                    self.block_slots.update(storage, next_id, update_vec_data)?;
-                }
-                SlotType::Cron => {
-                    self.time_slots.update(storage, next_id, update_vec_data)?;
-                }
+            let task = self.get_task_by_hash(storage, &hash)?;
+            // Still waits for the slot of an earlier completion of the parent task
+            if task.paused || self.is_scheduled(storage, &hash, task.with_queries())? {
+                continue;
             }
-        };
-        Ok(())
+            let (next_id, slot_kind) =
+                task.interval
+                    .next(env, task.boundary, slot_granularity_time);
+            if next_id == 0 {
+                continue;
+            }
+            self.schedule_task(storage, &hash, task.with_queries(), next_id, slot_kind)?;
+            triggered_tasks.push(TaskRescheduled {
+                task_hash: String::from_utf8_lossy(&hash).to_string(),
+                owner: task.owner_id,
+                slot_id: next_id,
+                slot_kind,
+            });
+        }
+        Ok(triggered_tasks)
     }
 
     fn check_ready_for_proxy_call(
@@ -414,7 +598,7 @@ impl<'a> CwCroncat<'a> {
         &self,
         storage: &mut dyn Storage,
         env: Env,
-        task_info: &TaskInfo,
+        task_info: &TaskInfo<T>,
     ) -> Result<(), ContractError> {
         // let TaskInfo {
         //     task_hash, task, ..
@@ -449,21 +633,26 @@ impl<'a> CwCroncat<'a> {
 
     /// Helps manage and cleanup agents
     /// Deletes agents which missed more than agents_eject_threshold slot
-    pub fn tick(&mut self, deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    pub fn tick(&mut self, deps: DepsMut, env: Env) -> Result<Response<T>, ContractError> {
         let current_slot = env.block.height;
         let cfg = self.config.load(deps.storage)?;
         let mut attributes = vec![];
         let mut submessages = vec![];
+        let mut events = vec![];
 
         for agent_id in self.agent_active_queue.load(deps.storage)? {
             let agent = self.agents.load(deps.storage, &agent_id)?;
             if current_slot > agent.last_executed_slot + cfg.agents_eject_threshold {
-                let resp = self
-                    .unregister_agent(deps.storage, &agent_id, None)
-                    .unwrap_or_default();
-                // Save attributes and messages
-                attributes.extend_from_slice(&resp.attributes);
-                submessages.extend_from_slice(&resp.messages);
+                if let Ok(resp) = self.unregister_agent(deps.storage, &agent_id, None) {
+                    // Save attributes and messages
+                    attributes.extend_from_slice(&resp.attributes);
+                    submessages.extend_from_slice(&resp.messages);
+                    let ejected = AgentEjected {
+                        agent: agent_id,
+                        status: AgentStatus::Active,
+                    };
+                    events.push(ejected.into_event());
+                }
             }
         }
 
@@ -476,7 +665,8 @@ impl<'a> CwCroncat<'a> {
         let response = Response::new()
             .add_attribute("method", "tick")
             .add_attributes(attributes)
-            .add_submessages(submessages);
+            .add_submessages(submessages)
+            .add_events(events);
         Ok(response)
     }
 }
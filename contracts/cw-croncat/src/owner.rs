@@ -2,16 +2,24 @@ use crate::error::ContractError;
 use crate::helpers::has_cw_coins;
 use crate::state::{Config, CwCroncat};
 use cosmwasm_std::{
-    has_coins, to_binary, BankMsg, Coin, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    SubMsg, WasmMsg,
+    has_coins, to_binary, BankMsg, Coin, CustomMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, SubMsg, WasmMsg,
 };
 use cw20::{Balance, Cw20ExecuteMsg};
+use cw_croncat_core::events::SettingsChanged;
 use cw_croncat_core::msg::{
-    ExecuteMsg, GetBalancesResponse, GetConfigResponse, GetWalletBalancesResponse,
+    GenericExecuteMsg, GetBalancesResponse, GetConfigResponse, GetWalletBalancesResponse,
 };
 use cw_croncat_core::traits::FindAndMutate;
+use cw_croncat_core::types::{
+    is_valid_stargate_type_url, AdminAllowance, FeeToken, GasPriceSource,
+};
+use serde::de::DeserializeOwned;
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     pub(crate) fn query_config(&self, deps: Deps) -> StdResult<GetConfigResponse> {
         let c: Config = self.config.load(deps.storage)?;
         Ok(GetConfigResponse {
@@ -27,6 +35,12 @@ impl<'a> CwCroncat<'a> {
             gas_price: c.gas_price,
             proxy_callback_gas: c.proxy_callback_gas,
             slot_granularity_time: c.slot_granularity_time,
+            ibc_transfer_timeout: c.ibc_transfer_timeout,
+            gov_msgs_enabled: c.gov_msgs_enabled,
+            stargate_type_urls: c.stargate_type_urls,
+            admin_allowlist: c.admin_allowlist,
+            fee_tokens: c.fee_tokens,
+            gas_price_source: c.gas_price_source,
             cw_rules_addr: c.cw_rules_addr,
             agent_nomination_duration: c.agent_nomination_duration,
             gas_base_fee: c.gas_base_fee,
@@ -56,8 +70,10 @@ impl<'a> CwCroncat<'a> {
     ) -> StdResult<GetWalletBalancesResponse> {
         let addr = deps.api.addr_validate(&wallet)?;
         let balances = self.balances.may_load(deps.storage, &addr)?;
+        let native_balances = self.native_balances.may_load(deps.storage, &addr)?;
         Ok(GetWalletBalancesResponse {
             cw20_balances: balances.unwrap_or_default(),
+            native_balances: native_balances.unwrap_or_default(),
         })
     }
 
@@ -67,16 +83,17 @@ impl<'a> CwCroncat<'a> {
         &self,
         deps: DepsMut,
         info: MessageInfo,
-        payload: ExecuteMsg,
-    ) -> Result<Response, ContractError> {
+        payload: GenericExecuteMsg<T>,
+    ) -> Result<Response<T>, ContractError> {
         for coin in info.funds.iter() {
             if coin.amount.u128() > 0 {
                 return Err(ContractError::AttachedDeposit {});
             }
         }
         let api = deps.api;
+        let old_config: Config = self.config.load(deps.storage)?;
         match payload {
-            ExecuteMsg::UpdateSettings {
+            GenericExecuteMsg::UpdateSettings {
                 owner_id,
                 chain_name,
                 slot_granularity_time,
@@ -88,15 +105,75 @@ impl<'a> CwCroncat<'a> {
                 gas_wasm_query_fee,
                 gas_price,
                 proxy_callback_gas,
+                ibc_transfer_timeout,
+                gov_msgs_enabled,
+                stargate_type_urls,
+                admin_allowlist,
+                fee_tokens,
+                gas_price_source,
                 min_tasks_per_agent,
                 agents_eject_threshold,
                 // treasury_id,
             } => {
+                // Tasks are re-keyed by the chain name and can't execute before they are migrated
+                if (paused == Some(false) || chain_name.is_some())
+                    && self.task_migration.may_load(deps.storage)?.is_some()
+                {
+                    return Err(ContractError::CustomError {
+                        val: "Task migration in progress".to_string(),
+                    });
+                }
                 let owner_id = if let Some(addr) = owner_id {
                     Some(api.addr_validate(&addr)?)
                 } else {
                     None
                 };
+                let admin_allowlist = admin_allowlist
+                    .map(|allowlist| {
+                        allowlist
+                            .into_iter()
+                            .map(|allowance| {
+                                Ok(AdminAllowance {
+                                    contract_addr: api
+                                        .addr_validate(allowance.contract_addr.as_str())?,
+                                    task_owner: api.addr_validate(allowance.task_owner.as_str())?,
+                                })
+                            })
+                            .collect::<StdResult<Vec<_>>>()
+                    })
+                    .transpose()?;
+                if let Some(fee_tokens) = &fee_tokens {
+                    for rate in fee_tokens {
+                        if let FeeToken::Cw20(address) = &rate.token {
+                            api.addr_validate(address.as_str())?;
+                        }
+                        if !rate.is_valid() {
+                            return Err(ContractError::CustomError {
+                                val: "Fee token rate invalid".to_string(),
+                            });
+                        }
+                    }
+                }
+                if let Some(type_urls) = &stargate_type_urls {
+                    if !type_urls
+                        .iter()
+                        .all(|type_url| is_valid_stargate_type_url(type_url))
+                    {
+                        return Err(ContractError::CustomError {
+                            val: "Stargate type url not allowed".to_string(),
+                        });
+                    }
+                }
+                if let Some(gas_price_source) = &gas_price_source {
+                    if let GasPriceSource::Oracle { contract_addr, .. } = gas_price_source {
+                        api.addr_validate(contract_addr.as_str())?;
+                    }
+                    if !gas_price_source.is_valid() {
+                        return Err(ContractError::CustomError {
+                            val: "Gas price source invalid".to_string(),
+                        });
+                    }
+                }
                 self.config
                     .update(deps.storage, |old_config| -> Result<_, ContractError> {
                         if info.sender != old_config.owner_id {
@@ -132,6 +209,16 @@ impl<'a> CwCroncat<'a> {
                                 .unwrap_or(old_config.proxy_callback_gas),
                             slot_granularity_time: slot_granularity_time
                                 .unwrap_or(old_config.slot_granularity_time),
+                            ibc_transfer_timeout: ibc_transfer_timeout
+                                .unwrap_or(old_config.ibc_transfer_timeout),
+                            gov_msgs_enabled: gov_msgs_enabled
+                                .unwrap_or(old_config.gov_msgs_enabled),
+                            stargate_type_urls: stargate_type_urls
+                                .unwrap_or(old_config.stargate_type_urls),
+                            admin_allowlist: admin_allowlist.unwrap_or(old_config.admin_allowlist),
+                            fee_tokens: fee_tokens.unwrap_or(old_config.fee_tokens),
+                            gas_price_source: gas_price_source
+                                .unwrap_or(old_config.gas_price_source),
                             cw20_whitelist: old_config.cw20_whitelist,
                             native_denom: old_config.native_denom,
                             available_balance: old_config.available_balance,
@@ -144,7 +231,13 @@ impl<'a> CwCroncat<'a> {
             _ => unreachable!(),
         }
         let c: Config = self.config.load(deps.storage)?;
+        let changed = SettingsChanged {
+            owner_id: c.owner_id.clone(),
+            paused: c.paused,
+            changed: changed_settings(&old_config, &c),
+        };
         Ok(Response::new()
+            .add_event(changed.into())
             .add_attribute("method", "update_settings")
             .add_attribute("paused", c.paused.to_string())
             .add_attribute("owner_id", c.owner_id.to_string())
@@ -172,6 +265,32 @@ impl<'a> CwCroncat<'a> {
             .add_attribute("slot_granularity_time", c.slot_granularity_time.to_string()))
     }
 
+    /// Remove the last execution kept for the ended tasks
+    /// Restricted to the owner, the history of the existing tasks stays
+    pub fn prune_task_history(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        task_hashes: Vec<String>,
+    ) -> Result<Response<T>, ContractError> {
+        let config = self.config.load(deps.storage)?;
+        if info.sender != config.owner_id {
+            return Err(ContractError::Unauthorized {});
+        }
+        let mut pruned_records = 0;
+        for task_hash in task_hashes {
+            let hash = task_hash.as_bytes();
+            if self.tasks.has(deps.storage, hash) || self.tasks_with_queries.has(deps.storage, hash)
+            {
+                continue;
+            }
+            pruned_records += self.clear_task_history(deps.storage, hash)?;
+        }
+        Ok(Response::new()
+            .add_attribute("method", "prune_task_history")
+            .add_attribute("pruned_records", pruned_records.to_string()))
+    }
+
     /// Move Balance
     /// Allows owner to move balance to DAO or to let treasury transfer to itself only.
     /// This is a restricted method for moving funds utilized in growth management strategies.
@@ -182,7 +301,7 @@ impl<'a> CwCroncat<'a> {
         env: Env,
         balances: Vec<Balance>,
         account_id: String,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         let account_id = deps.api.addr_validate(&account_id)?;
         let mut config = self.config.load(deps.storage)?;
 
@@ -213,7 +332,7 @@ impl<'a> CwCroncat<'a> {
         let state_balances = deps.querier.query_all_balances(env.contract.address)?;
         let mut has_fund_err = false;
 
-        let messages: Result<Vec<SubMsg>, ContractError> = balances
+        let messages: Result<Vec<SubMsg<T>>, ContractError> = balances
             .iter()
             .map(|balance| -> Result<SubMsg<_>, ContractError> {
                 match balance {
@@ -282,3 +401,62 @@ impl<'a> CwCroncat<'a> {
             .add_submessages(messages?))
     }
 }
+
+/// Names of the settings that differ between the configs
+fn changed_settings(old: &Config, new: &Config) -> Vec<String> {
+    [
+        ("owner_id", old.owner_id != new.owner_id),
+        ("chain_name", old.chain_name != new.chain_name),
+        ("paused", old.paused != new.paused),
+        (
+            "min_tasks_per_agent",
+            old.min_tasks_per_agent != new.min_tasks_per_agent,
+        ),
+        (
+            "agents_eject_threshold",
+            old.agents_eject_threshold != new.agents_eject_threshold,
+        ),
+        ("agent_fee", old.agent_fee != new.agent_fee),
+        ("gas_price", old.gas_price != new.gas_price),
+        ("gas_base_fee", old.gas_base_fee != new.gas_base_fee),
+        ("gas_action_fee", old.gas_action_fee != new.gas_action_fee),
+        ("gas_query_fee", old.gas_query_fee != new.gas_query_fee),
+        (
+            "gas_wasm_query_fee",
+            old.gas_wasm_query_fee != new.gas_wasm_query_fee,
+        ),
+        (
+            "proxy_callback_gas",
+            old.proxy_callback_gas != new.proxy_callback_gas,
+        ),
+        (
+            "slot_granularity_time",
+            old.slot_granularity_time != new.slot_granularity_time,
+        ),
+        (
+            "ibc_transfer_timeout",
+            old.ibc_transfer_timeout != new.ibc_transfer_timeout,
+        ),
+        (
+            "gov_msgs_enabled",
+            old.gov_msgs_enabled != new.gov_msgs_enabled,
+        ),
+        (
+            "stargate_type_urls",
+            old.stargate_type_urls != new.stargate_type_urls,
+        ),
+        (
+            "admin_allowlist",
+            old.admin_allowlist != new.admin_allowlist,
+        ),
+        ("fee_tokens", old.fee_tokens != new.fee_tokens),
+        (
+            "gas_price_source",
+            old.gas_price_source != new.gas_price_source,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
@@ -1,17 +1,21 @@
-use cosmwasm_std::{DepsMut, MessageInfo, Response};
+use cosmwasm_std::{CustomMsg, DepsMut, MessageInfo, Response};
 use cw20::{Cw20CoinVerified, Cw20ReceiveMsg};
 use cw_croncat_core::traits::BalancesOperations;
+use serde::de::DeserializeOwned;
 
 use crate::{ContractError, CwCroncat};
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     /// Add cw20 coin to user balance, that sent this coins
     pub fn receive_cw20(
         &self,
         deps: DepsMut,
         info: MessageInfo,
         msg: Cw20ReceiveMsg,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
         let sender = deps.api.addr_validate(&msg.sender)?;
         let coin_address = info.sender;
 
@@ -44,4 +48,41 @@ impl<'a> CwCroncat<'a> {
             .add_attribute("method", "receive_cw20")
             .add_attribute("total_cw20_balances", format!("{total_cw20_string:?}")))
     }
+
+    /// Add attached native coins to user balance, that sent this coins
+    pub fn deposit_wallet_balance(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<T>, ContractError> {
+        if info.funds.is_empty() {
+            return Err(ContractError::CustomError {
+                val: "Must attach funds".to_string(),
+            });
+        }
+
+        // Updating user balance
+        let new_balances = self.native_balances.update(
+            deps.storage,
+            &info.sender,
+            |balances| -> Result<_, ContractError> {
+                let mut balances = balances.unwrap_or_default();
+                balances.checked_add_coins(&info.funds)?;
+                Ok(balances)
+            },
+        )?;
+
+        // Updating contract balance
+        self.config
+            .update(deps.storage, |mut c| -> Result<_, ContractError> {
+                c.available_balance.checked_add_native(&info.funds)?;
+                Ok(c)
+            })?;
+
+        let total_native_string: Vec<String> =
+            new_balances.iter().map(ToString::to_string).collect();
+        Ok(Response::new()
+            .add_attribute("method", "deposit_wallet_balance")
+            .add_attribute("total_native_balances", format!("{total_native_string:?}")))
+    }
 }
@@ -1,9 +1,13 @@
 use crate::CwCroncat;
-use cosmwasm_std::{BlockInfo, Order, StdResult, Storage};
+use cosmwasm_std::{BlockInfo, CustomMsg, Order, StdResult, Storage};
 pub use cw_croncat_core::types::Interval;
 use cw_croncat_core::types::SlotType;
+use serde::de::DeserializeOwned;
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     /// Get the slot with lowest height/timestamp
     /// Returns a tuple of optionals: (Option<block height>, Option<timestamp>)
     /// NOTE: This prioritizes blocks over timestamps.
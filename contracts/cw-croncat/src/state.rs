@@ -1,15 +1,18 @@
 use crate::{balancer::RoundRobinBalancer, ContractError};
-use cosmwasm_std::{Addr, Deps, StdResult, Storage, Timestamp};
+use cosmwasm_std::{Addr, Coin, CustomMsg, Deps, Empty, StdResult, Storage, Timestamp};
 use cw2::ContractVersion;
 use cw20::Cw20CoinVerified;
-use cw_storage_plus::{Deque, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_storage_plus::{Deque, Index, IndexList, IndexedMap, Item, Map, MultiIndex, Prefix};
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::helpers::Task;
+use crate::contract::IBC_TRANSFER_TIMEOUT;
 use cw_croncat_core::{
     query::CroncatQuerier,
-    types::{Agent, GasPrice, GenericBalance, SlotType},
+    types::{
+        ActionFailure, AdminAllowance, Agent, ExecutionRecord, FeeTokenRate, GasPrice,
+        GasPriceSource, GenericBalance, GenericTask, SlotType, TriggerCondition,
+    },
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -46,6 +49,24 @@ pub struct Config {
     pub gas_wasm_query_fee: u64,
     pub proxy_callback_gas: u32,
     pub slot_granularity_time: u64,
+    // Seconds an IBC transfer action stays valid after the execution block
+    #[serde(default = "default_ibc_transfer_timeout")]
+    pub ibc_transfer_timeout: u64,
+    // Whether tasks can have governance actions
+    #[serde(default)]
+    pub gov_msgs_enabled: bool,
+    // Type urls of the stargate messages tasks can have, none of them can move the contract funds
+    #[serde(default)]
+    pub stargate_type_urls: Vec<String>,
+    // Contracts croncat is the admin of, with the task owners allowed to manage them
+    #[serde(default)]
+    pub admin_allowlist: Vec<AdminAllowance>,
+    // Tokens the agent fee can be paid in, besides the native denom
+    #[serde(default)]
+    pub fee_tokens: Vec<FeeTokenRate>,
+    // Gas price numerator is fixed, or read from an oracle on every proxy call
+    #[serde(default)]
+    pub gas_price_source: GasPriceSource,
 
     // Treasury
     // pub treasury_id: Option<Addr>,
@@ -58,7 +79,35 @@ pub struct Config {
     pub limit: u64,
 }
 
+fn default_ibc_transfer_timeout() -> u64 {
+    IBC_TRANSFER_TIMEOUT
+}
+
+/// Progress of re-keying the stored tasks after the task hash encoding changed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TaskMigration {
+    /// Pause state of the contract to restore once every task is migrated
+    pub paused: bool,
+    pub stage: TaskMigrationStage,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub enum TaskMigrationStage {
+    /// Moving the tasks under their new hashes, which also fills the newer indexes
+    Tasks {
+        with_queries: bool,
+        start_after: Option<Vec<u8>>,
+    },
+    /// Replacing the old hashes in the slots
+    Slots {
+        slot_kind: SlotType,
+        start_after: Option<u64>,
+    },
+    /// Dropping the renames of the tasks that weren't in any slot
+    Cleanup,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct QueueItem {
     pub contract_addr: Option<Addr>,
     // This is used to track disjointed callbacks
@@ -73,36 +122,93 @@ pub struct QueueItem {
     pub task_is_extra: Option<bool>,
     pub agent_id: Option<Addr>,
     pub failure: Option<String>,
+    // every failed action, reported to the task callback
+    #[serde(default)]
+    pub failures: Vec<ActionFailure>,
+    // agent fee taken from the task deposit for this execution
+    #[serde(default)]
+    pub fee: GenericBalance,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TaskInfo {
-    pub task: Task,
+pub struct TaskInfo<T> {
+    pub task: GenericTask<T>,
     pub task_hash: Vec<u8>,
     pub task_is_extra: Option<bool>,
     pub agent_id: Addr,
     pub slot_kind: SlotType,
 }
 
-pub struct TaskIndexes<'a> {
-    pub owner: MultiIndex<'a, Addr, Task, Addr>,
+pub struct TaskIndexes<'a, T> {
+    pub owner: MultiIndex<'a, Addr, GenericTask<T>, Addr>,
+    pub target: TargetIndex<'a>,
 }
 
-impl<'a> IndexList<Task> for TaskIndexes<'a> {
-    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Task>> + '_> {
-        let v: Vec<&dyn Index<Task>> = vec![&self.owner];
+impl<'a, T> IndexList<GenericTask<T>> for TaskIndexes<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<GenericTask<T>>> + '_> {
+        let v: Vec<&dyn Index<GenericTask<T>>> = vec![&self.owner, &self.target];
         Box::new(v.into_iter())
     }
 }
 
-pub fn token_owner_idx(_pk: &[u8], d: &Task) -> Addr {
+/// Indexes the task hash under every target address of its actions
+/// MultiIndex only supports a single index key per task
+pub struct TargetIndex<'a> {
+    idx: Map<'a, (Addr, Vec<u8>), Empty>,
+}
+
+impl<'a> TargetIndex<'a> {
+    pub const fn new(idx_namespace: &'a str) -> Self {
+        Self {
+            idx: Map::new(idx_namespace),
+        }
+    }
+
+    /// Hashes of the tasks calling the target
+    pub fn prefix(&self, target: Addr) -> Prefix<Vec<u8>, Empty, Vec<u8>> {
+        self.idx.prefix(target)
+    }
+}
+
+impl<'a, T> Index<GenericTask<T>> for TargetIndex<'a>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &GenericTask<T>) -> StdResult<()> {
+        for target in data.targets() {
+            self.idx.save(store, (target, pk.to_vec()), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    fn remove(
+        &self,
+        store: &mut dyn Storage,
+        pk: &[u8],
+        old_data: &GenericTask<T>,
+    ) -> StdResult<()> {
+        for target in old_data.targets() {
+            self.idx.remove(store, (target, pk.to_vec()));
+        }
+        Ok(())
+    }
+}
+
+pub fn token_owner_idx<T>(_pk: &[u8], d: &GenericTask<T>) -> Addr {
     d.owner_id.clone()
 }
 
 /// ----------------------------------------------------------------
 /// Tasks Storage
 /// ----------------------------------------------------------------
-pub struct CwCroncat<'a> {
+/// `T` is the `CosmosMsg::Custom` type of the task actions
+pub struct CwCroncat<'a, T = Empty>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     pub config: Item<'a, Config>,
 
     pub agents: Map<'a, &'a Addr, Agent>,
@@ -111,7 +217,7 @@ pub struct CwCroncat<'a> {
     pub agent_pending_queue: Deque<'a, Addr>,
 
     // REF: https://github.com/CosmWasm/cw-plus/tree/main/packages/storage-plus#indexedmap
-    pub tasks: IndexedMap<'a, &'a [u8], Task, TaskIndexes<'a>>,
+    pub tasks: IndexedMap<'a, &'a [u8], GenericTask<T>, TaskIndexes<'a, T>>,
     pub task_total: Item<'a, u64>,
 
     /// Timestamps can be grouped into slot buckets (1-60 second buckets) for easier agent handling
@@ -120,13 +226,26 @@ pub struct CwCroncat<'a> {
     /// this is done instead of forcing a block height into a range of timestamps for reliability
     pub block_slots: Map<'a, u64, Vec<Vec<u8>>>,
 
-    pub tasks_with_queries: IndexedMap<'a, &'a [u8], Task, TaskIndexes<'a>>,
+    pub tasks_with_queries: IndexedMap<'a, &'a [u8], GenericTask<T>, TaskIndexes<'a, T>>,
     pub tasks_with_queries_total: Item<'a, u64>,
 
     /// Time and block based maps by the corresponding task hash
     pub time_map_queries: Map<'a, &'a [u8], u64>,
     pub block_map_queries: Map<'a, &'a [u8], u64>,
 
+    /// Tasks waiting for the completion of another task, by parent and dependent task hash
+    pub task_dependents: Map<'a, (&'a [u8], &'a [u8]), TriggerCondition>,
+
+    /// Ring buffer of the last executions, by task hash and execution number modulo `TASK_HISTORY_SIZE`
+    /// Only the last execution outlives the task, pruned by the owner or when a task with the same hash is created
+    pub task_history: Map<'a, (&'a [u8], u64), ExecutionRecord>,
+
+    /// `TASK_HASH_VERSION` of the stored task hashes, unset before the versioned encoding
+    pub task_hash_version: Item<'a, String>,
+    pub task_migration: Item<'a, TaskMigration>,
+    /// New hashes of the migrated tasks without queries, by their old hash, until the slots get renamed
+    pub task_renames: Map<'a, &'a [u8], Vec<u8>>,
+
     /// Reply Queue
     /// Keeping ordered sub messages & reply id's
     pub reply_queue: Map<'a, u64, QueueItem>,
@@ -139,28 +258,48 @@ pub struct CwCroncat<'a> {
 
     pub balancer: RoundRobinBalancer,
     pub balances: Map<'a, &'a Addr, Vec<Cw20CoinVerified>>,
+    /// Native coins of the user wallets, only spent by task auto refills
+    pub native_balances: Map<'a, &'a Addr, Vec<Coin>>,
 }
 
 impl Default for CwCroncat<'static> {
     fn default() -> Self {
+        Self::with_custom_msg()
+    }
+}
+
+impl<T> CwCroncat<'static, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    /// Contract storage for the chains with `T` custom messages
+    pub fn with_custom_msg() -> Self {
         Self::new(
             "tasks",
             "tasks_with_queries",
             "tasks__owner",
             "tasks_with_queries__owner",
+            "tasks__target",
+            "tasks_with_queries__target",
         )
     }
 }
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     fn new(
         tasks_key: &'a str,
         tasks_with_queries_key: &'a str,
         tasks_owner_key: &'a str,
         tasks_with_queries_owner_key: &'a str,
+        tasks_target_key: &'a str,
+        tasks_with_queries_target_key: &'a str,
     ) -> Self {
         let indexes = TaskIndexes {
             owner: MultiIndex::new(token_owner_idx, tasks_key, tasks_owner_key),
+            target: TargetIndex::new(tasks_target_key),
         };
         let indexes_queries = TaskIndexes {
             owner: MultiIndex::new(
@@ -168,6 +307,7 @@ impl<'a> CwCroncat<'a> {
                 tasks_with_queries_key,
                 tasks_with_queries_owner_key,
             ),
+            target: TargetIndex::new(tasks_with_queries_target_key),
         };
         Self {
             config: Item::new("config"),
@@ -182,11 +322,17 @@ impl<'a> CwCroncat<'a> {
             block_slots: Map::new("block_slots"),
             time_map_queries: Map::new("time_slots_queries"),
             block_map_queries: Map::new("block_slots_queries"),
+            task_dependents: Map::new("task_dependents"),
+            task_history: Map::new("task_history"),
+            task_hash_version: Item::new("task_hash_version"),
+            task_migration: Item::new("task_migration"),
+            task_renames: Map::new("task_renames"),
             reply_queue: Map::new("reply_queue"),
             reply_index: Item::new("reply_index"),
             agent_nomination_begin_time: Item::new("agent_nomination_begin_time"),
             balancer: RoundRobinBalancer::default(),
             balances: Map::new("balances"),
+            native_balances: Map::new("native_balances"),
         }
     }
 
@@ -239,8 +385,14 @@ impl<'a> CwCroncat<'a> {
             let mut rq = rq.ok_or(ContractError::UnknownReplyID {})?;
             // if first fails it means whole thing failed
             // for cases where we stop task on failure
-            if rq.failure.is_none() {
-                rq.failure = failure;
+            if let Some(error) = failure {
+                rq.failures.push(ActionFailure {
+                    action_idx: rq.action_idx,
+                    error: error.clone(),
+                });
+                if rq.failure.is_none() {
+                    rq.failure = Some(error);
+                }
             }
             rq.action_idx += 1;
             Ok(rq)
@@ -251,7 +403,7 @@ impl<'a> CwCroncat<'a> {
         &self,
         storage: &dyn Storage,
         task_hash: &[u8],
-    ) -> Result<Task, ContractError> {
+    ) -> Result<GenericTask<T>, ContractError> {
         let some_task = self.tasks.may_load(storage, task_hash)?;
         if let Some(task) = some_task {
             Ok(task)
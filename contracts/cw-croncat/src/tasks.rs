@@ -1,42 +1,60 @@
 use crate::error::ContractError;
+use crate::helpers::{
+    agent_fee_price, fee_token_rate, next_cursor, owns_nfts, page_limit, reserved_gas_price,
+    validate_cw20_coins,
+};
 use crate::slots::Interval;
-use crate::state::{Config, CwCroncat};
-use cosmwasm_std::{coin, Storage};
+use crate::state::{Config, CwCroncat, TaskMigration, TaskMigrationStage};
+use cosmwasm_std::Storage;
 use cosmwasm_std::{
-    to_binary, BankMsg, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, SubMsg,
-    WasmMsg,
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, CustomMsg, Deps, DepsMut, Env, MessageInfo, Order,
+    Response, StdError, StdResult, SubMsg, Timestamp, WasmMsg,
 };
 use cw20::{Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg};
 use cw_croncat_core::error::CoreError;
+use cw_croncat_core::events::{
+    AgentNominated, TaskCreated, TaskEndReason, TaskEnded, TaskRefilled, TaskRemoved,
+    TaskRescheduled,
+};
 use cw_croncat_core::msg::{
-    GetSlotHashesResponse, GetSlotIdsResponse, TaskRequest, TaskResponse, TaskWithQueriesResponse,
+    GenericExecuteMsg, GenericGetTasksResponse, GenericTaskRequest, GenericTaskResponse,
+    GetSlotHashesResponse, GetSlotIdsResponse, GetTaskDependenciesResponse,
+    GetTasksWithQueriesResponse, PreviewScheduleResponse, SimulateTaskResponse,
+    TaskDependencyResponse,
 };
-use cw_croncat_core::traits::{BalancesOperations, FindAndMutate, Intervals};
+use cw_croncat_core::traits::{BalancesOperations, Intervals};
 use cw_croncat_core::types::{
-    gas_amount_with_agent_fee, CheckedBoundary, GenericBalance, SlotType, Task,
+    Boundary, CheckedBoundary, ExecutionRecord, GenericBalance, GenericTask, SlotType,
+    TASK_HASH_VERSION,
 };
+use cw_rules_core::types::MAX_QUERY_DEPTH;
+use cw_storage_plus::Bound;
+use serde::de::DeserializeOwned;
 
-impl<'a> CwCroncat<'a> {
+impl<'a, T> CwCroncat<'a, T>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     /// Returns task data
     /// Used by the frontend for viewing tasks
     pub(crate) fn query_get_tasks(
         &self,
         deps: Deps,
-        from_index: Option<u64>,
+        start_after: Option<String>,
         limit: Option<u64>,
-    ) -> StdResult<Vec<TaskResponse>> {
+    ) -> StdResult<GenericGetTasksResponse<T>> {
         let cfg: Config = self.config.load(deps.storage)?;
         let prefix = cfg.chain_name.as_str();
-        let default_limit = self.config.load(deps.storage)?.limit;
-        let size: u64 = self.task_total.load(deps.storage)?.min(default_limit);
-        let from_index = from_index.unwrap_or_default();
-        let limit = limit.unwrap_or(default_limit).min(size);
-        self.tasks
-            .range(deps.storage, None, None, Order::Ascending)
-            .skip(from_index as usize)
-            .take(limit as usize)
+        let limit = page_limit(limit, cfg.limit);
+        let start = start_after.map(|hash| Bound::ExclusiveRaw(hash.into_bytes()));
+        let mut tasks = self
+            .tasks
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit + 1)
             .map(|res| res.map(|(_k, task)| task.into_response(prefix)))
-            .collect()
+            .collect::<StdResult<Vec<_>>>()?;
+        let next_cursor = next_cursor(&mut tasks, limit, |task| task.task_hash.clone());
+        Ok(GenericGetTasksResponse { tasks, next_cursor })
     }
 
     /// Returns task with queries data
@@ -46,40 +64,90 @@ impl<'a> CwCroncat<'a> {
     pub(crate) fn query_get_tasks_with_queries(
         &self,
         deps: Deps,
-        from_index: Option<u64>,
+        start_after: Option<String>,
         limit: Option<u64>,
-    ) -> StdResult<Vec<TaskWithQueriesResponse>> {
+    ) -> StdResult<GetTasksWithQueriesResponse> {
         let cfg: Config = self.config.load(deps.storage)?;
         let prefix = cfg.chain_name.as_str();
-        let size: u64 = self.tasks_with_queries_total.load(deps.storage)?.min(1000);
-        let from_index = from_index.unwrap_or_default();
-        let limit = limit
-            .unwrap_or(self.config.load(deps.storage)?.limit)
-            .min(size);
-        self.tasks_with_queries
-            .range(deps.storage, None, None, Order::Ascending)
-            .skip(from_index as usize)
-            .take(limit as usize)
+        let limit = page_limit(limit, cfg.limit);
+        let start = start_after.map(|hash| Bound::ExclusiveRaw(hash.into_bytes()));
+        let mut tasks = self
+            .tasks_with_queries
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit + 1)
             .map(|res| res.map(|(_k, task)| task.into_response_with_queries(prefix)))
-            .collect()
+            .collect::<StdResult<Vec<_>>>()?;
+        let next_cursor = next_cursor(&mut tasks, limit, |task| task.task_hash.clone());
+        Ok(GetTasksWithQueriesResponse { tasks, next_cursor })
     }
 
-    /// Returns task data for a specific owner
+    /// Returns task data for a specific owner, with and without queries
+    /// Tasks are ordered by their hash
     pub(crate) fn query_get_tasks_by_owner(
         &self,
         deps: Deps,
         owner_id: String,
-    ) -> StdResult<Vec<TaskResponse>> {
+        start_after: Option<String>,
+        limit: Option<u64>,
+    ) -> StdResult<GenericGetTasksResponse<T>> {
         let cfg: Config = self.config.load(deps.storage)?;
         let prefix = cfg.chain_name.as_str();
         let owner_id = deps.api.addr_validate(&owner_id)?;
-        self.tasks
-            .idx
-            .owner
-            .prefix(owner_id)
-            .range(deps.storage, None, None, Order::Ascending)
-            .map(|x| x.map(|(_, task)| task.into_response(prefix)))
-            .collect::<StdResult<Vec<_>>>()
+        let limit = page_limit(limit, cfg.limit);
+        let mut tasks = Vec::new();
+        for map in [&self.tasks, &self.tasks_with_queries] {
+            let start = start_after
+                .as_ref()
+                .map(|hash| Bound::ExclusiveRaw(hash.as_bytes().to_vec()));
+            for res in map
+                .idx
+                .owner
+                .prefix(owner_id.clone())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit + 1)
+            {
+                let (_, task) = res?;
+                tasks.push(task.into_response(prefix));
+            }
+        }
+        tasks.sort_by(|a, b| a.task_hash.cmp(&b.task_hash));
+        let next_cursor = next_cursor(&mut tasks, limit, |task| task.task_hash.clone());
+        Ok(GenericGetTasksResponse { tasks, next_cursor })
+    }
+
+    /// Returns task data of the tasks calling a specific address, with and without queries
+    /// Tasks are ordered by their hash
+    pub(crate) fn query_get_tasks_by_target(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    ) -> StdResult<GenericGetTasksResponse<T>> {
+        let cfg: Config = self.config.load(deps.storage)?;
+        let prefix = cfg.chain_name.as_str();
+        let limit = page_limit(limit, cfg.limit);
+        // Not validated, targets of the IBC transfers are addresses of other chains
+        let target = Addr::unchecked(address);
+        let mut tasks = Vec::new();
+        for map in [&self.tasks, &self.tasks_with_queries] {
+            let start = start_after
+                .as_ref()
+                .map(|hash| Bound::ExclusiveRaw(hash.as_bytes().to_vec()));
+            for hash in map
+                .idx
+                .target
+                .prefix(target.clone())
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(limit + 1)
+            {
+                let task = map.load(deps.storage, &hash?)?;
+                tasks.push(task.into_response(prefix));
+            }
+        }
+        tasks.sort_by(|a, b| a.task_hash.cmp(&b.task_hash));
+        let next_cursor = next_cursor(&mut tasks, limit, |task| task.task_hash.clone());
+        Ok(GenericGetTasksResponse { tasks, next_cursor })
     }
 
     /// Returns single task data
@@ -87,9 +155,9 @@ impl<'a> CwCroncat<'a> {
         &self,
         deps: Deps,
         task_hash: String,
-    ) -> StdResult<Option<TaskResponse>> {
+    ) -> StdResult<Option<GenericTaskResponse<T>>> {
         let cfg: Config = self.config.load(deps.storage)?;
-        let res: Option<Task> = {
+        let res: Option<GenericTask<T>> = {
             let task = self.tasks.may_load(deps.storage, task_hash.as_bytes())?;
             if let Some(task) = task {
                 Some(task)
@@ -101,10 +169,143 @@ impl<'a> CwCroncat<'a> {
         Ok(res.map(|task| task.into_response(cfg.chain_name.as_str())))
     }
 
+    /// Returns the triggers between tasks of a specific owner
+    /// A page reads up to `limit` owner tasks, only the triggered ones are returned
+    pub(crate) fn query_get_task_dependencies(
+        &self,
+        deps: Deps,
+        owner_id: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    ) -> StdResult<GetTaskDependenciesResponse> {
+        let cfg: Config = self.config.load(deps.storage)?;
+        let prefix = cfg.chain_name.as_str();
+        let owner_id = deps.api.addr_validate(&owner_id)?;
+        let limit = page_limit(limit, cfg.limit);
+        let mut tasks = Vec::new();
+        for map in [&self.tasks, &self.tasks_with_queries] {
+            let start = start_after
+                .as_ref()
+                .map(|hash| Bound::ExclusiveRaw(hash.as_bytes().to_vec()));
+            for res in map
+                .idx
+                .owner
+                .prefix(owner_id.clone())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit + 1)
+            {
+                let (_, task) = res?;
+                tasks.push((task.to_hash(prefix), task.trigger));
+            }
+        }
+        tasks.sort_by(|a, b| a.0.cmp(&b.0));
+        let next_cursor = next_cursor(&mut tasks, limit, |(task_hash, _)| task_hash.clone());
+        let dependencies = tasks
+            .into_iter()
+            .filter_map(|(task_hash, trigger)| {
+                trigger.map(|trigger| TaskDependencyResponse { task_hash, trigger })
+            })
+            .collect();
+        Ok(GetTaskDependenciesResponse {
+            dependencies,
+            next_cursor,
+        })
+    }
+
+    /// Returns the last executions of the task, newest first
+    pub(crate) fn query_get_task_history(
+        &self,
+        deps: Deps,
+        task_hash: String,
+    ) -> StdResult<Vec<ExecutionRecord>> {
+        let mut records = self
+            .task_history
+            .prefix(task_hash.as_bytes())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|res| res.map(|(_, record)| record))
+            .collect::<StdResult<Vec<_>>>()?;
+        records.sort_by(|a, b| b.execution.cmp(&a.execution));
+        Ok(records)
+    }
+
     /// Returns a hash computed by the input task data
-    pub(crate) fn query_get_task_hash(&self, deps: Deps, task: Task) -> StdResult<String> {
+    pub(crate) fn query_get_task_hash(
+        &self,
+        deps: Deps,
+        task: GenericTaskRequest<T>,
+        owner_id: String,
+    ) -> StdResult<String> {
+        let owner_id = deps.api.addr_validate(&owner_id)?;
         let cfg: Config = self.config.load(deps.storage)?;
-        Ok(task.to_hash(cfg.chain_name.as_str()))
+        task.to_hash(&owner_id, cfg.chain_name.as_str())
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+
+    /// Validates the task request like its creation would, without any funds attached
+    /// Returns the costs of the task, or the error its creation would fail with
+    pub(crate) fn query_simulate_task(
+        &self,
+        deps: Deps,
+        env: Env,
+        task: GenericTaskRequest<T>,
+        owner_id: String,
+    ) -> StdResult<SimulateTaskResponse> {
+        let owner_id = deps.api.addr_validate(&owner_id)?;
+        let res = self
+            .simulate_task(deps, &env, &owner_id, task)
+            .unwrap_or_else(|err| SimulateTaskResponse {
+                error: Some(err.to_string()),
+                ..Default::default()
+            });
+        Ok(res)
+    }
+
+    fn simulate_task(
+        &self,
+        deps: Deps,
+        env: &Env,
+        owner_id: &Addr,
+        task: GenericTaskRequest<T>,
+    ) -> Result<SimulateTaskResponse, ContractError> {
+        let cfg: Config = self.config.load(deps.storage)?;
+        if cfg.paused {
+            return Err(ContractError::CustomError {
+                val: "Create task paused".to_string(),
+            });
+        }
+        validate_cw20_coins(deps.api, &task.cw20_coins)?;
+        let (item, gas_amount) =
+            self.task_from_request(deps, env, &cfg, owner_id, task, GenericBalance::default())?;
+
+        let (next_id, _) = item
+            .interval
+            .next(env, item.boundary, cfg.slot_granularity_time);
+        if next_id == 0 {
+            return Err(ContractError::CustomError {
+                val: "Task ended".to_string(),
+            });
+        }
+        let hash = item.to_hash(cfg.chain_name.as_str());
+        let exists = if item.with_queries() {
+            self.tasks_with_queries.has(deps.storage, hash.as_bytes())
+        } else {
+            self.tasks.has(deps.storage, hash.as_bytes())
+        };
+        if exists {
+            return Err(ContractError::CustomError {
+                val: "Task already exists".to_string(),
+            });
+        }
+
+        Ok(SimulateTaskResponse {
+            error: None,
+            boundary: item.boundary.to_boundary(&item.interval),
+            amount_for_one_task: item.amount_for_one_task.clone(),
+            gas_amount,
+            min_deposit: item.amount_for_executions(1)?,
+            min_recurring_deposit: item.amount_for_executions(2)?,
+            task_hash: Some(hash),
+        })
     }
 
     /// Check if interval params are valid by attempting to parse
@@ -112,6 +313,44 @@ impl<'a> CwCroncat<'a> {
         Ok(interval.is_valid())
     }
 
+    /// Next slots of the schedule, up to `count` (capped by the config limit)
+    /// Assumes every execution happens right at its slot
+    pub(crate) fn query_preview_schedule(
+        &self,
+        deps: Deps,
+        mut env: Env,
+        interval: Interval,
+        boundary: Option<Boundary>,
+        count: u64,
+    ) -> StdResult<PreviewScheduleResponse> {
+        if !interval.is_valid() {
+            return Err(StdError::generic_err("Interval invalid"));
+        }
+        let boundary = CheckedBoundary::new(boundary, &interval)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+        let cfg: Config = self.config.load(deps.storage)?;
+        let count = count.min(cfg.limit);
+        let once = matches!(interval, Interval::Once | Interval::At(_));
+
+        let (mut next_id, slot_type) = interval.next(&env, boundary, cfg.slot_granularity_time);
+        let mut slots = vec![];
+        while next_id != 0 && (slots.len() as u64) < count {
+            slots.push(next_id);
+            match slot_type {
+                SlotType::Block => env.block.height = next_id,
+                SlotType::Cron => env.block.time = Timestamp::from_nanos(next_id),
+            }
+            let (id, _) = interval.next(&env, boundary, cfg.slot_granularity_time);
+            // Schedule ended if it can't go past this slot
+            next_id = if once || id <= next_id { 0 } else { id };
+        }
+        Ok(PreviewScheduleResponse {
+            slot_type,
+            slots,
+            exhausted: next_id == 0,
+        })
+    }
+
     /// Gets a set of tasks.
     /// Default: Returns the next executable set of tasks hashes.
     ///
@@ -209,53 +448,60 @@ impl<'a> CwCroncat<'a> {
         })
     }
 
-    /// Allows any user or contract to pay for future txns based on a specific schedule
-    /// contract, function id & other settings. When the task runs out of balance
-    /// the task is no longer executed, any additional funds will be returned to task owner.
-    pub fn create_task(
+    /// Validate the task request of `owner_id` and build the task with the given deposit
+    /// Shared by the task creation and its simulation, returns the task with its total gas
+    fn task_from_request(
         &self,
-        deps: DepsMut,
-        info: MessageInfo,
-        env: Env,
-        task: TaskRequest,
-    ) -> Result<Response, ContractError> {
-        if info.funds.is_empty() {
-            return Err(ContractError::CustomError {
-                val: "Must attach funds".to_string(),
-            });
-        }
-        let cfg: Config = self.config.load(deps.storage)?;
-        if cfg.paused {
-            return Err(ContractError::CustomError {
-                val: "Create task paused".to_string(),
-            });
+        deps: Deps,
+        env: &Env,
+        cfg: &Config,
+        owner_id: &Addr,
+        task: GenericTaskRequest<T>,
+        total_deposit: GenericBalance,
+    ) -> Result<(GenericTask<T>, u64), ContractError> {
+        for action in task.actions.iter() {
+            match &action.msg {
+                CosmosMsg::Gov(_) if !cfg.gov_msgs_enabled => {
+                    return Err(ContractError::CustomError {
+                        val: "Governance actions disabled".to_string(),
+                    });
+                }
+                CosmosMsg::Wasm(
+                    WasmMsg::Migrate { contract_addr, .. }
+                    | WasmMsg::UpdateAdmin { contract_addr, .. }
+                    | WasmMsg::ClearAdmin { contract_addr },
+                ) if !cfg.admin_allowlist.iter().any(|allowance| {
+                    allowance.contract_addr == *contract_addr && allowance.task_owner == *owner_id
+                }) =>
+                {
+                    return Err(ContractError::CustomError {
+                        val: "Admin action not allowed".to_string(),
+                    });
+                }
+                CosmosMsg::Stargate { type_url, .. }
+                    if !cfg.stargate_type_urls.contains(type_url) =>
+                {
+                    return Err(ContractError::CustomError {
+                        val: "Stargate type url not allowed".to_string(),
+                    });
+                }
+                _ => (),
+            }
         }
-
-        let owner_id = &info.sender;
-        let cw20 = if !task.cw20_coins.is_empty() {
-            let mut cw20: Vec<Cw20CoinVerified> = Vec::with_capacity(task.cw20_coins.len());
-            for coin in &task.cw20_coins {
-                cw20.push(Cw20CoinVerified {
-                    address: deps.api.addr_validate(&coin.address)?,
-                    amount: coin.amount,
-                })
+        if let Some(fee_token) = &task.fee_token {
+            if fee_token_rate(cfg, fee_token).is_none() {
+                return Err(ContractError::CustomError {
+                    val: "Fee token not allowed".to_string(),
+                });
             }
-            // update user balances
-            self.balances.update(
-                deps.storage,
-                owner_id,
-                |balances| -> Result<_, ContractError> {
-                    let mut balances = balances.unwrap_or_default();
+        }
 
-                    balances.checked_sub_coins(&cw20)?;
-                    Ok(balances)
-                },
-            )?;
-            cw20
-        } else {
-            vec![]
-        };
         let boundary = CheckedBoundary::new(task.boundary, &task.interval)?;
+        let callback_addr = task
+            .callback_addr
+            .as_ref()
+            .map(|addr| deps.api.addr_validate(addr))
+            .transpose()?;
 
         if !task.interval.is_valid() {
             return Err(ContractError::CustomError {
@@ -263,6 +509,37 @@ impl<'a> CwCroncat<'a> {
             });
         }
 
+        if task.max_executions == Some(0) {
+            return Err(ContractError::CustomError {
+                val: "Max executions invalid".to_string(),
+            });
+        }
+        if matches!(&task.auto_refill, Some(auto_refill) if !auto_refill.is_valid()) {
+            return Err(ContractError::CustomError {
+                val: "Auto refill invalid".to_string(),
+            });
+        }
+
+        if matches!(&task.queries, Some(queries) if queries.iter().any(|query| query.depth() > MAX_QUERY_DEPTH))
+        {
+            return Err(ContractError::CustomError {
+                val: "Queries too deep".to_string(),
+            });
+        }
+
+        if let Some(trigger) = &task.trigger {
+            if !matches!(task.interval, Interval::Once | Interval::Immediate) {
+                return Err(ContractError::CustomError {
+                    val: "Trigger invalid".to_string(),
+                });
+            }
+            // Only tasks of the same owner can be chained
+            let parent = self.get_task_by_hash(deps.storage, trigger.task_hash.as_bytes())?;
+            if !parent.is_owner(owner_id.clone()) {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+
         let (mut amount_for_one_task, gas_amount) = task.is_valid_msg_calculate_usage(
             deps.api,
             &env.contract.address,
@@ -273,37 +550,97 @@ impl<'a> CwCroncat<'a> {
             cfg.gas_query_fee,
             cfg.gas_wasm_query_fee,
         )?;
-        let gas_amount_with_agent_fee = gas_amount_with_agent_fee(gas_amount, cfg.agent_fee)?;
-        let price = cfg.gas_price.calculate(gas_amount_with_agent_fee)?;
-        amount_for_one_task
-            .native
-            .find_checked_add(&coin(price, &cfg.native_denom))?;
+        // Only the NFTs of the task owner can be moved
+        if !owns_nfts(&deps.querier, deps.api, owner_id, &task.actions)? {
+            return Err(ContractError::Unauthorized {});
+        }
+        let gas_price = reserved_gas_price(cfg, task.max_gas_price)?;
+        let price = agent_fee_price(cfg, &gas_price, task.fee_token.as_ref(), gas_amount)?;
+        amount_for_one_task.checked_add_generic(&price)?;
 
         //ToDo: Change this method as env.contract.address does not exist in testing env
         let version = self
-            .query_contract_info(deps.as_ref(), env.contract.address.to_string())
+            .query_contract_info(deps, env.contract.address.to_string())
             .unwrap_or(cw2::ContractVersion {
                 contract: "test".to_string(),
                 version: "1.0.0".to_string(),
             });
-        let item = Task {
+        let item = GenericTask {
             owner_id: owner_id.clone(),
             interval: task.interval,
             boundary,
             stop_on_fail: task.stop_on_fail,
-            total_deposit: GenericBalance {
-                native: info.funds.clone(),
-                cw20,
-            },
+            total_deposit,
             amount_for_one_task,
             actions: task.actions,
             queries: task.queries,
             transforms: task.transforms,
             version: version.version,
+            max_executions: task.max_executions,
+            executions: 0,
+            failed_executions: 0,
+            task_hash: None,
+            paused: false,
+            trigger: task.trigger,
+            callback_addr,
+            fee_token: task.fee_token,
+            max_gas_price: task.max_gas_price,
+            auto_refill: task.auto_refill,
+        };
+        Ok((item, gas_amount))
+    }
+
+    /// Allows any user or contract to pay for future txns based on a specific schedule
+    /// contract, function id & other settings. When the task runs out of balance
+    /// the task is no longer executed, any additional funds will be returned to task owner.
+    pub fn create_task(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        env: Env,
+        task: GenericTaskRequest<T>,
+    ) -> Result<Response<T>, ContractError> {
+        // Tasks paying the agent fee with cw20 tokens may have no native deposit
+        if info.funds.is_empty() && task.fee_token.is_none() {
+            return Err(ContractError::CustomError {
+                val: "Must attach funds".to_string(),
+            });
+        }
+        let cfg: Config = self.config.load(deps.storage)?;
+        if cfg.paused {
+            return Err(ContractError::CustomError {
+                val: "Create task paused".to_string(),
+            });
+        }
+
+        let owner_id = &info.sender;
+        let cw20 = if !task.cw20_coins.is_empty() {
+            let cw20 = validate_cw20_coins(deps.api, &task.cw20_coins)?;
+            // update user balances
+            self.balances.update(
+                deps.storage,
+                owner_id,
+                |balances| -> Result<_, ContractError> {
+                    let mut balances = balances.unwrap_or_default();
+
+                    balances.checked_sub_coins(&cw20)?;
+                    Ok(balances)
+                },
+            )?;
+            cw20
+        } else {
+            vec![]
         };
+        let total_deposit = GenericBalance {
+            native: info.funds.clone(),
+            cw20,
+        };
+        let (item, _) =
+            self.task_from_request(deps.as_ref(), &env, &cfg, owner_id, task, total_deposit)?;
 
         // Check that balance is sufficient for 1 execution minimum
-        let recurring = item.interval != Interval::Once;
+        let recurring = !matches!(item.interval, Interval::Once | Interval::At(_))
+            && item.max_executions != Some(1);
         item.verify_enough_balances(recurring)?;
         // Add the attached balance into available_balance
         let cfg = self
@@ -328,14 +665,22 @@ impl<'a> CwCroncat<'a> {
         }
 
         let with_queries = item.with_queries();
+        let trigger = item.trigger.clone();
+        let mut created = TaskCreated {
+            task_hash: hash.clone(),
+            owner: owner_id.clone(),
+            with_queries,
+            slot_id: None,
+            slot_kind: None,
+            trigger_task_hash: None,
+        };
+        let mut events = vec![];
         // Add task to catalog
         if with_queries {
             // Add task with queries
             self.tasks_with_queries
                 .update(deps.storage, hash.as_bytes(), |old| match old {
-                    Some(_) => Err(ContractError::CustomError {
-                        val: "Task already exists".to_string(),
-                    }),
+                    Some(old) => Err(task_exists_error(&old)),
                     None => Ok(item.clone()),
                 })?;
 
@@ -348,23 +693,14 @@ impl<'a> CwCroncat<'a> {
             }
 
             // Based on slot kind, put into block or cron slots
-            match slot_kind {
-                SlotType::Block => {
-                    self.block_map_queries
-                        .save(deps.storage, hash.as_bytes(), &next_id)?;
-                }
-                SlotType::Cron => {
-                    self.time_map_queries
-                        .save(deps.storage, hash.as_bytes(), &next_id)?;
-                }
+            if trigger.is_none() {
+                self.schedule_task(deps.storage, hash.as_bytes(), true, next_id, slot_kind)?;
             }
         } else {
             // Add task without queries
             let hash = item.to_hash_vec(hash_prefix);
             self.tasks.update(deps.storage, &hash, |old| match old {
-                Some(_) => Err(ContractError::CustomError {
-                    val: "Task already exists".to_string(),
-                }),
+                Some(old) => Err(task_exists_error(&old)),
                 None => Ok(item),
             })?;
 
@@ -390,41 +726,44 @@ impl<'a> CwCroncat<'a> {
                 if begin.is_none() {
                     self.agent_nomination_begin_time
                         .save(deps.storage, &Some(env.block.time))?;
+                    if let Some(agent) = self.agent_pending_queue.front(deps.storage)? {
+                        events.push(AgentNominated { agent }.into());
+                    }
                 }
             }
 
             // Get previous task hashes in slot, add as needed
-            let update_vec_data = |d: Option<Vec<Vec<u8>>>| -> StdResult<Vec<Vec<u8>>> {
-                match d {
-                    // has some data, simply push new hash
-                    Some(data) => {
-                        let mut s = data;
-                        s.push(hash);
-                        Ok(s)
-                    }
-                    // No data, push new vec & hash
-                    None => Ok(vec![hash]),
-                }
-            };
-            match slot_kind {
-                SlotType::Block => {
-                    self.block_slots
-                        .update(deps.storage, next_id, update_vec_data)?;
-                }
-                SlotType::Cron => {
-                    self.time_slots
-                        .update(deps.storage, next_id, update_vec_data)?;
-                }
+            if trigger.is_none() {
+                self.schedule_task(deps.storage, &hash, false, next_id, slot_kind)?;
             }
         };
 
-        Ok(Response::new()
+        // History of an earlier task with the same hash doesn't belong to this one
+        self.clear_task_history(deps.storage, hash.as_bytes())?;
+
+        let res = Response::new()
             .set_data(hash.as_bytes())
-            .add_attribute("method", "create_task")
-            .add_attribute("slot_id", next_id.to_string())
-            .add_attribute("slot_kind", format!("{:?}", slot_kind))
+            .add_attribute("method", "create_task");
+        let res = if let Some(trigger) = trigger {
+            // Scheduled once the parent task completes
+            self.task_dependents.save(
+                deps.storage,
+                (trigger.task_hash.as_bytes(), hash.as_bytes()),
+                &trigger.condition,
+            )?;
+            created.trigger_task_hash = Some(trigger.task_hash.clone());
+            res.add_attribute("trigger_task_hash", trigger.task_hash)
+        } else {
+            created.slot_id = Some(next_id);
+            created.slot_kind = Some(slot_kind);
+            res.add_attribute("slot_id", next_id.to_string())
+                .add_attribute("slot_kind", format!("{:?}", slot_kind))
+        };
+        Ok(res
             .add_attribute("task_hash", hash)
-            .add_attribute("with_queries", with_queries.to_string()))
+            .add_attribute("with_queries", with_queries.to_string())
+            .add_event(created.into())
+            .add_events(events))
     }
 
     /// Deletes a task in its entirety, returning any remaining balance to task owner.
@@ -433,7 +772,10 @@ impl<'a> CwCroncat<'a> {
         storage: &mut dyn Storage,
         task_hash: &str,
         info: Option<MessageInfo>,
-    ) -> Result<Response, ContractError> {
+    ) -> Result<Response<T>, ContractError> {
+        if info.is_some() {
+            self.check_no_task_migration(storage)?;
+        }
         let hash_vec = task_hash.as_bytes().to_vec();
         let some_task = self.tasks.may_load(storage, &hash_vec)?;
 
@@ -448,50 +790,57 @@ impl<'a> CwCroncat<'a> {
             self.tasks.remove(storage, &hash_vec)?;
 
             // find any scheduled things and remove them!
-            // check which type of slot  it would be in, then iterate to remove
-            // NOTE: def could use some spiffy refactor here
-            let time_ids: Vec<u64> = self
-                .time_slots
-                .keys(storage, None, None, Order::Ascending)
-                .collect::<StdResult<Vec<_>>>()?;
-
-            for tid in time_ids {
-                let mut time_hashes = self.time_slots.may_load(storage, tid)?.unwrap_or_default();
-                if !time_hashes.is_empty() {
-                    time_hashes.retain(|h| h != &hash_vec);
-                }
-
-                // save the updates, remove if slot no longer has hashes
-                if time_hashes.is_empty() {
-                    self.time_slots.remove(storage, tid);
-                } else {
-                    self.time_slots.save(storage, tid, &time_hashes)?;
-                }
-            }
-            let block_ids: Vec<u64> = self
-                .block_slots
-                .keys(storage, None, None, Order::Ascending)
-                .collect::<StdResult<Vec<_>>>()?;
-
-            for bid in block_ids {
-                let mut block_hashes = self.block_slots.may_load(storage, bid)?.unwrap_or_default();
-                if !block_hashes.is_empty() {
-                    block_hashes.retain(|h| h != &hash_vec);
-                }
-
-                // save the updates, remove if slot no longer has hashes
-                if block_hashes.is_empty() {
-                    self.block_slots.remove(storage, bid);
-                } else {
-                    self.block_slots.save(storage, bid, &block_hashes)?;
-                }
-            }
+            self.remove_from_slots(storage, &hash_vec)?;
             task
         } else {
             // Find a task with queries
             self.pop_task_with_queries(storage, hash_vec, info)?
         };
 
+        // Unlink the task from its parent, dependent tasks won't get triggered anymore
+        let hash = task_hash.as_bytes();
+        if let Some(trigger) = &task.trigger {
+            self.task_dependents
+                .remove(storage, (trigger.task_hash.as_bytes(), hash));
+        }
+        let dependents = self
+            .task_dependents
+            .prefix(hash)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut ended_dependents = Vec::new();
+        for dependent in dependents {
+            self.task_dependents.remove(storage, (hash, &dependent));
+            // Triggered ones still run, the others would wait forever with their deposit
+            let dependent_task = self.get_task_by_hash(storage, &dependent)?;
+            if self.is_scheduled(storage, &dependent, dependent_task.with_queries())? {
+                continue;
+            }
+            let dependent_hash = String::from_utf8_lossy(&dependent).to_string();
+            let ended = TaskEnded {
+                task_hash: dependent_hash.clone(),
+                owner: dependent_task.owner_id,
+                reason: TaskEndReason::ParentEnded,
+                error: None,
+            };
+            let mut resp = self.remove_task(storage, &dependent_hash, None)?;
+            // Ended goes before the removal of the task
+            resp.events.insert(0, ended.into());
+            ended_dependents.push(resp);
+        }
+        // Only the last execution stays queryable after the task ends
+        let history = self
+            .task_history
+            .prefix(hash)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let last_execution = history.iter().map(|(_, record)| record.execution).max();
+        for (idx, record) in history {
+            if Some(record.execution) != last_execution {
+                self.task_history.remove(storage, (hash, idx));
+            }
+        }
+
         // return any remaining total_cw20_deposit to the owner
         self.balances.update(
             storage,
@@ -509,17 +858,26 @@ impl<'a> CwCroncat<'a> {
                     .checked_sub_native(&task.total_deposit.native)?;
                 Ok(c)
             })?;
+        let removed = TaskRemoved {
+            task_hash: task_hash.to_string(),
+            owner: task.owner_id.clone(),
+            refund_native: task.total_deposit.native.clone(),
+            refund_cw20: task.total_deposit.cw20,
+        };
+        let mut res = Response::new()
+            .add_attribute("method", "remove_task")
+            .add_event(removed.into());
         // setup sub-msgs for returning any remaining total_deposit to the owner
         if !task.total_deposit.native.is_empty() {
-            Ok(Response::new()
-                .add_attribute("method", "remove_task")
-                .add_submessage(SubMsg::new(BankMsg::Send {
-                    to_address: task.owner_id.into(),
-                    amount: task.total_deposit.native,
-                })))
-        } else {
-            Ok(Response::new().add_attribute("method", "remove_task"))
+            res = res.add_submessage(SubMsg::new(BankMsg::Send {
+                to_address: task.owner_id.into(),
+                amount: task.total_deposit.native,
+            }));
+        }
+        for resp in ended_dependents {
+            res = res.add_submessages(resp.messages).add_events(resp.events);
         }
+        Ok(res)
     }
 
     fn pop_task_with_queries(
@@ -527,7 +885,7 @@ impl<'a> CwCroncat<'a> {
         storage: &mut dyn Storage,
         hash_vec: Vec<u8>,
         info: Option<MessageInfo>,
-    ) -> Result<Task, ContractError> {
+    ) -> Result<GenericTask<T>, ContractError> {
         let task = self
             .tasks_with_queries
             .may_load(storage, &hash_vec)?
@@ -539,12 +897,346 @@ impl<'a> CwCroncat<'a> {
         }
         self.tasks_with_queries.remove(storage, &hash_vec)?;
         match task.interval {
-            Interval::Cron(_) => self.time_map_queries.remove(storage, &hash_vec),
+            Interval::Cron(_)
+            | Interval::CronTz { .. }
+            | Interval::At(_)
+            | Interval::Every { .. } => self.time_map_queries.remove(storage, &hash_vec),
             _ => self.block_map_queries.remove(storage, &hash_vec),
         }
         Ok(task)
     }
 
+    /// Put the task hash into the slot, or into the queries map for tasks with queries
+    pub(crate) fn schedule_task(
+        &self,
+        storage: &mut dyn Storage,
+        hash: &[u8],
+        with_queries: bool,
+        next_id: u64,
+        slot_kind: SlotType,
+    ) -> StdResult<()> {
+        if with_queries {
+            return match slot_kind {
+                SlotType::Block => self.block_map_queries.save(storage, hash, &next_id),
+                SlotType::Cron => self.time_map_queries.save(storage, hash, &next_id),
+            };
+        }
+        let update_vec_data = |d: Option<Vec<Vec<u8>>>| -> StdResult<Vec<Vec<u8>>> {
+            match d {
+                // has some data, simply push new hash
+                Some(data) => {
+                    let mut s = data;
+                    s.push(hash.to_vec());
+                    Ok(s)
+                }
+                // No data, push new vec & hash
+                None => Ok(vec![hash.to_vec()]),
+            }
+        };
+        match slot_kind {
+            SlotType::Block => self.block_slots.update(storage, next_id, update_vec_data)?,
+            SlotType::Cron => self.time_slots.update(storage, next_id, update_vec_data)?,
+        };
+        Ok(())
+    }
+
+    /// Take the task hash out of the slots, or out of the queries map for tasks with queries
+    fn unschedule_task(
+        &self,
+        storage: &mut dyn Storage,
+        hash: &[u8],
+        with_queries: bool,
+    ) -> StdResult<()> {
+        if with_queries {
+            self.time_map_queries.remove(storage, hash);
+            self.block_map_queries.remove(storage, hash);
+            Ok(())
+        } else {
+            self.remove_from_slots(storage, hash)
+        }
+    }
+
+    /// Remove every execution record of the task hash
+    /// Returns the amount of removed records
+    pub(crate) fn clear_task_history(
+        &self,
+        storage: &mut dyn Storage,
+        hash: &[u8],
+    ) -> StdResult<u64> {
+        let history = self
+            .task_history
+            .prefix(hash)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for idx in history.iter() {
+            self.task_history.remove(storage, (hash, *idx));
+        }
+        Ok(history.len() as u64)
+    }
+
+    /// Whether the task hash waits in a slot, or in the queries map for tasks with queries
+    pub(crate) fn is_scheduled(
+        &self,
+        storage: &dyn Storage,
+        hash: &[u8],
+        with_queries: bool,
+    ) -> StdResult<bool> {
+        if with_queries {
+            return Ok(self.time_map_queries.has(storage, hash)
+                || self.block_map_queries.has(storage, hash));
+        }
+        for slots in [&self.time_slots, &self.block_slots] {
+            for slot in slots.range(storage, None, None, Order::Ascending) {
+                let (_, hashes) = slot?;
+                if hashes.iter().any(|h| h == hash) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Save the task into the map it belongs to
+    fn save_task(
+        &self,
+        storage: &mut dyn Storage,
+        hash: &[u8],
+        task: &GenericTask<T>,
+    ) -> StdResult<()> {
+        if task.with_queries() {
+            self.tasks_with_queries.save(storage, hash, task)
+        } else {
+            self.tasks.save(storage, hash, task)
+        }
+    }
+
+    /// Remove the task hash from every block and time slot
+    /// Only the slots holding the hash get saved, emptied slots are removed
+    fn remove_from_slots(&self, storage: &mut dyn Storage, hash_vec: &[u8]) -> StdResult<()> {
+        for slots in [&self.time_slots, &self.block_slots] {
+            let found = slots
+                .range(storage, None, None, Order::Ascending)
+                .filter(|slot| {
+                    slot.as_ref()
+                        .map_or(true, |(_, hashes)| hashes.iter().any(|h| h == hash_vec))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            for (slot_id, mut hashes) in found {
+                hashes.retain(|h| h != hash_vec);
+                // save the updates, remove if slot no longer has hashes
+                if hashes.is_empty() {
+                    slots.remove(storage, slot_id);
+                } else {
+                    slots.save(storage, slot_id, &hashes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Update scheduling and gas limits of a task in place
+    /// The task keeps its hash, attached funds are added to the task deposit
+    /// NOTE: Restricting this to owner only
+    pub fn update_task(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        env: Env,
+        payload: GenericExecuteMsg<T>,
+    ) -> Result<Response<T>, ContractError> {
+        let (task_hash, interval, boundary, stop_on_fail, gas_limits) = match payload {
+            GenericExecuteMsg::UpdateTask {
+                task_hash,
+                interval,
+                boundary,
+                stop_on_fail,
+                gas_limits,
+            } => (task_hash, interval, boundary, stop_on_fail, gas_limits),
+            _ => unreachable!(),
+        };
+        let mut cfg: Config = self.config.load(deps.storage)?;
+        if cfg.paused {
+            return Err(ContractError::CustomError {
+                val: "Update task paused".to_string(),
+            });
+        }
+
+        let hash_vec = task_hash.clone().into_bytes();
+        let mut task = self.get_task_by_hash(deps.storage, &hash_vec)?;
+        if !task.is_owner(info.sender.clone()) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // Keep the old boundary, unless a new one is provided
+        let boundary = match boundary {
+            Some(boundary) => Some(boundary),
+            None if task.boundary.start.is_none() && task.boundary.end.is_none() => None,
+            None => task.boundary.to_boundary(&task.interval),
+        };
+        let mut actions = task.actions.clone();
+        if let Some(gas_limits) = gas_limits {
+            if gas_limits.len() != actions.len() {
+                return Err(ContractError::CustomError {
+                    val: "Gas limits invalid".to_string(),
+                });
+            }
+            for (action, gas_limit) in actions.iter_mut().zip(gas_limits) {
+                action.gas_limit = gas_limit;
+            }
+        }
+        let request = GenericTaskRequest {
+            interval: interval.unwrap_or_else(|| task.interval.clone()),
+            boundary,
+            stop_on_fail: stop_on_fail.unwrap_or(task.stop_on_fail),
+            actions,
+            queries: task.queries.clone(),
+            transforms: task.transforms.clone(),
+            cw20_coins: vec![],
+            max_executions: task.max_executions,
+            trigger: task.trigger.clone(),
+            callback_addr: task.callback_addr.as_ref().map(ToString::to_string),
+            fee_token: task.fee_token.clone(),
+            max_gas_price: task.max_gas_price,
+            auto_refill: task.auto_refill.clone(),
+        };
+        // Updated task goes through the same checks as a new one
+        let (updated, _) = self.task_from_request(
+            deps.as_ref(),
+            &env,
+            &cfg,
+            &task.owner_id,
+            request,
+            GenericBalance::default(),
+        )?;
+        task.interval = updated.interval;
+        task.boundary = updated.boundary;
+        task.stop_on_fail = updated.stop_on_fail;
+        task.actions = updated.actions;
+        task.amount_for_one_task = updated.amount_for_one_task;
+        task.total_deposit.checked_add_native(&info.funds)?;
+
+        // Check that balance is sufficient for 1 execution minimum
+        let recurring = !matches!(task.interval, Interval::Once | Interval::At(_))
+            && task.remaining_executions() != Some(1);
+        task.verify_enough_balances(recurring)?;
+        cfg.available_balance.checked_add_native(&info.funds)?;
+        self.config.save(deps.storage, &cfg)?;
+
+        let (next_id, slot_kind) =
+            task.interval
+                .next(&env, task.boundary, cfg.slot_granularity_time);
+        if next_id == 0 {
+            return Err(ContractError::CustomError {
+                val: "Task ended".to_string(),
+            });
+        }
+
+        // Keep the hash the task was created with
+        task.task_hash = Some(task_hash.clone());
+        let with_queries = task.with_queries();
+        self.unschedule_task(deps.storage, &hash_vec, with_queries)?;
+        self.save_task(deps.storage, &hash_vec, &task)?;
+        // Paused task gets scheduled once resumed, triggered task once the parent completes
+        if !task.paused && task.trigger.is_none() {
+            self.schedule_task(deps.storage, &hash_vec, with_queries, next_id, slot_kind)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "update_task")
+            .add_attribute("slot_id", next_id.to_string())
+            .add_attribute("slot_kind", format!("{:?}", slot_kind))
+            .add_attribute("task_hash", task_hash))
+    }
+
+    /// Pause a task, taking it out of the schedule
+    /// The task keeps its hash and deposit until resumed or removed
+    /// NOTE: Restricting this to owner only
+    pub fn pause_task(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        task_hash: String,
+    ) -> Result<Response<T>, ContractError> {
+        self.check_no_task_migration(deps.storage)?;
+        let hash_vec = task_hash.clone().into_bytes();
+        let mut task = self.get_task_by_hash(deps.storage, &hash_vec)?;
+        if !task.is_owner(info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if task.paused {
+            return Err(ContractError::CustomError {
+                val: "Task already paused".to_string(),
+            });
+        }
+
+        task.paused = true;
+        self.unschedule_task(deps.storage, &hash_vec, task.with_queries())?;
+        self.save_task(deps.storage, &hash_vec, &task)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "pause_task")
+            .add_attribute("task_hash", task_hash))
+    }
+
+    /// Resume a paused task, scheduling it at the next valid slot
+    /// NOTE: Restricting this to owner only
+    pub fn resume_task(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        env: Env,
+        task_hash: String,
+    ) -> Result<Response<T>, ContractError> {
+        self.check_no_task_migration(deps.storage)?;
+        let hash_vec = task_hash.clone().into_bytes();
+        let mut task = self.get_task_by_hash(deps.storage, &hash_vec)?;
+        if !task.is_owner(info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if !task.paused {
+            return Err(ContractError::CustomError {
+                val: "Task not paused".to_string(),
+            });
+        }
+
+        task.paused = false;
+        self.save_task(deps.storage, &hash_vec, &task)?;
+        let res = Response::new().add_attribute("method", "resume_task");
+        // Triggered task waits for the parent task to complete
+        if task.trigger.is_some() {
+            return Ok(res.add_attribute("task_hash", task_hash));
+        }
+
+        let cfg: Config = self.config.load(deps.storage)?;
+        let (next_id, slot_kind) =
+            task.interval
+                .next(&env, task.boundary, cfg.slot_granularity_time);
+        if next_id == 0 {
+            return Err(ContractError::CustomError {
+                val: "Task ended".to_string(),
+            });
+        }
+        self.schedule_task(
+            deps.storage,
+            &hash_vec,
+            task.with_queries(),
+            next_id,
+            slot_kind,
+        )?;
+
+        let rescheduled = TaskRescheduled {
+            task_hash: task_hash.clone(),
+            owner: task.owner_id,
+            slot_id: next_id,
+            slot_kind,
+        };
+        Ok(res
+            .add_attribute("slot_id", next_id.to_string())
+            .add_attribute("slot_kind", format!("{:?}", slot_kind))
+            .add_attribute("task_hash", task_hash)
+            .add_event(rescheduled.into()))
+    }
+
     /// Refill a task with more balance to continue its execution
     /// NOTE: Restricting this to owner only, so owner can make sure the task ends
     pub fn refill_task(
@@ -552,8 +1244,8 @@ impl<'a> CwCroncat<'a> {
         deps: DepsMut,
         info: MessageInfo,
         task_hash: String,
-    ) -> Result<Response, ContractError> {
-        let hash_vec = task_hash.into_bytes();
+    ) -> Result<Response<T>, ContractError> {
+        let hash_vec = task_hash.clone().into_bytes();
         let mut task = self
             .tasks
             .may_load(deps.storage, &hash_vec)?
@@ -579,9 +1271,17 @@ impl<'a> CwCroncat<'a> {
             .iter()
             .map(ToString::to_string)
             .collect();
+        let refilled = TaskRefilled {
+            task_hash,
+            owner: task.owner_id,
+            native: info.funds,
+            cw20: vec![],
+            auto_refill: false,
+        };
         Ok(Response::new()
             .add_attribute("method", "refill_task")
-            .add_attribute("total_deposit", format!("{coins_total:?}")))
+            .add_attribute("total_deposit", format!("{coins_total:?}"))
+            .add_event(refilled.into()))
     }
 
     /// Refill a task with more cw20 balance from user `balance` to continue its execution
@@ -592,8 +1292,8 @@ impl<'a> CwCroncat<'a> {
         info: MessageInfo,
         task_hash: String,
         cw20_coins: Vec<Cw20Coin>,
-    ) -> Result<Response, ContractError> {
-        let task_hash = task_hash.into_bytes();
+    ) -> Result<Response<T>, ContractError> {
+        let hash_vec = task_hash.clone().into_bytes();
         let cw20_coins_validated = {
             let mut validated = Vec::with_capacity(cw20_coins.len());
             for coin in cw20_coins {
@@ -604,7 +1304,7 @@ impl<'a> CwCroncat<'a> {
             }
             validated
         };
-        let task = self.tasks.update(deps.storage, &task_hash, |task| {
+        let task = self.tasks.update(deps.storage, &hash_vec, |task| {
             let mut task = task.ok_or(ContractError::NoTaskFound {})?;
             if task.owner_id != info.sender {
                 return Err(ContractError::RefillNotTaskOwner {});
@@ -625,8 +1325,6 @@ impl<'a> CwCroncat<'a> {
             },
         )?;
 
-        // used `update` here to not clone task_hash
-
         let total_cw20_string: Vec<String> = task
             .total_deposit
             .cw20
@@ -634,9 +1332,61 @@ impl<'a> CwCroncat<'a> {
             .map(ToString::to_string)
             .collect();
 
+        let refilled = TaskRefilled {
+            task_hash,
+            owner: task.owner_id,
+            native: vec![],
+            cw20: cw20_coins_validated,
+            auto_refill: false,
+        };
         Ok(Response::new()
             .add_attribute("method", "refill_task_cw20")
-            .add_attribute("total_cw20_deposit", format!("{total_cw20_string:?}")))
+            .add_attribute("total_cw20_deposit", format!("{total_cw20_string:?}"))
+            .add_event(refilled.into()))
+    }
+
+    /// Top up the task deposit from the owner wallet balances, if its auto refill policy asks for it
+    /// Nothing is taken unless the wallet balances cover all the refilled executions
+    /// Returns the refilled amount, if the task got refilled
+    pub(crate) fn auto_refill_task(
+        &self,
+        storage: &mut dyn Storage,
+        task: &mut GenericTask<T>,
+    ) -> Result<Option<GenericBalance>, ContractError> {
+        let auto_refill = match &task.auto_refill {
+            Some(auto_refill) => auto_refill,
+            None => return Ok(None),
+        };
+        // No need to cover the executions the task won't have
+        let min_executions = task
+            .remaining_executions()
+            .map_or(auto_refill.min_executions, |remaining| {
+                remaining.min(auto_refill.min_executions)
+            });
+        if task.covered_executions() >= u128::from(min_executions) {
+            return Ok(None);
+        }
+        let refill = task.amount_for_executions(auto_refill.refill_executions)?;
+
+        let mut native_balances = self
+            .native_balances
+            .may_load(storage, &task.owner_id)?
+            .unwrap_or_default();
+        let mut cw20_balances = self
+            .balances
+            .may_load(storage, &task.owner_id)?
+            .unwrap_or_default();
+        if native_balances.checked_sub_coins(&refill.native).is_err()
+            || cw20_balances.checked_sub_coins(&refill.cw20).is_err()
+        {
+            return Ok(None);
+        }
+        self.native_balances
+            .save(storage, &task.owner_id, &native_balances)?;
+        self.balances
+            .save(storage, &task.owner_id, &cw20_balances)?;
+        task.total_deposit.checked_add_generic(&refill)?;
+        Ok(Some(refill))
     }
 
     /// Let users withdraw their balances
@@ -645,7 +1395,8 @@ impl<'a> CwCroncat<'a> {
         deps: DepsMut,
         info: MessageInfo,
         cw20_amounts: Vec<Cw20Coin>,
-    ) -> Result<Response, ContractError> {
+        native_amounts: Vec<Coin>,
+    ) -> Result<Response<T>, ContractError> {
         let wallet = info.sender;
         let withdraws: Vec<Cw20CoinVerified> = {
             let mut withdraws = Vec::with_capacity(cw20_amounts.len());
@@ -659,22 +1410,45 @@ impl<'a> CwCroncat<'a> {
         };
 
         // update user and croncat manager balances
-        let new_balances = self.balances.update(
-            deps.storage,
-            &wallet,
-            |balances| -> Result<_, ContractError> {
-                let mut balances =
-                    balances.ok_or(ContractError::CoreError(CoreError::EmptyBalance {}))?;
-                balances.checked_sub_coins(&withdraws)?;
-                Ok(balances)
-            },
-        )?;
+        let new_balances = if withdraws.is_empty() {
+            self.balances
+                .may_load(deps.storage, &wallet)?
+                .unwrap_or_default()
+        } else {
+            self.balances.update(
+                deps.storage,
+                &wallet,
+                |balances| -> Result<_, ContractError> {
+                    let mut balances =
+                        balances.ok_or(ContractError::CoreError(CoreError::EmptyBalance {}))?;
+                    balances.checked_sub_coins(&withdraws)?;
+                    Ok(balances)
+                },
+            )?
+        };
+        if !native_amounts.is_empty() {
+            self.native_balances.update(
+                deps.storage,
+                &wallet,
+                |balances| -> Result<_, ContractError> {
+                    let mut balances =
+                        balances.ok_or(ContractError::CoreError(CoreError::EmptyBalance {}))?;
+                    balances.checked_sub_coins(&native_amounts)?;
+                    Ok(balances)
+                },
+            )?;
+        }
         self.config
             .update(deps.storage, |mut c| -> Result<_, ContractError> {
                 c.available_balance.checked_sub_cw20(&withdraws)?;
+                c.available_balance.checked_sub_native(&native_amounts)?;
                 Ok(c)
             })?;
 
+        let native_msg = (!native_amounts.is_empty()).then(|| BankMsg::Send {
+            to_address: wallet.to_string(),
+            amount: native_amounts,
+        });
         let msgs = {
             let mut msgs = Vec::with_capacity(withdraws.len());
             for wd in withdraws {
@@ -695,6 +1469,236 @@ impl<'a> CwCroncat<'a> {
         Ok(Response::new()
             .add_attribute("method", "withdraw_wallet_balances")
             .add_attribute("total_cw20_deposit", format!("{new_balances_string:?}"))
-            .add_messages(msgs))
+            .add_messages(msgs)
+            .add_messages(native_msg))
+    }
+
+    /// Slots still refer to the old hashes of the tasks until the migration is done
+    fn check_no_task_migration(&self, storage: &dyn Storage) -> Result<(), ContractError> {
+        if self.task_migration.may_load(storage)?.is_some() {
+            return Err(ContractError::CustomError {
+                val: "Task migration in progress".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Migrates the next page of tasks, slots or renames, see `TaskMigrationStage`
+    /// Unpauses the contract back once every task is migrated
+    pub fn migrate_tasks(
+        &self,
+        deps: DepsMut,
+        limit: Option<u64>,
+    ) -> Result<Response<T>, ContractError> {
+        let migration = self.task_migration.may_load(deps.storage)?.ok_or_else(|| {
+            ContractError::CustomError {
+                val: "No task migration in progress".to_string(),
+            }
+        })?;
+        let mut cfg: Config = self.config.load(deps.storage)?;
+        let limit = page_limit(limit, cfg.limit);
+
+        let (migrated, next_stage) = match migration.stage {
+            TaskMigrationStage::Tasks {
+                with_queries,
+                start_after,
+            } => {
+                let tasks = if with_queries {
+                    &self.tasks_with_queries
+                } else {
+                    &self.tasks
+                };
+                let start = start_after.map(Bound::ExclusiveRaw);
+                let hashes = tasks
+                    .keys(deps.storage, start, None, Order::Ascending)
+                    .take(limit)
+                    .collect::<StdResult<Vec<Vec<u8>>>>()?;
+                for hash in hashes.iter() {
+                    self.rekey_task(deps.storage, &cfg.chain_name, hash, with_queries)?;
+                }
+                let next_stage = match hashes.last() {
+                    Some(hash) if hashes.len() == limit => Some(TaskMigrationStage::Tasks {
+                        with_queries,
+                        start_after: Some(hash.clone()),
+                    }),
+                    _ if !with_queries => Some(TaskMigrationStage::Tasks {
+                        with_queries: true,
+                        start_after: None,
+                    }),
+                    _ => Some(TaskMigrationStage::Slots {
+                        slot_kind: SlotType::Block,
+                        start_after: None,
+                    }),
+                };
+                (hashes.len(), next_stage)
+            }
+            TaskMigrationStage::Slots {
+                slot_kind,
+                start_after,
+            } => {
+                let slots = match slot_kind {
+                    SlotType::Block => &self.block_slots,
+                    SlotType::Cron => &self.time_slots,
+                };
+                let stored = slots
+                    .range(
+                        deps.storage,
+                        start_after.map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .collect::<StdResult<Vec<(u64, Vec<Vec<u8>>)>>>()?;
+                for (slot, hashes) in stored.iter() {
+                    let mut renamed = Vec::with_capacity(hashes.len());
+                    for hash in hashes {
+                        match self.task_renames.may_load(deps.storage, hash)? {
+                            Some(new_hash) => {
+                                self.task_renames.remove(deps.storage, hash);
+                                renamed.push(new_hash);
+                            }
+                            None => renamed.push(hash.clone()),
+                        }
+                    }
+                    if &renamed != hashes {
+                        slots.save(deps.storage, *slot, &renamed)?;
+                    }
+                }
+                let next_stage = match stored.last() {
+                    Some((slot, _)) if stored.len() == limit => Some(TaskMigrationStage::Slots {
+                        slot_kind,
+                        start_after: Some(*slot),
+                    }),
+                    _ if slot_kind == SlotType::Block => Some(TaskMigrationStage::Slots {
+                        slot_kind: SlotType::Cron,
+                        start_after: None,
+                    }),
+                    _ => Some(TaskMigrationStage::Cleanup),
+                };
+                (stored.len(), next_stage)
+            }
+            TaskMigrationStage::Cleanup => {
+                let hashes = self
+                    .task_renames
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .take(limit)
+                    .collect::<StdResult<Vec<Vec<u8>>>>()?;
+                for hash in hashes.iter() {
+                    self.task_renames.remove(deps.storage, hash);
+                }
+                let next_stage = (hashes.len() == limit).then_some(TaskMigrationStage::Cleanup);
+                (hashes.len(), next_stage)
+            }
+        };
+
+        let done = next_stage.is_none();
+        match next_stage {
+            Some(stage) => self.task_migration.save(
+                deps.storage,
+                &TaskMigration {
+                    paused: migration.paused,
+                    stage,
+                },
+            )?,
+            None => {
+                self.task_migration.remove(deps.storage);
+                self.task_hash_version
+                    .save(deps.storage, &TASK_HASH_VERSION.to_string())?;
+                cfg.paused = migration.paused;
+                self.config.save(deps.storage, &cfg)?;
+            }
+        }
+
+        Ok(Response::new()
+            .add_attribute("method", "migrate_tasks")
+            .add_attribute("migrated", migrated.to_string())
+            .add_attribute("done", done.to_string()))
+    }
+
+    /// Moves the task under its current hash, together with its slot, history and triggers
+    /// Tasks without queries keep the old hash in their slot until the slots stage renames it
+    /// Saving the task again also fills the indexes added after it was created
+    fn rekey_task(
+        &self,
+        storage: &mut dyn Storage,
+        prefix: &str,
+        old_hash: &[u8],
+        with_queries: bool,
+    ) -> StdResult<()> {
+        let tasks = if with_queries {
+            &self.tasks_with_queries
+        } else {
+            &self.tasks
+        };
+        let task = tasks.load(storage, old_hash)?;
+        let new_hash = task.to_hash_vec(prefix);
+        if new_hash == old_hash {
+            return tasks.save(storage, old_hash, &task);
+        }
+        tasks.remove(storage, old_hash)?;
+        tasks.save(storage, &new_hash, &task)?;
+
+        if with_queries {
+            for map_queries in [&self.time_map_queries, &self.block_map_queries] {
+                if let Some(slot) = map_queries.may_load(storage, old_hash)? {
+                    map_queries.remove(storage, old_hash);
+                    map_queries.save(storage, &new_hash, &slot)?;
+                }
+            }
+        } else {
+            self.task_renames.save(storage, old_hash, &new_hash)?;
+        }
+
+        let history = self
+            .task_history
+            .prefix(old_hash)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (execution, record) in history {
+            self.task_history.remove(storage, (old_hash, execution));
+            self.task_history
+                .save(storage, (&new_hash, execution), &record)?;
+        }
+
+        if let Some(trigger) = &task.trigger {
+            let parent = trigger.task_hash.as_bytes();
+            if let Some(condition) = self.task_dependents.may_load(storage, (parent, old_hash))? {
+                self.task_dependents.remove(storage, (parent, old_hash));
+                self.task_dependents
+                    .save(storage, (parent, &new_hash), &condition)?;
+            }
+        }
+        let dependents = self
+            .task_dependents
+            .prefix(old_hash)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (dependent, condition) in dependents {
+            self.task_dependents.remove(storage, (old_hash, &dependent));
+            self.task_dependents
+                .save(storage, (&new_hash, &dependent), &condition)?;
+            for tasks in [&self.tasks, &self.tasks_with_queries] {
+                if let Some(mut dependent_task) = tasks.may_load(storage, &dependent)? {
+                    if let Some(trigger) = dependent_task.trigger.as_mut() {
+                        trigger.task_hash = task.to_hash(prefix);
+                    }
+                    tasks.save(storage, &dependent, &dependent_task)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Updated tasks keep the hash they were created with, so a new task with the
+/// original parameters can't take it until the updated task is removed
+fn task_exists_error<T>(existing: &GenericTask<T>) -> ContractError {
+    let val = if existing.task_hash.is_some() {
+        "Task already exists: an updated task keeps this hash"
+    } else {
+        "Task already exists"
+    };
+    ContractError::CustomError {
+        val: val.to_string(),
     }
 }
@@ -8,8 +8,8 @@ use cosmwasm_std::{
     StakingMsg, StdResult, Storage,
 };
 use cw_croncat_core::msg::{
-    AgentResponse, AgentTaskResponse, ExecuteMsg, GetAgentIdsResponse, InstantiateMsg, QueryMsg,
-    TaskRequest, TaskResponse,
+    AgentResponse, AgentTaskResponse, ExecuteMsg, GetAgentIdsResponse, GetTasksResponse,
+    InstantiateMsg, QueryMsg, TaskRequest, TaskResponse,
 };
 use cw_croncat_core::types::{Action, Agent, AgentStatus, GasPrice, GenericBalance, Interval};
 use cw_multi_test::{App, AppResponse, BankSudo, Executor, SudoMsg};
@@ -23,14 +23,15 @@ use super::helpers::{
 fn get_task_total(app: &App, contract_addr: &Addr) -> usize {
     let res: Vec<TaskResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksResponse>(
             contract_addr,
             &QueryMsg::GetTasks {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     res.len()
 }
 
@@ -55,6 +56,12 @@ fn add_task_exec(app: &mut App, contract_addr: &Addr, sender: &str) -> AppRespon
                 queries: None,
                 transforms: None,
                 cw20_coins: vec![],
+                max_executions: None,
+                trigger: None,
+                callback_addr: None,
+                fee_token: None,
+                max_gas_price: None,
+                auto_refill: None,
             },
         },
         send_funds.as_ref(),
@@ -88,6 +95,12 @@ fn add_block_task_exec(
                 queries: None,
                 transforms: None,
                 cw20_coins: vec![],
+                max_executions: None,
+                trigger: None,
+                callback_addr: None,
+                fee_token: None,
+                max_gas_price: None,
+                auto_refill: None,
             },
         },
         send_funds.as_ref(),
@@ -121,6 +134,12 @@ fn add_cron_task_exec(
                 queries: None,
                 transforms: None,
                 cw20_coins: vec![],
+                max_executions: None,
+                trigger: None,
+                callback_addr: None,
+                fee_token: None,
+                max_gas_price: None,
+                auto_refill: None,
             },
         },
         send_funds.as_ref(),
@@ -155,6 +174,12 @@ fn contract_create_task(
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     )
 }
@@ -223,7 +248,13 @@ fn check_in_exec(
 fn get_agent_ids(app: &App, contract_addr: &Addr) -> (GetAgentIdsResponse, usize, usize) {
     let res: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr, &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr,
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     (res.clone(), res.active.len(), res.pending.len())
 }
@@ -261,6 +292,8 @@ fn test_instantiate_sets_balance() {
                 gas_wasm_query_fee: None,
                 gas_price: None,
                 agent_nomination_duration: None,
+                ibc_transfer_timeout: None,
+                gov_msgs_enabled: None,
                 gas_base_fee: None,
             },
             &sent_funds,
@@ -315,11 +348,17 @@ fn register_agent_fail_cases() {
         agents_eject_threshold: None,
         gas_price: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
         slot_granularity_time: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     app.execute_contract(
         Addr::unchecked(ADMIN),
@@ -354,11 +393,17 @@ fn register_agent_fail_cases() {
             gas_adjustment_numerator: 1,
         }),
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     app.execute_contract(
         Addr::unchecked(ADMIN),
@@ -622,7 +667,7 @@ fn accept_nomination_agent() {
     let res = add_task_exec(&mut app, &contract_addr, PARTICIPANT0);
     let task_hash = res.events[1].attributes[4].clone().value;
     assert_eq!(
-        "atom:bba87d76697192d8b7d4aa1224ea280b62777027f10c4f338c98bf31c2e", task_hash,
+        "atom:ae77fa52578d3db9ad52e9cadfe30e468b57d487058a5560c282f7d4914", task_hash,
         "Unexpected task hash"
     );
 
@@ -766,6 +811,8 @@ fn test_get_agent_status() {
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: Some(360),
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
         cw_rules_addr: "todo".to_string(),
         gas_base_fee: None,
     };
@@ -941,6 +988,56 @@ fn test_query_get_agent_tasks() {
     );
 }
 
+#[test]
+fn test_get_agent_ids_pagination() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    register_agent_exec(&mut app, &contract_addr, AGENT1, &AGENT_BENEFICIARY);
+    register_agent_exec(&mut app, &contract_addr, AGENT2, &AGENT_BENEFICIARY);
+    register_agent_exec(&mut app, &contract_addr, AGENT3, &AGENT_BENEFICIARY);
+    register_agent_exec(&mut app, &contract_addr, AGENT4, &AGENT_BENEFICIARY);
+
+    // Active agents go first
+    let agent_ids: GetAgentIdsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        agent_ids,
+        GetAgentIdsResponse {
+            active: vec![Addr::unchecked(AGENT1)],
+            pending: vec![Addr::unchecked(AGENT2)],
+            next_cursor: Some(1),
+        }
+    );
+
+    let agent_ids: GetAgentIdsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: agent_ids.next_cursor,
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        agent_ids,
+        GetAgentIdsResponse {
+            active: vec![],
+            pending: vec![Addr::unchecked(AGENT3), Addr::unchecked(AGENT4)],
+            next_cursor: None,
+        }
+    );
+}
+
 #[test]
 fn test_last_unregistered_active_agent_promotes_first_pending() {
     let (mut app, cw_template_contract, _) = proper_instantiate();
@@ -955,7 +1052,13 @@ fn test_last_unregistered_active_agent_promotes_first_pending() {
     // Check if one is active and rest is pending
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
@@ -965,7 +1068,8 @@ fn test_last_unregistered_active_agent_promotes_first_pending() {
                 Addr::unchecked(AGENT2),
                 Addr::unchecked(AGENT3),
                 Addr::unchecked(AGENT4)
-            ]
+            ],
+            next_cursor: None,
         }
     );
 
@@ -980,7 +1084,13 @@ fn test_last_unregistered_active_agent_promotes_first_pending() {
     .unwrap();
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
@@ -990,7 +1100,8 @@ fn test_last_unregistered_active_agent_promotes_first_pending() {
                 Addr::unchecked(AGENT2),
                 Addr::unchecked(AGENT3),
                 Addr::unchecked(AGENT4)
-            ]
+            ],
+            next_cursor: None,
         }
     );
 
@@ -1016,13 +1127,20 @@ fn test_last_unregistered_active_agent_promotes_first_pending() {
     .unwrap();
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
         GetAgentIdsResponse {
             active: vec![Addr::unchecked(AGENT2)],
-            pending: vec![Addr::unchecked(AGENT3), Addr::unchecked(AGENT4)]
+            pending: vec![Addr::unchecked(AGENT3), Addr::unchecked(AGENT4)],
+            next_cursor: None,
         }
     );
 }
@@ -1041,7 +1159,13 @@ fn removing_agent_from_any_side_is_working() {
 
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
@@ -1052,7 +1176,8 @@ fn removing_agent_from_any_side_is_working() {
                 Addr::unchecked(AGENT2),
                 Addr::unchecked(AGENT3),
                 Addr::unchecked(AGENT4)
-            ]
+            ],
+            next_cursor: None,
         }
     );
 
@@ -1067,7 +1192,13 @@ fn removing_agent_from_any_side_is_working() {
 
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
@@ -1077,7 +1208,8 @@ fn removing_agent_from_any_side_is_working() {
                 Addr::unchecked(AGENT1),
                 Addr::unchecked(AGENT3),
                 Addr::unchecked(AGENT4)
-            ]
+            ],
+            next_cursor: None,
         }
     );
 
@@ -1094,13 +1226,20 @@ fn removing_agent_from_any_side_is_working() {
 
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
         GetAgentIdsResponse {
             active: vec![Addr::unchecked(AGENT0)],
-            pending: vec![Addr::unchecked(AGENT1), Addr::unchecked(AGENT4)]
+            pending: vec![Addr::unchecked(AGENT1), Addr::unchecked(AGENT4)],
+            next_cursor: None,
         }
     );
 
@@ -1117,13 +1256,20 @@ fn removing_agent_from_any_side_is_working() {
 
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
         GetAgentIdsResponse {
             active: vec![Addr::unchecked(AGENT0)],
-            pending: vec![Addr::unchecked(AGENT4)]
+            pending: vec![Addr::unchecked(AGENT4)],
+            next_cursor: None,
         }
     );
 
@@ -1131,13 +1277,20 @@ fn removing_agent_from_any_side_is_working() {
     register_agent_exec(&mut app, &contract_addr, AGENT1, &AGENT_BENEFICIARY);
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
         GetAgentIdsResponse {
             active: vec![Addr::unchecked(AGENT0)],
-            pending: vec![Addr::unchecked(AGENT4), Addr::unchecked(AGENT1)]
+            pending: vec![Addr::unchecked(AGENT4), Addr::unchecked(AGENT1)],
+            next_cursor: None,
         }
     );
     // Or the last
@@ -1153,13 +1306,20 @@ fn removing_agent_from_any_side_is_working() {
 
     let agent_ids: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(
         agent_ids,
         GetAgentIdsResponse {
             active: vec![Addr::unchecked(AGENT0)],
-            pending: vec![Addr::unchecked(AGENT4)]
+            pending: vec![Addr::unchecked(AGENT4)],
+            next_cursor: None,
         }
     );
 }
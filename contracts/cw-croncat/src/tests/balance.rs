@@ -7,7 +7,7 @@ use crate::state::{Config, TaskInfo};
 use crate::tests::helpers::{default_task, AGENT0, AGENT1, AGENT2, AGENT3, AGENT4};
 use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env};
 use cosmwasm_std::{coins, Addr};
-use cw_croncat_core::types::{GasPrice, GenericBalance, SlotType};
+use cw_croncat_core::types::{GasPrice, GasPriceSource, GenericBalance, SlotType};
 
 use crate::CwCroncat;
 
@@ -34,6 +34,10 @@ fn mock_config() -> Config {
         gas_query_fee: GAS_QUERY_FEE,
         gas_wasm_query_fee: GAS_WASM_QUERY_FEE,
         proxy_callback_gas: 3,
+        ibc_transfer_timeout: 600,
+        gov_msgs_enabled: false,
+        stargate_type_urls: vec![],
+        admin_allowlist: vec![],
         slot_granularity_time: 60_000_000_000,
         native_denom: NATIVE_DENOM.to_owned(),
         cw20_whitelist: vec![],
@@ -41,6 +45,8 @@ fn mock_config() -> Config {
         limit: 100,
         cw_rules_addr: Addr::unchecked("todo"),
         gas_base_fee: GAS_BASE_FEE,
+        fee_tokens: vec![],
+        gas_price_source: GasPriceSource::Fixed,
     }
 }
 #[test]
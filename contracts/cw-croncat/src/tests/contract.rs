@@ -8,13 +8,23 @@ use crate::tests::helpers::NATIVE_DENOM;
 use crate::ContractError;
 use crate::CwCroncat;
 use crate::InstantiateMsg;
+use crate::MigrateMsg;
 use cosmwasm_std::testing::{
-    mock_dependencies_with_balance, mock_env, mock_info, MOCK_CONTRACT_ADDR,
+    mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coins, from_binary, Addr, BankMsg, Binary, CosmosMsg, Event, Order, OwnedDeps, Reply,
+    StdResult, Storage, SubMsgResponse, SubMsgResult,
 };
-use cosmwasm_std::{coins, from_binary, Addr, Binary, Event, Reply, SubMsgResponse, SubMsgResult};
 use cw_croncat_core::msg::{GetConfigResponse, QueryMsg};
 use cw_croncat_core::types::GasPrice;
 use cw_croncat_core::types::SlotType;
+use cw_croncat_core::types::{
+    Action, CheckedBoundary, GenericBalance, Interval, Task, TASK_HASH_VERSION,
+};
+use cw_rules_core::types::{CroncatQuery, HasBalanceGte};
+use cw_storage_plus::Index;
 
 #[test]
 fn configure() {
@@ -30,6 +40,8 @@ fn configure() {
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: Some(360),
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
         cw_rules_addr: "todo".to_string(),
         gas_base_fee: None,
     };
@@ -140,6 +152,8 @@ fn replies() {
                 task_is_extra: Some(false),
                 agent_id: Some(Addr::unchecked(AGENT0)),
                 failure: None,
+                failures: vec![],
+                fee: GenericBalance::default(),
             },
         )
         .unwrap();
@@ -176,3 +190,281 @@ pub fn tasks_with_queries_total_initialized() {
         .unwrap();
     assert_eq!(total, Some(0));
 }
+
+#[test]
+fn migrate_rehashes_tasks() {
+    let mut deps = mock_dependencies_with_balance(&coins(200, ""));
+    let store = CwCroncat::default();
+    mock_init(&store, deps.as_mut()).unwrap();
+
+    let msg: CosmosMsg = BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1015, NATIVE_DENOM),
+    }
+    .into();
+    let task = Task {
+        owner_id: Addr::unchecked("nobody"),
+        interval: Interval::Block(5),
+        boundary: CheckedBoundary {
+            start: None,
+            end: None,
+            is_block_boundary: Some(true),
+        },
+        stop_on_fail: false,
+        total_deposit: Default::default(),
+        amount_for_one_task: Default::default(),
+        actions: vec![Action {
+            msg,
+            gas_limit: Some(150_000),
+        }],
+        queries: None,
+        transforms: None,
+        version: "0.0.1".to_string(),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let task_with_queries = Task {
+        queries: Some(vec![CroncatQuery::HasBalanceGte(HasBalanceGte {
+            address: "foo".to_string(),
+            required_balance: coins(5, NATIVE_DENOM).into(),
+        })]),
+        ..task.clone()
+    };
+
+    // Store tasks under hashes of the older encoding
+    let legacy_hash = b"atom:legacy_task".to_vec();
+    let legacy_hash_with_queries = b"atom:legacy_task_with_queries".to_vec();
+    let storage = deps.as_mut().storage;
+    store.tasks.save(storage, &legacy_hash, &task).unwrap();
+    store
+        .block_slots
+        .save(storage, 12350, &vec![legacy_hash.clone()])
+        .unwrap();
+    store
+        .tasks_with_queries
+        .save(storage, &legacy_hash_with_queries, &task_with_queries)
+        .unwrap();
+    store
+        .block_map_queries
+        .save(storage, &legacy_hash_with_queries, &12350)
+        .unwrap();
+
+    mock_legacy_contract(&store, deps.as_mut().storage);
+
+    let res = store
+        .migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+        .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "task_migration" && attr.value == "true"));
+    // Contract is paused until every task is migrated
+    let config: GetConfigResponse = from_binary(
+        &store
+            .query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {})
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(config.paused);
+    let err = store
+        .remove_task(
+            deps.as_mut().storage,
+            "atom:legacy_task",
+            Some(mock_info("nobody", &[])),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Task migration in progress".to_string()
+        }
+    );
+
+    // A page per task and per block slot, an empty page closing each stage,
+    // the cron slots and the cleanup
+    assert_eq!(migrate_all_tasks(&store, &mut deps), 8);
+    let config: GetConfigResponse = from_binary(
+        &store
+            .query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {})
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(!config.paused);
+
+    let storage = deps.as_ref().storage;
+    let new_hash = task.to_hash_vec("atom");
+    assert!(store
+        .tasks
+        .may_load(storage, &legacy_hash)
+        .unwrap()
+        .is_none());
+    assert_eq!(store.tasks.load(storage, &new_hash).unwrap(), task);
+    assert_eq!(
+        store.block_slots.load(storage, 12350).unwrap(),
+        vec![new_hash]
+    );
+
+    let new_hash_with_queries = task_with_queries.to_hash_vec("atom");
+    assert!(store
+        .tasks_with_queries
+        .may_load(storage, &legacy_hash_with_queries)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        store
+            .tasks_with_queries
+            .load(storage, &new_hash_with_queries)
+            .unwrap(),
+        task_with_queries
+    );
+    assert!(store
+        .block_map_queries
+        .may_load(storage, &legacy_hash_with_queries)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        store
+            .block_map_queries
+            .load(storage, &new_hash_with_queries)
+            .unwrap(),
+        12350
+    );
+
+    assert!(store.task_renames.is_empty(storage));
+    assert_eq!(
+        store.task_hash_version.load(storage).unwrap(),
+        TASK_HASH_VERSION
+    );
+
+    // Same version can't be migrated twice
+    let err = store
+        .migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Contract already migrated".to_string()
+        }
+    );
+
+    // Newer version with the same hash encoding doesn't migrate the tasks
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw-croncat", "0.0.1").unwrap();
+    let res = store
+        .migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+        .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "task_migration" && attr.value == "false"));
+    let err = store.migrate_tasks(deps.as_mut(), None).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "No task migration in progress".to_string()
+        }
+    );
+}
+
+/// Contract before the versioned task hashes
+fn mock_legacy_contract(store: &CwCroncat, storage: &mut dyn Storage) {
+    cw2::set_contract_version(storage, "crates.io:cw-croncat", "0.0.1").unwrap();
+    store.task_hash_version.remove(storage);
+}
+
+/// Migrates the tasks one by one, returns the amount of `MigrateTasks` calls
+fn migrate_all_tasks(
+    store: &CwCroncat,
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+) -> u64 {
+    let mut calls = 0;
+    loop {
+        let res = store.migrate_tasks(deps.as_mut(), Some(1)).unwrap();
+        calls += 1;
+        if res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "done" && attr.value == "true")
+        {
+            return calls;
+        }
+    }
+}
+
+#[test]
+fn migrate_indexes_task_targets() {
+    let mut deps = mock_dependencies_with_balance(&coins(200, ""));
+    let store = CwCroncat::default();
+    mock_init(&store, deps.as_mut()).unwrap();
+
+    let msg: CosmosMsg = BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1015, NATIVE_DENOM),
+    }
+    .into();
+    let task = Task {
+        owner_id: Addr::unchecked("nobody"),
+        interval: Interval::Block(5),
+        boundary: CheckedBoundary {
+            start: None,
+            end: None,
+            is_block_boundary: Some(true),
+        },
+        stop_on_fail: false,
+        total_deposit: Default::default(),
+        amount_for_one_task: Default::default(),
+        actions: vec![Action {
+            msg,
+            gas_limit: Some(150_000),
+        }],
+        queries: None,
+        transforms: None,
+        version: "0.0.1".to_string(),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let hash = task.to_hash_vec("atom");
+    let target_hashes = |storage: &dyn Storage| {
+        store
+            .tasks
+            .idx
+            .target
+            .prefix(Addr::unchecked("you"))
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<Vec<u8>>>>()
+            .unwrap()
+    };
+
+    // Task stored before the target index existed
+    let storage = deps.as_mut().storage;
+    store.tasks.save(storage, &hash, &task).unwrap();
+    store
+        .tasks
+        .idx
+        .target
+        .remove(storage, &hash, &task)
+        .unwrap();
+    assert!(target_hashes(deps.as_ref().storage).is_empty());
+
+    mock_legacy_contract(&store, deps.as_mut().storage);
+    store
+        .migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+        .unwrap();
+    migrate_all_tasks(&store, &mut deps);
+    assert_eq!(target_hashes(deps.as_ref().storage), vec![hash]);
+}
@@ -1,11 +1,12 @@
 use cosmwasm_std::{
     coin, coins,
     testing::{mock_env, mock_info},
-    to_binary, Addr, Binary, BlockInfo, DepsMut, Empty, Response, Uint128,
+    to_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdResult, Uint128,
 };
 use cw20::Cw20Coin;
 use cw_croncat_core::{
-    msg::InstantiateMsg,
+    msg::{GasPriceOracleQueryMsg, GasPriceOracleResponse, InstantiateMsg},
     types::{CheckedBoundary, Interval, Task},
 };
 use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
@@ -43,6 +44,8 @@ pub fn mock_init(store: &CwCroncat, deps: DepsMut<Empty>) -> Result<Response, Co
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: Some(360),
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
         cw_rules_addr: "todo".to_string(),
         gas_base_fee: None,
     };
@@ -118,6 +121,43 @@ pub fn single_proposal_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+pub fn cw721_template() -> Box<dyn Contract<Empty>> {
+    let cw721 = ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    );
+    Box::new(cw721)
+}
+
+fn gas_price_oracle_set(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: GasPriceOracleResponse,
+) -> StdResult<Response> {
+    deps.storage.set(b"gas_price", &to_binary(&msg)?);
+    Ok(Response::new())
+}
+
+fn gas_price_oracle_query(
+    deps: Deps,
+    _env: Env,
+    _msg: GasPriceOracleQueryMsg,
+) -> StdResult<Binary> {
+    Ok(Binary::from(deps.storage.get(b"gas_price").unwrap()))
+}
+
+/// Gas price oracle answering with the numerator it got on instantiate or execute
+pub fn gas_price_oracle_template() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        gas_price_oracle_set,
+        gas_price_oracle_set,
+        gas_price_oracle_query,
+    );
+    Box::new(contract)
+}
+
 pub fn cw4_template() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         cw4_group::contract::execute,
@@ -187,6 +227,8 @@ pub fn proper_instantiate() -> (App, CwTemplateContract, Addr) {
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
     };
     let cw_template_contract_addr = app
         //Must send some available balance for rewards
@@ -345,5 +387,15 @@ pub fn default_task() -> Task {
         queries: Default::default(),
         transforms: Default::default(),
         version: "1.0.0".to_string(),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
 }
@@ -1,24 +1,35 @@
 use crate::contract::{
     GAS_ACTION_FEE, GAS_ADJUSTMENT_NUMERATOR_DEFAULT, GAS_BASE_FEE, GAS_DENOMINATOR,
-    GAS_NUMERATOR_DEFAULT,
+    GAS_NUMERATOR_DEFAULT, TASK_HISTORY_SIZE,
 };
 
 use crate::tests::helpers::{
-    add_1000_blocks, add_little_time, add_one_duration_of_time, cw4_template, proper_instantiate,
-    AGENT1, AGENT2, AGENT3,
+    add_1000_blocks, add_little_time, add_one_duration_of_time, cw4_template, cw721_template,
+    gas_price_oracle_template, proper_instantiate, AGENT1, AGENT2, AGENT3,
 };
 use crate::ContractError;
 use cosmwasm_std::{
-    coin, coins, to_binary, Addr, BankMsg, Coin, CosmosMsg, StakingMsg, StdResult, Uint128, WasmMsg,
+    coin, coins, to_binary, Addr, BankMsg, Coin, CosmosMsg, Empty, StakingMsg, StdResult, Uint128,
+    WasmMsg,
 };
 use cw20::Cw20Coin;
 use cw_croncat_core::error::CoreError;
+use cw_croncat_core::events::{
+    AgentActivated, AgentRegistered, CroncatEvent, SettingsChanged, TaskCreated, TaskEndReason,
+    TaskEnded, TaskExecuted, TaskRemoved,
+};
 use cw_croncat_core::msg::{
-    AgentResponse, AgentTaskResponse, ExecuteMsg, GetAgentIdsResponse, QueryMsg, TaskRequest,
-    TaskResponse, TaskWithQueriesResponse,
+    AgentResponse, AgentTaskResponse, ExecuteMsg, GasPriceOracleResponse, GetAgentIdsResponse,
+    GetBalancesResponse, GetConfigResponse, GetSlotHashesResponse, GetSlotIdsResponse,
+    GetTaskDependenciesResponse, GetTasksResponse, GetTasksWithQueriesResponse,
+    GetWalletBalancesResponse, QueryMsg, TaskDependencyResponse, TaskRequest, TaskResponse,
+    TaskWithQueriesResponse,
+};
+use cw_croncat_core::types::{
+    Action, AgentStatus, AutoRefill, Boundary, ExecutionRecord, GasPriceSource, Interval, SlotType,
+    Transform, Trigger, TriggerCondition,
 };
-use cw_croncat_core::types::{Action, Boundary, Interval, Transform};
-use cw_multi_test::Executor;
+use cw_multi_test::{AppResponse, Executor};
 use cw_rules_core::types::{CroncatQuery, HasBalanceGte};
 use dao_core::state::ProposalModule;
 use dao_voting::proposal::SingleChoiceProposeMsg;
@@ -54,6 +65,12 @@ fn proxy_call_fail_cases() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -105,11 +122,17 @@ fn proxy_call_fail_cases() -> StdResult<()> {
         agents_eject_threshold: None,
         gas_price: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     app.execute_contract(
         Addr::unchecked(ADMIN),
@@ -174,11 +197,17 @@ fn proxy_call_fail_cases() -> StdResult<()> {
             agents_eject_threshold: None,
             gas_price: None,
             proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: None,
+            stargate_type_urls: None,
+            admin_allowlist: None,
             slot_granularity_time: None,
             gas_base_fee: None,
             gas_action_fee: None,
             gas_query_fee: None,
             gas_wasm_query_fee: None,
+            fee_tokens: None,
+            gas_price_source: None,
         },
         &vec![],
     )
@@ -259,7 +288,7 @@ fn proxy_call_success() -> StdResult<()> {
     let contract_addr = cw_template_contract.addr();
     let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
     let task_id_str =
-        "atom:ffe9c635cc899c44f4fa8a5c40ed38ad41c46c3e92cd395a6408162feb3".to_string();
+        "atom:07c6c0b84ec2c82953a56c405c107abc41598f696eb728cedf03b463172".to_string();
 
     // Doing this msg since its the easiest to guarantee success in reply
     let msg = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -283,6 +312,12 @@ fn proxy_call_success() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -439,6 +474,12 @@ fn proxy_call_no_task_and_withdraw() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let gas_for_one = GAS_BASE_FEE + gas_limit;
@@ -519,7 +560,7 @@ fn proxy_callback_fail_cases() -> StdResult<()> {
     let contract_addr = cw_template_contract.addr();
     let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
     let task_id_str =
-        "atom:42012e0e2791902f167d9b87547fd73064690a8dc81d612704c89d6c77c".to_string();
+        "atom:0c26c0b53c2b75fc4bb3f4986f0fdde9e4acd2e276707523c83d23df7d3".to_string();
 
     // Doing this msg since its the easiest to guarantee success in reply
     let validator = String::from("you");
@@ -542,6 +583,12 @@ fn proxy_callback_fail_cases() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -665,6 +712,12 @@ fn proxy_callback_fail_cases() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -748,7 +801,7 @@ fn proxy_callback_block_slots() -> StdResult<()> {
     let contract_addr = cw_template_contract.addr();
     let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
     let task_id_str =
-        "atom:ffe9c635cc899c44f4fa8a5c40ed38ad41c46c3e92cd395a6408162feb3".to_string();
+        "atom:07c6c0b84ec2c82953a56c405c107abc41598f696eb728cedf03b463172".to_string();
 
     // Doing this msg since its the easiest to guarantee success in reply
     let msg = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -769,6 +822,12 @@ fn proxy_callback_block_slots() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -878,7 +937,7 @@ fn proxy_callback_time_slots() -> StdResult<()> {
     let contract_addr = cw_template_contract.addr();
     let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
     let task_id_str =
-        "atom:ec99cd112818db4a74ef3e84fb10d524203aaecbaccd28565627062f65f".to_string();
+        "atom:9ed8f5e2348dbc1da9724c931e8c00fcc5272bfba99dbf8f1949ccca8bb".to_string();
 
     // Doing this msg since its the easiest to guarantee success in reply
     let msg = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -899,6 +958,12 @@ fn proxy_callback_time_slots() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -1041,6 +1106,12 @@ fn proxy_call_several_tasks() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -1056,6 +1127,12 @@ fn proxy_call_several_tasks() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -1071,6 +1148,12 @@ fn proxy_call_several_tasks() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -1172,6 +1255,12 @@ fn test_proxy_call_with_bank_message() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let amount_for_one_task =
@@ -1231,6 +1320,12 @@ fn test_proxy_call_with_bank_message_should_fail() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     // create 1 token off task
@@ -1307,6 +1402,12 @@ fn test_multi_action() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let gas_limit = GAS_ACTION_FEE;
@@ -1379,6 +1480,12 @@ fn test_balance_changes() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let gas_for_one = GAS_BASE_FEE + (GAS_ACTION_FEE * 2);
@@ -1517,6 +1624,12 @@ fn test_no_reschedule_if_lack_balance() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -1634,7 +1747,7 @@ fn test_no_reschedule_if_lack_balance() {
 }
 
 #[test]
-fn test_complete_task_with_query() {
+fn test_max_executions_ends_task() {
     let (mut app, cw_template_contract, _) = proper_instantiate();
     let contract_addr = cw_template_contract.addr();
 
@@ -1646,40 +1759,53 @@ fn test_complete_task_with_query() {
     };
     let create_task_msg = ExecuteMsg::CreateTask {
         task: TaskRequest {
-            interval: Interval::Once,
+            interval: Interval::Immediate,
             boundary: None,
             stop_on_fail: false,
             actions: vec![Action {
-                msg: send.clone().into(),
+                msg: send.into(),
                 gas_limit: None,
             }],
-            queries: Some(vec![CroncatQuery::HasBalanceGte(HasBalanceGte {
-                address: String::from("addr2"),
-                required_balance: coins(1, NATIVE_DENOM).into(),
-            })]),
+            queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: Some(2),
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
-    let attached_balance = 900058;
+    // Enough for way more than 2 executions
     let resp = app
         .execute_contract(
             Addr::unchecked(ADMIN),
             contract_addr.clone(),
             &create_task_msg,
-            &coins(attached_balance, NATIVE_DENOM),
+            &coins(1_000_000, NATIVE_DENOM),
         )
         .unwrap();
-
-    let mut task_hash = String::new();
+    let mut hash = String::new();
     for e in resp.events {
         for a in e.attributes {
             if a.key == "task_hash" && a.value.len() > 0 {
-                task_hash = a.value;
+                hash = a.value;
             }
         }
     }
+    let task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: hash.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(task.unwrap().remaining_executions, Some(2));
+
     // quick agent register
     let msg = ExecuteMsg::RegisterAgent {
         payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
@@ -1687,215 +1813,1998 @@ fn test_complete_task_with_query() {
     app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
         .unwrap();
 
+    let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
     app.update_block(add_little_time);
-
-    let agent_tasks: Option<AgentTaskResponse> = app
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &proxy_call_msg,
+        &vec![],
+    )
+    .unwrap();
+    let task: Option<TaskResponse> = app
         .wrap()
         .query_wasm_smart(
             contract_addr.clone(),
-            &QueryMsg::GetAgentTasks {
-                account_id: String::from(AGENT0),
+            &QueryMsg::GetTask {
+                task_hash: hash.clone(),
             },
         )
         .unwrap();
-    assert!(agent_tasks.is_none());
+    assert_eq!(task.unwrap().remaining_executions, Some(1));
 
-    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
+    // Second execution is the last one, leftover deposit goes back to the owner
+    let admin_balance = app
         .wrap()
-        .query_wasm_smart(
-            contract_addr.clone(),
-            &QueryMsg::GetTasksWithQueries {
-                from_index: None,
-                limit: None,
-            },
-        )
-        .unwrap();
-    assert_eq!(tasks_with_queries.len(), 1);
-    app.send_tokens(
-        Addr::unchecked(ADMIN),
-        Addr::unchecked("addr2"),
-        &coins(1, NATIVE_DENOM),
-    )
-    .unwrap();
-
+        .query_balance(ADMIN, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    app.update_block(add_little_time);
     let res = app
         .execute_contract(
             Addr::unchecked(AGENT0),
             contract_addr.clone(),
-            &ExecuteMsg::ProxyCall {
-                task_hash: Some(String::from(task_hash.clone())),
-            },
-            &[],
+            &proxy_call_msg,
+            &vec![],
         )
         .unwrap();
-
-    assert!(res.events.iter().any(|ev| ev
-        .attributes
-        .iter()
-        .any(|attr| attr.key == "task_hash" && attr.value == task_hash)));
     assert!(res.events.iter().any(|ev| ev
         .attributes
         .iter()
-        .any(|attr| attr.key == "method" && attr.value == "proxy_callback")));
-
-    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
+        .any(|attr| attr.key == "ended_task" && attr.value == hash)));
+    let task: Option<TaskResponse> = app
         .wrap()
         .query_wasm_smart(
             contract_addr.clone(),
-            &QueryMsg::GetTasksWithQueries {
-                from_index: None,
-                limit: None,
-            },
+            &QueryMsg::GetTask { task_hash: hash },
         )
         .unwrap();
-    assert!(tasks_with_queries.is_empty());
-}
-
-#[test]
-fn test_reschedule_task_with_queries() {
-    let (mut app, cw_template_contract, _) = proper_instantiate();
-    let contract_addr = cw_template_contract.addr();
+    assert!(task.is_none());
+    let new_admin_balance = app
+        .wrap()
+        .query_balance(ADMIN, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert!(new_admin_balance > admin_balance);
 
-    let addr1 = String::from("addr1");
-    let amount = coins(3, NATIVE_DENOM);
-    let send = BankMsg::Send {
-        to_address: addr1,
-        amount,
-    };
+    // Zero executions are not allowed
     let create_task_msg = ExecuteMsg::CreateTask {
         task: TaskRequest {
             interval: Interval::Immediate,
             boundary: None,
             stop_on_fail: false,
             actions: vec![Action {
-                msg: send.clone().into(),
+                msg: BankMsg::Send {
+                    to_address: "addr1".to_string(),
+                    amount: coins(3, NATIVE_DENOM),
+                }
+                .into(),
                 gas_limit: None,
             }],
-            queries: Some(vec![CroncatQuery::HasBalanceGte(HasBalanceGte {
-                address: String::from("addr2"),
-                required_balance: coins(1, NATIVE_DENOM).into(),
-            })]),
+            queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: Some(0),
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
-
-    let attached_balance = 100338 * 4;
-    let create_task_resp = app
+    let res_err = app
         .execute_contract(
             Addr::unchecked(ADMIN),
             contract_addr.clone(),
             &create_task_msg,
-            &coins(attached_balance, NATIVE_DENOM),
+            &coins(1_000_000, NATIVE_DENOM),
         )
-        .unwrap();
-    let mut task_hash = String::new();
-    for e in create_task_resp.events {
-        for a in e.attributes {
-            if a.key == "task_hash" && a.value.len() > 0 {
-                task_hash = a.value;
-            }
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Max executions invalid".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn test_triggered_task_runs_after_parent() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let send_action = |to_address: &str| Action {
+        msg: BankMsg::Send {
+            to_address: to_address.to_string(),
+            amount: coins(3, NATIVE_DENOM),
         }
-    }
+        .into(),
+        gas_limit: None,
+    };
+    let task_hash_from = |resp: AppResponse| {
+        resp.events
+            .into_iter()
+            .flat_map(|e| e.attributes)
+            .find(|a| a.key == "task_hash")
+            .unwrap()
+            .value
+    };
 
-    // quick agent register
-    let msg = ExecuteMsg::RegisterAgent {
-        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![send_action("addr1")],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
     };
-    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
         .unwrap();
+    let parent_hash = task_hash_from(resp);
 
-    app.update_block(add_little_time);
+    let trigger = Trigger {
+        task_hash: parent_hash.clone(),
+        condition: TriggerCondition::Success,
+    };
+    let mut child_task = TaskRequest {
+        interval: Interval::Block(5),
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![send_action("addr2")],
+        queries: None,
+        transforms: None,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: Some(trigger.clone()),
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
 
-    let agent_tasks: Option<AgentTaskResponse> = app
-        .wrap()
-        .query_wasm_smart(
+    // Only Once and Immediate tasks can be triggered
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
             contract_addr.clone(),
-            &QueryMsg::GetAgentTasks {
-                account_id: String::from(AGENT0),
+            &ExecuteMsg::CreateTask {
+                task: child_task.clone(),
             },
+            &coins(300_000, NATIVE_DENOM),
         )
-        .unwrap();
-    assert!(agent_tasks.is_none());
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Trigger invalid".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
 
-    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
-        .wrap()
-        .query_wasm_smart(
+    // Can't chain to the task of another owner
+    child_task.interval = Interval::Once;
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
             contract_addr.clone(),
-            &QueryMsg::GetTasksWithQueries {
-                from_index: None,
-                limit: None,
+            &ExecuteMsg::CreateTask {
+                task: child_task.clone(),
             },
+            &coins(300_000, NATIVE_DENOM),
         )
-        .unwrap();
-    assert_eq!(tasks_with_queries.len(), 1);
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, res_err.downcast().unwrap());
 
-    app.send_tokens(
-        Addr::unchecked(ADMIN),
-        Addr::unchecked("addr2"),
-        &coins(1, NATIVE_DENOM),
-    )
-    .unwrap();
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::CreateTask { task: child_task },
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let child_hash = task_hash_from(resp);
 
-    let res = app
+    // Update can't turn the triggered task into a recurring one
+    let res_err = app
         .execute_contract(
-            Addr::unchecked(AGENT0),
+            Addr::unchecked(ADMIN),
             contract_addr.clone(),
-            &ExecuteMsg::ProxyCall {
-                task_hash: Some(String::from(task_hash.clone())),
+            &ExecuteMsg::UpdateTask {
+                task_hash: child_hash.clone(),
+                interval: Some(Interval::Block(5)),
+                boundary: None,
+                stop_on_fail: None,
+                gas_limits: None,
             },
             &[],
         )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Trigger invalid".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+
+    // Only the parent is scheduled
+    let slot_ids: GetSlotIdsResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetSlotIds {})
         .unwrap();
-    assert!(res.events.iter().any(|ev| ev
-        .attributes
-        .iter()
-        .any(|attr| attr.key == "task_hash" && attr.value == task_hash.clone())));
-    assert!(res.events.iter().any(|ev| ev
-        .attributes
-        .iter()
-        .any(|attr| attr.key == "method" && attr.value == "proxy_callback")));
+    assert_eq!(slot_ids.block_ids, vec![12346]);
 
-    // Shouldn't affect tasks without queries
-    let tasks_response: Vec<TaskResponse> = app
+    let dependencies: GetTaskDependenciesResponse = app
         .wrap()
         .query_wasm_smart(
             contract_addr.clone(),
-            &QueryMsg::GetTasks {
-                from_index: None,
+            &QueryMsg::GetTaskDependencies {
+                owner_id: ADMIN.to_string(),
+                start_after: None,
                 limit: None,
             },
         )
         .unwrap();
-    assert!(tasks_response.is_empty());
+    assert_eq!(
+        dependencies,
+        GetTaskDependenciesResponse {
+            dependencies: vec![TaskDependencyResponse {
+                task_hash: child_hash.clone(),
+                trigger: trigger.clone(),
+            }],
+            next_cursor: None,
+        }
+    );
 
-    // Run it a bunch of times successfully, until it's removed because the balance falls too low
-    for _ in 1..12 {
-        let _ = app
-            .execute_contract(
-                Addr::unchecked(AGENT0),
+    // Pages of a single owner task, only the child has a trigger
+    let mut walked_dependencies = vec![];
+    let mut start_after = None;
+    let mut pages = 0;
+    loop {
+        let page: GetTaskDependenciesResponse = app
+            .wrap()
+            .query_wasm_smart(
                 contract_addr.clone(),
-                &ExecuteMsg::ProxyCall {
-                    task_hash: Some(task_hash.clone()),
+                &QueryMsg::GetTaskDependencies {
+                    owner_id: ADMIN.to_string(),
+                    start_after,
+                    limit: Some(1),
                 },
-                &[],
             )
-            .is_ok();
+            .unwrap();
+        pages += 1;
+        walked_dependencies.extend(page.dependencies);
+        match page.next_cursor {
+            Some(cursor) => start_after = Some(cursor),
+            None => break,
+        }
     }
+    assert_eq!(pages, 2);
+    assert_eq!(
+        walked_dependencies,
+        vec![TaskDependencyResponse {
+            task_hash: child_hash.clone(),
+            trigger,
+        }]
+    );
 
-    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
-        .wrap()
-        .query_wasm_smart(
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Parent completes, child goes into the next block
+    let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
             contract_addr.clone(),
-            &QueryMsg::GetTasksWithQueries {
-                from_index: None,
-                limit: None,
-            },
+            &proxy_call_msg,
+            &vec![],
         )
         .unwrap();
-
-    assert!(tasks_with_queries.is_empty());
-}
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "triggered_task" && attr.value == child_hash)));
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "ended_task" && attr.value == parent_hash)));
+    let slot_ids: GetSlotIdsResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetSlotIds {})
+        .unwrap();
+    assert_eq!(slot_ids.block_ids, vec![app.block_info().height + 1]);
+
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &proxy_call_msg,
+            &vec![],
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "ended_task" && attr.value == child_hash)));
+    let addr2_balance = app.wrap().query_balance("addr2", NATIVE_DENOM).unwrap();
+    assert_eq!(addr2_balance.amount, Uint128::new(3));
+}
+
+#[test]
+fn test_triggered_task_scheduled_once_for_repeated_parent() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let send_action = |to_address: &str| Action {
+        msg: BankMsg::Send {
+            to_address: to_address.to_string(),
+            amount: coins(3, NATIVE_DENOM),
+        }
+        .into(),
+        gas_limit: None,
+    };
+    let task_hash_from = |resp: AppResponse| {
+        resp.events
+            .into_iter()
+            .flat_map(|e| e.attributes)
+            .find(|a| a.key == "task_hash")
+            .unwrap()
+            .value
+    };
+
+    // Parent runs every block
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Block(1),
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![send_action("addr1")],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(600_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let parent_hash = task_hash_from(resp);
+
+    // Child waits for a later block, so the parent completes again in the meantime
+    let child_start = app.block_info().height + 100;
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: Some(Boundary::Height {
+                start: Some(child_start.into()),
+                end: None,
+            }),
+            stop_on_fail: false,
+            actions: vec![send_action("addr2")],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: Some(Trigger {
+                task_hash: parent_hash,
+                condition: TriggerCondition::Success,
+            }),
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let child_hash = task_hash_from(resp);
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Parent completes twice
+    let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
+    for triggered in [true, false] {
+        app.update_block(add_little_time);
+        let res = app
+            .execute_contract(
+                Addr::unchecked(AGENT0),
+                contract_addr.clone(),
+                &proxy_call_msg,
+                &vec![],
+            )
+            .unwrap();
+        assert_eq!(
+            res.events.iter().any(|ev| ev
+                .attributes
+                .iter()
+                .any(|attr| attr.key == "triggered_task" && attr.value == child_hash)),
+            triggered
+        );
+    }
+
+    // Child is in its slot only once
+    let slot_info: GetSlotHashesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetSlotHashes {
+                slot: Some(child_start),
+            },
+        )
+        .unwrap();
+    assert_eq!(slot_info.block_task_hash, vec![child_hash]);
+}
+
+#[test]
+fn test_dependent_task_ends_with_parent() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let task_request = |to_address: &str, trigger: Option<Trigger>| TaskRequest {
+        interval: Interval::Once,
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: BankMsg::Send {
+                to_address: to_address.to_string(),
+                amount: coins(3, NATIVE_DENOM),
+            }
+            .into(),
+            gas_limit: None,
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let task_hash_from = |resp: AppResponse| {
+        resp.events
+            .into_iter()
+            .flat_map(|e| e.attributes)
+            .find(|a| a.key == "task_hash")
+            .unwrap()
+            .value
+    };
+
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::CreateTask {
+                task: task_request("addr1", None),
+            },
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let parent_hash = task_hash_from(resp);
+    let trigger = Trigger {
+        task_hash: parent_hash.clone(),
+        condition: TriggerCondition::Failure,
+    };
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::CreateTask {
+                task: task_request("addr2", Some(trigger)),
+            },
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let child_hash = task_hash_from(resp);
+    let child_task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: child_hash.clone(),
+            },
+        )
+        .unwrap();
+    let child_deposit = child_task.unwrap().total_deposit;
+
+    // Removing the parent ends the child waiting for it
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::RemoveTask {
+                task_hash: parent_hash,
+            },
+            &[],
+        )
+        .unwrap();
+    let ended = res
+        .events
+        .iter()
+        .find_map(|ev| TaskEnded::from_event(ev).ok())
+        .unwrap();
+    assert_eq!(ended.task_hash, child_hash);
+    assert_eq!(ended.reason, TaskEndReason::ParentEnded);
+    let removed: Vec<TaskRemoved> = res
+        .events
+        .iter()
+        .filter_map(|ev| TaskRemoved::from_event(ev).ok())
+        .collect();
+    assert_eq!(removed.len(), 2);
+    assert_eq!(removed[1].task_hash, child_hash);
+    assert_eq!(removed[1].refund_native, child_deposit);
+
+    let child_task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: child_hash,
+            },
+        )
+        .unwrap();
+    assert!(child_task.is_none());
+    let dependencies: GetTaskDependenciesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr,
+            &QueryMsg::GetTaskDependencies {
+                owner_id: ADMIN.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(dependencies.dependencies.is_empty());
+}
+
+#[test]
+fn test_failed_task_callback_does_not_revert_execution() {
+    let (mut app, cw_template_contract, cw20_addr) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    // cw20 doesn't know the callback message, so the callback fails
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: BankMsg::Send {
+                    to_address: "addr1".to_string(),
+                    amount: coins(3, NATIVE_DENOM),
+                }
+                .into(),
+                gas_limit: None,
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: Some(cw20_addr.to_string()),
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &vec![],
+        )
+        .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .any(|ev| ev.attributes.iter().any(|attr| attr.key == "ended_task")));
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "task_callback_failure")));
+    let addr1_balance = app.wrap().query_balance("addr1", NATIVE_DENOM).unwrap();
+    assert_eq!(addr1_balance.amount, Uint128::new(3));
+}
+
+#[test]
+fn test_nft_transferred_after_task_creation() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let cw721_id = app.store_code(cw721_template());
+    let cw721_addr = app
+        .instantiate_contract(
+            cw721_id,
+            Addr::unchecked(ADMIN),
+            &cw721_base::msg::InstantiateMsg {
+                name: "Name".to_string(),
+                symbol: "Symbol".to_string(),
+                minter: ADMIN.to_string(),
+            },
+            &[],
+            "cw721",
+            None,
+        )
+        .unwrap();
+    let mint_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> =
+        cw721_base::ExecuteMsg::Mint(cw721_base::MintMsg {
+            token_id: "vested".to_string(),
+            owner: ANYONE.to_string(),
+            token_uri: None,
+            extension: None,
+        });
+    app.execute_contract(Addr::unchecked(ADMIN), cw721_addr.clone(), &mint_msg, &[])
+        .unwrap();
+    let approve_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> =
+        cw721_base::ExecuteMsg::Approve {
+            spender: contract_addr.to_string(),
+            token_id: "vested".to_string(),
+            expires: None,
+        };
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        cw721_addr.clone(),
+        &approve_msg,
+        &[],
+    )
+    .unwrap();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: WasmMsg::Execute {
+                    contract_addr: cw721_addr.to_string(),
+                    msg: to_binary(&cw721::Cw721ExecuteMsg::TransferNft {
+                        recipient: "beneficiary".to_string(),
+                        token_id: "vested".to_string(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+                .into(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    // Owner sells the NFT, the approval to croncat is kept by the new owner
+    let transfer_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> =
+        cw721_base::ExecuteMsg::TransferNft {
+            recipient: "buyer".to_string(),
+            token_id: "vested".to_string(),
+        };
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        cw721_addr.clone(),
+        &transfer_msg,
+        &[],
+    )
+    .unwrap();
+    let approve_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> =
+        cw721_base::ExecuteMsg::Approve {
+            spender: contract_addr.to_string(),
+            token_id: "vested".to_string(),
+            expires: None,
+        };
+    app.execute_contract(
+        Addr::unchecked("buyer"),
+        cw721_addr.clone(),
+        &approve_msg,
+        &[],
+    )
+    .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &vec![],
+        )
+        .unwrap();
+    let ended = res
+        .events
+        .iter()
+        .find_map(|ev| TaskEnded::from_event(ev).ok())
+        .unwrap();
+    assert_eq!(ended.reason, TaskEndReason::NftOwnerChanged);
+
+    // NFT stays with the buyer and the task is gone
+    let nft_owner: cw721::OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(
+            cw721_addr,
+            &cw721::Cw721QueryMsg::OwnerOf {
+                token_id: "vested".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_owner.owner, "buyer");
+    let tasks: GetTasksResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr,
+            &QueryMsg::GetTasks {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(tasks.tasks.is_empty());
+}
+
+#[test]
+fn test_nft_burned_after_task_creation() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let cw721_id = app.store_code(cw721_template());
+    let cw721_addr = app
+        .instantiate_contract(
+            cw721_id,
+            Addr::unchecked(ADMIN),
+            &cw721_base::msg::InstantiateMsg {
+                name: "Name".to_string(),
+                symbol: "Symbol".to_string(),
+                minter: ADMIN.to_string(),
+            },
+            &[],
+            "cw721",
+            None,
+        )
+        .unwrap();
+    let mint_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> =
+        cw721_base::ExecuteMsg::Mint(cw721_base::MintMsg {
+            token_id: "vested".to_string(),
+            owner: ANYONE.to_string(),
+            token_uri: None,
+            extension: None,
+        });
+    app.execute_contract(Addr::unchecked(ADMIN), cw721_addr.clone(), &mint_msg, &[])
+        .unwrap();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: WasmMsg::Execute {
+                    contract_addr: cw721_addr.to_string(),
+                    msg: to_binary(&cw721::Cw721ExecuteMsg::TransferNft {
+                        recipient: "beneficiary".to_string(),
+                        token_id: "vested".to_string(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+                .into(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    // Owner burns the NFT, the owner query fails with not found
+    let burn_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> = cw721_base::ExecuteMsg::Burn {
+        token_id: "vested".to_string(),
+    };
+    app.execute_contract(Addr::unchecked(ANYONE), cw721_addr, &burn_msg, &[])
+        .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &vec![],
+        )
+        .unwrap();
+    let ended = res
+        .events
+        .iter()
+        .find_map(|ev| TaskEnded::from_event(ev).ok())
+        .unwrap();
+    assert_eq!(ended.reason, TaskEndReason::NftOwnerChanged);
+    let tasks: GetTasksResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr,
+            &QueryMsg::GetTasks {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(tasks.tasks.is_empty());
+}
+
+#[test]
+fn test_wasm_funds_keep_balances_in_sync() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+    let config: GetConfigResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetConfig {})
+        .unwrap();
+    let cw_rules_addr = config.cw_rules_addr;
+
+    // Contract bank balance must always match the tracked available balance
+    let assert_balanced = |app: &cw_multi_test::App| {
+        let balances: GetBalancesResponse = app
+            .wrap()
+            .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetBalances {})
+            .unwrap();
+        let bank_balance = app
+            .wrap()
+            .query_balance(contract_addr.clone(), NATIVE_DENOM)
+            .unwrap();
+        assert_eq!(balances.available_balance.native, vec![bank_balance]);
+    };
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Block(1),
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: WasmMsg::Execute {
+                    contract_addr: cw_rules_addr.to_string(),
+                    msg: to_binary(&cw_rules_core::msg::ExecuteMsg::QueryResult {}).unwrap(),
+                    funds: coins(7, NATIVE_DENOM),
+                }
+                .into(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: Some(2),
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+    assert_balanced(&app);
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
+    app.update_block(add_little_time);
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &proxy_call_msg,
+        &vec![],
+    )
+    .unwrap();
+    assert_balanced(&app);
+
+    // Attached funds are charged from the task deposit
+    let tasks: Vec<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasksByOwner {
+                owner_id: ANYONE.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    let gas = GAS_BASE_FEE + 150_000;
+    let fee = u128::from(
+        (gas + gas * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
+            * GAS_NUMERATOR_DEFAULT
+            / GAS_DENOMINATOR,
+    );
+    assert_eq!(
+        tasks[0].total_deposit,
+        coins(300_000 - fee - 7, NATIVE_DENOM)
+    );
+
+    // Last execution refunds the rest of the deposit
+    app.update_block(add_little_time);
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &proxy_call_msg,
+        &vec![],
+    )
+    .unwrap();
+    assert_balanced(&app);
+    let rules_balance = app
+        .wrap()
+        .query_balance(cw_rules_addr, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(rules_balance.amount, Uint128::new(14));
+}
+
+#[test]
+fn test_gas_price_oracle() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let oracle_id = app.store_code(gas_price_oracle_template());
+    let oracle_addr = app
+        .instantiate_contract(
+            oracle_id,
+            Addr::unchecked(ADMIN),
+            &GasPriceOracleResponse { numerator: 8 },
+            &[],
+            "gas-price-oracle",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &ExecuteMsg::UpdateSettings {
+            owner_id: None,
+            slot_granularity_time: None,
+            paused: None,
+            agent_fee: None,
+            gas_base_fee: None,
+            gas_action_fee: None,
+            gas_query_fee: None,
+            gas_wasm_query_fee: None,
+            gas_price: None,
+            proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: None,
+            stargate_type_urls: None,
+            admin_allowlist: None,
+            fee_tokens: None,
+            gas_price_source: Some(GasPriceSource::Oracle {
+                contract_addr: oracle_addr.clone(),
+                min_numerator: 2,
+                max_numerator: 10,
+            }),
+            min_tasks_per_agent: None,
+            agents_eject_threshold: None,
+            chain_name: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    let task_request = |max_gas_price| TaskRequest {
+        interval: Interval::Block(1),
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: BankMsg::Send {
+                to_address: "addr1".to_string(),
+                amount: coins(1, NATIVE_DENOM),
+            }
+            .into(),
+            gas_limit: None,
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price,
+        auto_refill: None,
+    };
+
+    // Max gas price can't be lower than the oracle minimum
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &ExecuteMsg::CreateTask {
+                task: task_request(Some(1)),
+            },
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CustomError {
+            val: "Max gas price too low".to_string()
+        }
+    );
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::CreateTask {
+            task: task_request(Some(6)),
+        },
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let gas = GAS_BASE_FEE + GAS_ACTION_FEE;
+    let fee = |numerator| {
+        u128::from(
+            (gas + gas * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR * numerator
+                / GAS_DENOMINATOR,
+        )
+    };
+    let agent_balance = |app: &cw_multi_test::App| {
+        let agent: Option<AgentResponse> = app
+            .wrap()
+            .query_wasm_smart(
+                contract_addr.clone(),
+                &QueryMsg::GetAgent {
+                    account_id: AGENT0.to_string(),
+                },
+            )
+            .unwrap();
+        agent.unwrap().balance.native
+    };
+    let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
+
+    // Oracle price is above the task maximum, so the maximum is paid
+    app.update_block(add_little_time);
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &proxy_call_msg,
+        &vec![],
+    )
+    .unwrap();
+    assert_eq!(agent_balance(&app), coins(fee(6), NATIVE_DENOM));
+
+    // Oracle price below the minimum gets clamped
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        oracle_addr,
+        &GasPriceOracleResponse { numerator: 1 },
+        &[],
+    )
+    .unwrap();
+    app.update_block(add_little_time);
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &proxy_call_msg,
+        &vec![],
+    )
+    .unwrap();
+    assert_eq!(agent_balance(&app), coins(fee(6) + fee(2), NATIVE_DENOM));
+}
+
+#[test]
+fn test_auto_refill_from_wallet_balance() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let gas = GAS_BASE_FEE + GAS_ACTION_FEE;
+    let fee = u128::from(
+        (gas + gas * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
+            * GAS_NUMERATOR_DEFAULT
+            / GAS_DENOMINATOR,
+    );
+    let one_execution = fee + 1;
+
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::DepositWalletBalance {},
+        &coins(100_000, NATIVE_DENOM),
+    )
+    .unwrap();
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Block(1),
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: BankMsg::Send {
+                    to_address: "addr1".to_string(),
+                    amount: coins(1, NATIVE_DENOM),
+                }
+                .into(),
+                gas_limit: None,
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: Some(AutoRefill {
+                min_executions: 2,
+                refill_executions: 3,
+            }),
+        },
+    };
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(2 * one_execution, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Deposit covers one execution after this call, so it takes three more from the wallet
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &vec![],
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "auto_refill" && attr.value == "true")));
+    let tasks: Vec<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasksByOwner {
+                owner_id: ANYONE.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert_eq!(
+        tasks[0].total_deposit,
+        coins(4 * one_execution, NATIVE_DENOM)
+    );
+    let wallet: GetWalletBalancesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetWalletBalances {
+                wallet: ANYONE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        wallet.native_balances,
+        coins(100_000 - 3 * one_execution, NATIVE_DENOM)
+    );
+
+    // The rest of the wallet balance can be withdrawn
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr,
+        &ExecuteMsg::WithdrawWalletBalance {
+            cw20_amounts: vec![],
+            native_amounts: wallet.native_balances,
+        },
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_task_history_keeps_last_executions() {
+    let (mut app, cw_template_contract, cw20_addr) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    // cw20 can't parse the second action, so it fails on every execution
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Block(1),
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![
+                Action {
+                    msg: BankMsg::Send {
+                        to_address: "addr1".to_string(),
+                        amount: coins(1, NATIVE_DENOM),
+                    }
+                    .into(),
+                    gas_limit: None,
+                },
+                Action {
+                    msg: WasmMsg::Execute {
+                        contract_addr: cw20_addr.to_string(),
+                        msg: to_binary("unknown").unwrap(),
+                        funds: vec![],
+                    }
+                    .into(),
+                    gas_limit: Some(100_000),
+                },
+            ],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(1_000_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let task_hash = res
+        .events
+        .iter()
+        .flat_map(|ev| ev.attributes.iter())
+        .find(|attr| attr.key == "task_hash")
+        .unwrap()
+        .value
+        .clone();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let executions = TASK_HISTORY_SIZE + 2;
+    for _ in 0..executions {
+        app.update_block(add_little_time);
+        app.execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &vec![],
+        )
+        .unwrap();
+    }
+
+    let task: TaskResponse = app
+        .wrap()
+        .query_wasm_smart::<Option<TaskResponse>>(
+            contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.executions, executions);
+    assert_eq!(task.failed_executions, executions);
+
+    // Only the last executions are kept, newest first
+    let history: Vec<ExecutionRecord> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTaskHistory {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len() as u64, TASK_HISTORY_SIZE);
+    assert_eq!(
+        history.iter().map(|r| r.execution).collect::<Vec<_>>(),
+        (3..=executions).rev().collect::<Vec<_>>()
+    );
+    let last = &history[0];
+    assert_eq!(last.block_height, app.block_info().height);
+    assert_eq!(last.time, app.block_info().time);
+    assert_eq!(last.agent, Addr::unchecked(AGENT0));
+    // Agent fee is the execution cost without the sent coin
+    assert_eq!(
+        last.fee.native,
+        coins(
+            task.amount_for_one_task_native[0].amount.u128() - 1,
+            NATIVE_DENOM
+        )
+    );
+    assert_eq!(last.executed_actions, 2);
+    assert_eq!(last.failures.len(), 1);
+    assert_eq!(last.failures[0].action_idx, 1);
+    assert_eq!(last.failure, Some(last.failures[0].error.clone()));
+
+    // Only the last execution outlives the task
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::RemoveTask {
+            task_hash: task_hash.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    let history_after_removal: Vec<ExecutionRecord> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTaskHistory {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(history_after_removal, history[..1]);
+
+    // Only the owner prunes it
+    let prune_msg = ExecuteMsg::PruneTaskHistory {
+        task_hashes: vec![task_hash.clone()],
+    };
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &prune_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, res_err.downcast().unwrap());
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &prune_msg,
+            &[],
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "pruned_records" && attr.value == "1")));
+    let history_after_pruning: Vec<ExecutionRecord> = app
+        .wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetTaskHistory { task_hash })
+        .unwrap();
+    assert!(history_after_pruning.is_empty());
+}
+
+#[test]
+fn test_task_history_of_completed_task() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: BankMsg::Send {
+                    to_address: "addr1".to_string(),
+                    amount: coins(3, NATIVE_DENOM),
+                }
+                .into(),
+                gas_limit: None,
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let task_hash = res
+        .events
+        .into_iter()
+        .flat_map(|ev| ev.attributes)
+        .find(|attr| attr.key == "task_hash")
+        .unwrap()
+        .value;
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &ExecuteMsg::ProxyCall { task_hash: None },
+        &vec![],
+    )
+    .unwrap();
+
+    // Task is removed once executed, its execution is still in the history
+    let task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert!(task.is_none());
+    let history: Vec<ExecutionRecord> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTaskHistory {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].execution, 1);
+    assert_eq!(history[0].agent, Addr::unchecked(AGENT0));
+    assert_eq!(history[0].executed_actions, 1);
+    assert!(history[0].failure.is_none());
+
+    // Same task created again starts with an empty history
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+    let history: Vec<ExecutionRecord> = app
+        .wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetTaskHistory { task_hash })
+        .unwrap();
+    assert!(history.is_empty());
+}
+
+#[test]
+fn test_lifecycle_events() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+    fn find_event<E: CroncatEvent>(res: &AppResponse) -> E {
+        res.events
+            .iter()
+            .find_map(|ev| E::from_event(ev).ok())
+            .unwrap()
+    }
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: BankMsg::Send {
+                    to_address: "addr1".to_string(),
+                    amount: coins(3, NATIVE_DENOM),
+                }
+                .into(),
+                gas_limit: None,
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    let created: TaskCreated = find_event(&res);
+    assert_eq!(created.owner, Addr::unchecked(ADMIN));
+    assert!(!created.with_queries);
+    assert_eq!(created.slot_kind, Some(SlotType::Block));
+    let task_hash = created.task_hash;
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::RegisterAgent {
+                payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    let registered: AgentRegistered = find_event(&res);
+    assert_eq!(registered.agent, Addr::unchecked(AGENT0));
+    assert_eq!(registered.status, AgentStatus::Active);
+    let activated: AgentActivated = find_event(&res);
+    assert_eq!(activated.agent, Addr::unchecked(AGENT0));
+
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &[],
+        )
+        .unwrap();
+    let executed: TaskExecuted = find_event(&res);
+    assert_eq!(executed.task_hash, task_hash);
+    assert_eq!(executed.agent, Addr::unchecked(AGENT0));
+    assert_eq!(executed.execution, 1);
+    assert_eq!(executed.executed_actions, 1);
+    assert_eq!(executed.failed_actions, 0);
+    let ended: TaskEnded = find_event(&res);
+    assert_eq!(ended.task_hash, task_hash);
+    assert_eq!(ended.reason, TaskEndReason::Completed);
+    let removed: TaskRemoved = find_event(&res);
+    assert_eq!(removed.task_hash, task_hash);
+    let fee = executed.fee_native[0].amount.u128();
+    assert_eq!(
+        removed.refund_native,
+        coins(300_000 - 3 - fee, NATIVE_DENOM)
+    );
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr,
+            &ExecuteMsg::UpdateSettings {
+                owner_id: None,
+                slot_granularity_time: None,
+                paused: Some(true),
+                agent_fee: None,
+                gas_base_fee: None,
+                gas_action_fee: None,
+                gas_query_fee: None,
+                gas_wasm_query_fee: None,
+                gas_price: None,
+                proxy_callback_gas: None,
+                min_tasks_per_agent: None,
+                agents_eject_threshold: None,
+                chain_name: None,
+                ibc_transfer_timeout: None,
+                gov_msgs_enabled: None,
+                stargate_type_urls: None,
+                admin_allowlist: None,
+                fee_tokens: None,
+                gas_price_source: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let changed: SettingsChanged = find_event(&res);
+    assert!(changed.paused);
+    assert_eq!(changed.changed, vec!["paused".to_string()]);
+}
+
+#[test]
+fn test_complete_task_with_query() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let addr1 = String::from("addr1");
+    let amount = coins(3, NATIVE_DENOM);
+    let send = BankMsg::Send {
+        to_address: addr1,
+        amount,
+    };
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: send.clone().into(),
+                gas_limit: None,
+            }],
+            queries: Some(vec![CroncatQuery::HasBalanceGte(HasBalanceGte {
+                address: String::from("addr2"),
+                required_balance: coins(1, NATIVE_DENOM).into(),
+            })]),
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    let attached_balance = 900058;
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(attached_balance, NATIVE_DENOM),
+        )
+        .unwrap();
+
+    let mut task_hash = String::new();
+    for e in resp.events {
+        for a in e.attributes {
+            if a.key == "task_hash" && a.value.len() > 0 {
+                task_hash = a.value;
+            }
+        }
+    }
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+
+    let agent_tasks: Option<AgentTaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentTasks {
+                account_id: String::from(AGENT0),
+            },
+        )
+        .unwrap();
+    assert!(agent_tasks.is_none());
+
+    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasksWithQueries {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert_eq!(tasks_with_queries.len(), 1);
+    app.send_tokens(
+        Addr::unchecked(ADMIN),
+        Addr::unchecked("addr2"),
+        &coins(1, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall {
+                task_hash: Some(String::from(task_hash.clone())),
+            },
+            &[],
+        )
+        .unwrap();
+
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "task_hash" && attr.value == task_hash)));
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "method" && attr.value == "proxy_callback")));
+
+    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasksWithQueries {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert!(tasks_with_queries.is_empty());
+}
+
+#[test]
+fn test_reschedule_task_with_queries() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let addr1 = String::from("addr1");
+    let amount = coins(3, NATIVE_DENOM);
+    let send = BankMsg::Send {
+        to_address: addr1,
+        amount,
+    };
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Immediate,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: send.clone().into(),
+                gas_limit: None,
+            }],
+            queries: Some(vec![CroncatQuery::HasBalanceGte(HasBalanceGte {
+                address: String::from("addr2"),
+                required_balance: coins(1, NATIVE_DENOM).into(),
+            })]),
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    let attached_balance = 100338 * 4;
+    let create_task_resp = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(attached_balance, NATIVE_DENOM),
+        )
+        .unwrap();
+    let mut task_hash = String::new();
+    for e in create_task_resp.events {
+        for a in e.attributes {
+            if a.key == "task_hash" && a.value.len() > 0 {
+                task_hash = a.value;
+            }
+        }
+    }
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+
+    let agent_tasks: Option<AgentTaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentTasks {
+                account_id: String::from(AGENT0),
+            },
+        )
+        .unwrap();
+    assert!(agent_tasks.is_none());
+
+    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasksWithQueries {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert_eq!(tasks_with_queries.len(), 1);
+
+    app.send_tokens(
+        Addr::unchecked(ADMIN),
+        Addr::unchecked("addr2"),
+        &coins(1, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall {
+                task_hash: Some(String::from(task_hash.clone())),
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "task_hash" && attr.value == task_hash.clone())));
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "method" && attr.value == "proxy_callback")));
+
+    // Shouldn't affect tasks without queries
+    let tasks_response: Vec<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasks {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert!(tasks_response.is_empty());
+
+    // Run it a bunch of times successfully, until it's removed because the balance falls too low
+    for _ in 1..12 {
+        let _ = app
+            .execute_contract(
+                Addr::unchecked(AGENT0),
+                contract_addr.clone(),
+                &ExecuteMsg::ProxyCall {
+                    task_hash: Some(task_hash.clone()),
+                },
+                &[],
+            )
+            .is_ok();
+    }
+
+    let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
+            contract_addr.clone(),
+            &QueryMsg::GetTasksWithQueries {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+
+    assert!(tasks_with_queries.is_empty());
+}
 
 #[test]
 fn tick() {
@@ -1914,9 +3823,15 @@ fn tick() {
         gas_query_fee: None,
         gas_wasm_query_fee: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_price: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     app.execute_contract(
         Addr::unchecked(ADMIN),
@@ -1968,7 +3883,13 @@ fn tick() {
     // Pending agents weren't deleted
     let agents: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert!(agents.active.is_empty());
     assert!(agents.pending.is_empty());
@@ -2009,7 +3930,13 @@ fn tick() {
     // The agent wasn't unregistered
     let agents: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(agents.active.len(), 1);
     assert_eq!(agents.pending.len(), 2);
@@ -2063,7 +3990,13 @@ fn tick() {
     // Pending agents weren't deleted
     let agents: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert!(agents.active.is_empty());
     assert_eq!(agents.pending.len(), 2);
@@ -2106,12 +4039,18 @@ fn tick_task() -> StdResult<()> {
         min_tasks_per_agent: Some(1),
         agents_eject_threshold: Some(1000), // allow to miss 100 slots
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
         gas_price: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     app.execute_contract(
         Addr::unchecked(ADMIN),
@@ -2163,6 +4102,12 @@ fn tick_task() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     // create a task with tick
@@ -2189,6 +4134,12 @@ fn tick_task() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     // create a second task so that another agent can be registered
@@ -2213,7 +4164,13 @@ fn tick_task() -> StdResult<()> {
 
     let agents: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(agents.active.len(), 2);
     assert!(agents.pending.is_empty());
@@ -2247,7 +4204,13 @@ fn tick_task() -> StdResult<()> {
 
     let agents: GetAgentIdsResponse = app
         .wrap()
-        .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetAgentIds {})
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgentIds {
+                start_after: None,
+                limit: None,
+            },
+        )
         .unwrap();
     assert_eq!(agents.active.len(), 1);
     assert!(agents.pending.is_empty());
@@ -2278,6 +4241,12 @@ fn testing_fee_works() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let delegate = StakingMsg::Delegate {
@@ -2296,6 +4265,12 @@ fn testing_fee_works() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let total_gas = GAS_BASE_FEE + GAS_ACTION_FEE;
@@ -2334,7 +4309,7 @@ fn testing_fee_works() {
     //     .query_wasm_smart(
     //         contract_addr.clone(),
     //         &QueryMsg::GetTasks {
-    //             from_index: None,
+    //             start_after: None,
     //             limit: None,
     //         },
     //     )
@@ -2359,7 +4334,7 @@ fn testing_fee_works() {
     //     .query_wasm_smart(
     //         contract_addr.clone(),
     //         &QueryMsg::GetTasks {
-    //             from_index: None,
+    //             start_after: None,
     //             limit: None,
     //         },
     //     )
@@ -2384,7 +4359,7 @@ fn testing_fee_works() {
     //     .query_wasm_smart(
     //         contract_addr.clone(),
     //         &QueryMsg::GetTasks {
-    //             from_index: None,
+    //             start_after: None,
     //             limit: None,
     //         },
     //     )
@@ -2475,6 +4450,12 @@ fn smart_query() {
             queries: Some(vec![smart_query]),
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -2505,14 +4486,15 @@ fn smart_query() {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     let our_task = tasks_with_queries.first();
     assert!(our_task.is_some());
     let task_hash = our_task.unwrap().task_hash.as_ref();
@@ -2539,14 +4521,15 @@ fn smart_query() {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     assert!(tasks_with_queries.is_empty());
 }
 
@@ -2633,6 +4616,12 @@ fn insertable_query_res_positive() {
                 address: cw20_addr.to_string(),
                 amount: 10u128.into(),
             }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -2656,14 +4645,15 @@ fn insertable_query_res_positive() {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     let our_task = tasks_with_queries.first();
     assert!(our_task.is_some());
     let task_hash: &str = our_task.unwrap().task_hash.as_ref();
@@ -2724,14 +4714,15 @@ fn insertable_query_res_positive() {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     assert!(tasks_with_queries.is_empty());
 
     let new_balance_of_agent3: cw20::BalanceResponse = app
@@ -2834,6 +4825,12 @@ fn insertable_query_res_negative() {
                 // Notice that would be not enough
                 amount: 1u128.into(),
             }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -2857,14 +4854,15 @@ fn insertable_query_res_negative() {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     let our_task = tasks_with_queries.first();
     assert!(our_task.is_some());
     let task_hash: &str = our_task.unwrap().task_hash.as_ref();
@@ -2925,14 +4923,15 @@ fn insertable_query_res_negative() {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
     assert!(tasks_with_queries.is_empty());
 
     let new_balance_of_agent3: cw20::BalanceResponse = app
@@ -3048,6 +5047,12 @@ fn test_error_in_reply() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -3098,6 +5103,12 @@ fn empty_actions_not_allowed() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
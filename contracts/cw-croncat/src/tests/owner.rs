@@ -24,6 +24,8 @@ fn update_settings() {
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: Some(360),
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
     };
     let info = MessageInfo {
         sender: Addr::unchecked("creator"),
@@ -45,11 +47,17 @@ fn update_settings() {
         agents_eject_threshold: None,
         gas_price: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
 
     // non-owner fails
@@ -110,6 +118,8 @@ fn move_balances_auth_checks() {
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: Some(360),
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
         cw_rules_addr: "todo".to_string(),
         gas_base_fee: None,
     };
@@ -128,11 +138,17 @@ fn move_balances_auth_checks() {
         agents_eject_threshold: None,
         gas_price: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     let info_setting = mock_info("owner_id", &coins(0, "meow"));
     let res_exec = store
@@ -185,6 +201,8 @@ fn move_balances_native() {
         gas_wasm_query_fee: None,
         gas_price: None,
         agent_nomination_duration: Some(360),
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
         cw_rules_addr: "todo".to_string(),
         gas_base_fee: None,
     };
@@ -203,11 +221,17 @@ fn move_balances_native() {
         agents_eject_threshold: None,
         gas_price: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     let info_settings = mock_info("owner_id", &coins(0, "meow"));
     let res_exec = store
@@ -1,15 +1,19 @@
+use crate::contract::{
+    GAS_ADJUSTMENT_NUMERATOR_DEFAULT, GAS_BASE_FEE, GAS_DENOMINATOR, GAS_NUMERATOR_DEFAULT,
+};
 use crate::tests::helpers::{add_little_time, proper_instantiate};
 use crate::ContractError;
 use cosmwasm_std::{coins, to_binary, Addr, CosmosMsg, StdError, Uint128, WasmMsg};
 use cw20::{BalanceResponse, Cw20Coin, Cw20CoinVerified};
 use cw_croncat_core::error::CoreError;
+use cw_croncat_core::events::{CroncatEvent, TaskEndReason, TaskEnded};
 use cw_croncat_core::msg::{
-    ExecuteMsg, GetWalletBalancesResponse, QueryMsg, TaskRequest, TaskResponse,
+    AgentResponse, ExecuteMsg, GetWalletBalancesResponse, QueryMsg, TaskRequest, TaskResponse,
 };
-use cw_croncat_core::types::{Action, Interval};
+use cw_croncat_core::types::{Action, FeeToken, FeeTokenRate, Interval};
 use cw_multi_test::Executor;
 
-use super::helpers::{AGENT0, AGENT_BENEFICIARY, ANYONE, NATIVE_DENOM};
+use super::helpers::{ADMIN, AGENT0, AGENT_BENEFICIARY, ANYONE, NATIVE_DENOM};
 
 #[test]
 fn test_cw20_action() {
@@ -57,6 +61,12 @@ fn test_cw20_action() {
                 address: cw20_contract.to_string(),
                 amount: 10u128.into(),
             }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     app.execute_contract(
@@ -154,7 +164,8 @@ fn test_cw20_balances() {
             cw20_balances: vec![Cw20CoinVerified {
                 address: cw20_contract.clone(),
                 amount: 10u128.into()
-            }]
+            }],
+            native_balances: vec![],
         }
     );
 
@@ -184,6 +195,12 @@ fn test_cw20_balances() {
                 address: cw20_contract.to_string(),
                 amount: 10u128.into(),
             }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let mut resp = app
@@ -266,6 +283,12 @@ fn test_cw20_negative() {
                 address: cw20_contract.to_string(),
                 amount: 10u128.into(),
             }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let resp: ContractError = app
@@ -323,6 +346,12 @@ fn test_cw20_negative() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     let resp: ContractError = app
@@ -339,3 +368,330 @@ fn test_cw20_negative() {
                 resp,
                 ContractError::CoreError(CoreError::NotEnoughCw20 { lack, .. }) if lack == Uint128::from(10_u128)));
 }
+
+#[test]
+fn test_cw20_agent_fee() {
+    let (mut app, cw_template_contract, cw20_contract) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    // fill balance of cw20 tokens of user
+    let user = ANYONE;
+    let refill_balance_msg = cw20::Cw20ExecuteMsg::Send {
+        contract: contract_addr.to_string(),
+        amount: 10u128.into(),
+        msg: Default::default(),
+    };
+    app.execute_contract(
+        Addr::unchecked(user),
+        cw20_contract.clone(),
+        &refill_balance_msg,
+        &[],
+    )
+    .unwrap();
+
+    // task pays the agent in cw20, no native deposit
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: WasmMsg::Execute {
+                    contract_addr: cw20_contract.to_string(),
+                    msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                        recipient: AGENT0.to_string(),
+                        amount: 1u128.into(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+                .into(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![Cw20Coin {
+                address: cw20_contract.to_string(),
+                amount: 10u128.into(),
+            }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: Some(FeeToken::Cw20(cw20_contract.clone())),
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    // token is not in the registry yet
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(user),
+            contract_addr.clone(),
+            &create_task_msg,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CustomError {
+            val: "Fee token not allowed".to_string()
+        }
+    );
+
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &ExecuteMsg::UpdateSettings {
+            owner_id: None,
+            slot_granularity_time: None,
+            paused: None,
+            agent_fee: None,
+            gas_base_fee: None,
+            gas_action_fee: None,
+            gas_query_fee: None,
+            gas_wasm_query_fee: None,
+            gas_price: None,
+            proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: None,
+            stargate_type_urls: None,
+            admin_allowlist: None,
+            fee_tokens: Some(vec![FeeTokenRate {
+                token: FeeToken::Cw20(cw20_contract.clone()),
+                numerator: 1,
+                denominator: 10_000,
+            }]),
+            min_tasks_per_agent: None,
+            agents_eject_threshold: None,
+            chain_name: None,
+            gas_price_source: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(user),
+        contract_addr.clone(),
+        &create_task_msg,
+        &[],
+    )
+    .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(add_little_time);
+    let proxy_call_msg = ExecuteMsg::ProxyCall { task_hash: None };
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr.clone(),
+        &proxy_call_msg,
+        &vec![],
+    )
+    .unwrap();
+
+    // Fee of the native denom converted with the registry rate
+    let gas = GAS_BASE_FEE + 150_000;
+    let native_fee = (gas + gas * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
+        * GAS_NUMERATOR_DEFAULT
+        / GAS_DENOMINATOR;
+    let cw20_fee = Uint128::from(native_fee / 10_000);
+    let agent: Option<AgentResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetAgent {
+                account_id: AGENT0.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        agent.unwrap().balance.cw20,
+        vec![Cw20CoinVerified {
+            address: cw20_contract.clone(),
+            amount: cw20_fee,
+        }]
+    );
+
+    // Agent withdraws the fee in cw20
+    app.execute_contract(
+        Addr::unchecked(AGENT0),
+        contract_addr,
+        &ExecuteMsg::WithdrawReward {},
+        &[],
+    )
+    .unwrap();
+    let balance: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            cw20_contract,
+            &cw20::Cw20QueryMsg::Balance {
+                address: AGENT_BENEFICIARY.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(balance.balance, cw20_fee);
+}
+
+#[test]
+fn test_cw20_fee_token_removed() {
+    let (mut app, cw_template_contract, cw20_contract) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let user = ANYONE;
+    let refill_balance_msg = cw20::Cw20ExecuteMsg::Send {
+        contract: contract_addr.to_string(),
+        amount: 10u128.into(),
+        msg: Default::default(),
+    };
+    app.execute_contract(
+        Addr::unchecked(user),
+        cw20_contract.clone(),
+        &refill_balance_msg,
+        &[],
+    )
+    .unwrap();
+
+    let update_fee_tokens = |fee_tokens: Vec<FeeTokenRate>| ExecuteMsg::UpdateSettings {
+        owner_id: None,
+        slot_granularity_time: None,
+        paused: None,
+        agent_fee: None,
+        gas_base_fee: None,
+        gas_action_fee: None,
+        gas_query_fee: None,
+        gas_wasm_query_fee: None,
+        gas_price: None,
+        proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
+        fee_tokens: Some(fee_tokens),
+        min_tasks_per_agent: None,
+        agents_eject_threshold: None,
+        chain_name: None,
+        gas_price_source: None,
+    };
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &update_fee_tokens(vec![FeeTokenRate {
+            token: FeeToken::Cw20(cw20_contract.clone()),
+            numerator: 1,
+            denominator: 10_000,
+        }]),
+        &[],
+    )
+    .unwrap();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: WasmMsg::Execute {
+                    contract_addr: cw20_contract.to_string(),
+                    msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                        recipient: AGENT0.to_string(),
+                        amount: 1u128.into(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+                .into(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![Cw20Coin {
+                address: cw20_contract.to_string(),
+                amount: 10u128.into(),
+            }],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: Some(FeeToken::Cw20(cw20_contract.clone())),
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    app.execute_contract(
+        Addr::unchecked(user),
+        contract_addr.clone(),
+        &create_task_msg,
+        &[],
+    )
+    .unwrap();
+
+    // Token gets removed while the task is scheduled
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &update_fee_tokens(vec![]),
+        &[],
+    )
+    .unwrap();
+
+    // quick agent register
+    let msg = ExecuteMsg::RegisterAgent {
+        payable_account_id: Some(AGENT_BENEFICIARY.to_string()),
+    };
+    app.execute_contract(Addr::unchecked(AGENT0), contract_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Task is ended instead of charging the agent fee in the native denom
+    app.update_block(add_little_time);
+    let res = app
+        .execute_contract(
+            Addr::unchecked(AGENT0),
+            contract_addr.clone(),
+            &ExecuteMsg::ProxyCall { task_hash: None },
+            &vec![],
+        )
+        .unwrap();
+    let ended = res
+        .events
+        .iter()
+        .find_map(|ev| TaskEnded::from_event(ev).ok())
+        .unwrap();
+    assert_eq!(ended.reason, TaskEndReason::FeeTokenRemoved);
+
+    // Whole cw20 deposit is refunded and the agent got nothing
+    let wallet_balances: GetWalletBalancesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetWalletBalances {
+                wallet: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        wallet_balances.cw20_balances,
+        vec![Cw20CoinVerified {
+            address: cw20_contract,
+            amount: 10u128.into(),
+        }]
+    );
+    let agent: Option<AgentResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr,
+            &QueryMsg::GetAgent {
+                account_id: AGENT0.to_string(),
+            },
+        )
+        .unwrap();
+    let agent = agent.unwrap();
+    assert!(agent.balance.native.is_empty());
+    assert!(agent.balance.cw20.is_empty());
+}
@@ -37,8 +37,18 @@ fn check_task_storage_structure() -> StdResult<()> {
         queries: None,
         transforms: None,
         version: version.version,
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
-    let task_id_str = "atom:05dbd09a8948de64d52e9da638b8709eb4f7cadf85a7c203c4b2889c8ae";
+    let task_id_str = "atom:8f8e9e867ec693e86aaca3e7262397c335828ba54b2687fad36523e0d71";
     let task_id = task_id_str.to_string().into_bytes();
 
     // create a task
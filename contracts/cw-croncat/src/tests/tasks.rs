@@ -3,57 +3,50 @@ use crate::contract::{
     GAS_ACTION_FEE, GAS_ADJUSTMENT_NUMERATOR_DEFAULT, GAS_BASE_FEE, GAS_DENOMINATOR,
     GAS_NUMERATOR_DEFAULT,
 };
-use crate::tests::helpers::proper_instantiate;
+use crate::tests::helpers::{cw721_template, proper_instantiate};
 use crate::ContractError;
 use cosmwasm_std::{
-    coin, coins, to_binary, Addr, BankMsg, CosmosMsg, StakingMsg, StdResult, Uint128, WasmMsg,
+    coin, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, Empty, GovMsg, IbcMsg, IbcTimeout,
+    StakingMsg, StdResult, Timestamp, Uint128, VoteOption, WasmMsg,
 };
-use cw2::ContractVersion;
 use cw_croncat_core::error::CoreError;
 use cw_croncat_core::msg::{
-    ExecuteMsg, GetBalancesResponse, GetSlotHashesResponse, GetSlotIdsResponse, QueryMsg,
+    ExecuteMsg, GetBalancesResponse, GetSlotHashesResponse, GetSlotIdsResponse, GetTasksResponse,
+    GetTasksWithQueriesResponse, PreviewScheduleResponse, QueryMsg, SimulateTaskResponse,
     TaskRequest, TaskResponse, TaskWithQueriesResponse,
 };
-use cw_croncat_core::types::{Action, Boundary, CheckedBoundary, GenericBalance, Interval, Task};
-use cw_multi_test::Executor;
+use cw_croncat_core::types::{Action, AdminAllowance, Boundary, Interval, SlotType};
+use cw_multi_test::{App, Executor};
 use cw_rules_core::types::{CroncatQuery, HasBalanceGte};
 use std::convert::TryInto;
 
 #[test]
 fn query_task_hash_success() {
-    let (app, cw_template_contract, _) = proper_instantiate();
+    let (mut app, cw_template_contract, _) = proper_instantiate();
     let contract_addr = cw_template_contract.addr();
 
     let to_address = String::from("you");
-    let amount = coins(1015, "earth");
+    let amount = coins(1015, NATIVE_DENOM);
     let bank = BankMsg::Send { to_address, amount };
     let msg: CosmosMsg = bank.clone().into();
-    let version = ContractVersion {
-        version: "0.0.1".to_string(),
-        contract: "nobidy".to_string(),
-    };
 
-    let task = Task {
-        owner_id: Addr::unchecked("nobody".to_string()),
+    let task = TaskRequest {
         interval: Interval::Immediate,
-        boundary: CheckedBoundary {
-            start: None,
-            end: None,
-            is_block_boundary: None,
-        },
+        boundary: None,
         stop_on_fail: false,
-        total_deposit: GenericBalance {
-            native: coins(37, NATIVE_DENOM),
-            cw20: Default::default(),
-        },
-        amount_for_one_task: Default::default(),
         actions: vec![Action {
             msg,
             gas_limit: Some(150_000),
         }],
         queries: None,
         transforms: None,
-        version: version.version,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
 
     // HASH CHECK!
@@ -62,14 +55,29 @@ fn query_task_hash_success() {
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTaskHash {
-                task: Box::new(task),
+                task: Box::new(task.clone()),
+                owner_id: ANYONE.to_string(),
             },
         )
         .unwrap();
     assert_eq!(
-        "atom:05dbd09a8948de64d52e9da638b8709eb4f7cadf85a7c203c4b2889c8ae",
+        "atom:4dcf86947ee6b0bcd5410a9d51db6e97195c1e0a9d5a3e6a45f7c1c3d3f",
         task_hash
     );
+
+    // Same hash once the task is created
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &ExecuteMsg::CreateTask { task },
+            &coins(100_000, NATIVE_DENOM),
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|ev| ev
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "task_hash" && attr.value == task_hash)));
 }
 
 #[test]
@@ -82,6 +90,10 @@ fn query_validate_interval_success() {
         Interval::Immediate,
         Interval::Block(12345),
         Interval::Cron("0 0 * * * *".to_string()),
+        Interval::CronTz {
+            crontab: "0 0 9 * * *".to_string(),
+            timezone: "Europe/Berlin".to_string(),
+        },
     ];
     for i in intervals.iter() {
         let valid: bool = app
@@ -95,6 +107,125 @@ fn query_validate_interval_success() {
             .unwrap();
         assert!(valid);
     }
+
+    let invalid_intervals: Vec<Interval> = vec![
+        Interval::Block(0),
+        Interval::Cron("not a crontab".to_string()),
+        Interval::CronTz {
+            crontab: "0 0 9 * * *".to_string(),
+            timezone: "Mars/Olympus_Mons".to_string(),
+        },
+    ];
+    for i in invalid_intervals.iter() {
+        let valid: bool = app
+            .wrap()
+            .query_wasm_smart(
+                &contract_addr.clone(),
+                &QueryMsg::ValidateInterval {
+                    interval: i.to_owned(),
+                },
+            )
+            .unwrap();
+        assert!(!valid);
+    }
+}
+
+#[test]
+fn query_preview_schedule() {
+    let (app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+    let height = app.block_info().height;
+    let first = height - height % 5 + 5;
+
+    let res: PreviewScheduleResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::PreviewSchedule {
+                interval: Interval::Block(5),
+                boundary: None,
+                count: 3,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        PreviewScheduleResponse {
+            slot_type: SlotType::Block,
+            slots: vec![first, first + 5, first + 10],
+            exhausted: false,
+        }
+    );
+
+    // Stops at the end boundary
+    let res: PreviewScheduleResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::PreviewSchedule {
+                interval: Interval::Block(5),
+                boundary: Some(Boundary::Height {
+                    start: None,
+                    end: Some((first + 7).into()),
+                }),
+                count: 5,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        PreviewScheduleResponse {
+            slot_type: SlotType::Block,
+            slots: vec![first, first + 5],
+            exhausted: true,
+        }
+    );
+
+    // Single slot for the tasks executed once
+    let res: PreviewScheduleResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::PreviewSchedule {
+                interval: Interval::Once,
+                boundary: None,
+                count: 5,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.slots.len(), 1);
+    assert!(res.exhausted);
+
+    // Crontab without future occurrences
+    let res: PreviewScheduleResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::PreviewSchedule {
+                interval: Interval::Cron("0 0 0 1 1 * 2020".to_string()),
+                boundary: None,
+                count: 5,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        PreviewScheduleResponse {
+            slot_type: SlotType::Cron,
+            slots: vec![],
+            exhausted: true,
+        }
+    );
+
+    let res: StdResult<PreviewScheduleResponse> = app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::PreviewSchedule {
+            interval: Interval::Cron("not a crontab".to_string()),
+            boundary: None,
+            count: 5,
+        },
+    );
+    assert!(res.is_err());
 }
 
 #[test]
@@ -119,6 +250,12 @@ fn query_get_tasks() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -132,28 +269,33 @@ fn query_get_tasks() {
     .unwrap();
 
     // check storage has the task
-    let all_tasks: Vec<TaskResponse> = app
+    let all_tasks: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
         .unwrap();
-    assert_eq!(all_tasks.len(), 1);
+    assert_eq!(all_tasks.tasks.len(), 1);
+    assert_eq!(all_tasks.next_cursor, None);
 
-    let owner_tasks: Vec<TaskResponse> = app
+    let owner_tasks: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasksByOwner {
                 owner_id: ANYONE.to_string(),
+                start_after: None,
+                limit: None,
+                start_after: None,
+                limit: None,
             },
         )
         .unwrap();
-    assert_eq!(owner_tasks.len(), 1);
+    assert_eq!(owner_tasks.tasks.len(), 1);
 }
 
 #[test]
@@ -164,7 +306,7 @@ fn query_get_tasks_pagination() {
     let validator = String::from("you");
     let tasks_amnt: u64 = 10;
     let from_index = 3;
-    let limit = 2;
+    let limit: u64 = 2;
     let new_msg = |amount| ExecuteMsg::CreateTask {
         task: TaskRequest {
             interval: Interval::Immediate,
@@ -181,6 +323,12 @@ fn query_get_tasks_pagination() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -194,108 +342,144 @@ fn query_get_tasks_pagination() {
         )
         .unwrap();
     }
-    let mut all_tasks: Vec<TaskResponse> = app
+    let all_tasks: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
         .unwrap();
-    assert_eq!(all_tasks.len(), tasks_amnt as usize);
+    assert_eq!(all_tasks.tasks.len(), tasks_amnt as usize);
+    assert_eq!(all_tasks.next_cursor, None);
+    let mut all_tasks = all_tasks.tasks;
+
+    // walk all the tasks page by page
+    let mut walked_tasks: Vec<TaskResponse> = vec![];
+    let mut start_after = None;
+    loop {
+        let page: GetTasksResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &contract_addr.clone(),
+                &QueryMsg::GetTasks {
+                    start_after,
+                    limit: Some(limit),
+                },
+            )
+            .unwrap();
+        assert!(page.tasks.len() <= limit as usize);
+        walked_tasks.extend(page.tasks);
+        match page.next_cursor {
+            Some(cursor) => start_after = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(walked_tasks, all_tasks);
 
-    // check we get right amount of tasks
-    let part_of_tasks: Vec<TaskResponse> = app
+    // check we get right tasks after the cursor, in right order
+    let part_of_tasks: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: Some(from_index),
-                limit: None,
+                start_after: Some(all_tasks[from_index].task_hash.clone()),
+                limit: Some(limit),
             },
         )
         .unwrap();
-    let expected_amnt: usize = (tasks_amnt - from_index).try_into().unwrap();
-    assert_eq!(part_of_tasks.len(), expected_amnt);
+    let expected_tasks = &all_tasks[from_index + 1..from_index + 1 + limit as usize];
+    assert_eq!(part_of_tasks.tasks, expected_tasks);
+    assert_eq!(
+        part_of_tasks.next_cursor,
+        Some(expected_tasks[1].task_hash.clone())
+    );
 
-    // Check it's in right order
-    for i in 0..expected_amnt {
-        assert_eq!(
-            all_tasks[from_index as usize + i].task_hash,
-            part_of_tasks[i].task_hash
-        );
-    }
+    // Edge cases
 
-    // and with limit
-    let part_of_tasks: Vec<TaskResponse> = app
+    // Nothing after the last task
+    let out_of_bounds: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: Some(from_index),
-                limit: Some(limit),
+                start_after: Some(all_tasks.last().unwrap().task_hash.clone()),
+                limit: None,
             },
         )
         .unwrap();
-    let expected_amnt: usize = (limit).try_into().unwrap();
-    assert_eq!(part_of_tasks.len(), expected_amnt);
-
-    // Edge cases
+    assert!(out_of_bounds.tasks.is_empty());
+    assert_eq!(out_of_bounds.next_cursor, None);
 
-    // Index out of bounds, so we return nothing
-    let from_index = tasks_amnt;
-    let out_of_bounds: Vec<TaskResponse> = app
+    // Returns as many elements as possible without a panic
+    let from_index = tasks_amnt as usize - 3;
+    let two_last_elements: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: Some(from_index),
-                limit: None,
+                start_after: Some(all_tasks[from_index].task_hash.clone()),
+                limit: Some(tasks_amnt),
             },
         )
         .unwrap();
-    assert!(out_of_bounds.is_empty());
+    assert_eq!(two_last_elements.tasks.len(), 2);
+    assert_eq!(two_last_elements.next_cursor, None);
 
-    // Returns as many elements as possible without a panic
-    let from_index = tasks_amnt - 2;
-    let two_last_elements: Vec<TaskResponse> = app
+    // Zero limit still returns a task, so the paging doesn't stop early
+    let zero_limit: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: Some(from_index),
-                limit: Some(tasks_amnt),
+                start_after: None,
+                limit: Some(0),
             },
         )
         .unwrap();
-    assert_eq!(two_last_elements.len(), 2);
+    assert_eq!(zero_limit.tasks, all_tasks[..1]);
+    assert_eq!(zero_limit.next_cursor, Some(all_tasks[0].task_hash.clone()));
 
     // Removed task shouldn't reorder things
-    let removed_index = from_index as usize;
+    let removed_index = from_index + 1;
+    let removed_hash = all_tasks
+        .remove(removed_index) // We removed hash from original vector to match
+        .task_hash;
     app.execute_contract(
         Addr::unchecked(VERY_RICH),
         contract_addr.clone(),
         &ExecuteMsg::RemoveTask {
-            task_hash: all_tasks
-                .remove(removed_index) // We removed hash from original vector to match
-                .task_hash,
+            task_hash: removed_hash.clone(),
         },
         &vec![],
     )
     .unwrap();
-    let new_tasks: Vec<TaskResponse> = app
+    let new_tasks: GetTasksResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTasks {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(new_tasks.tasks, all_tasks);
+
+    // Cursor of the removed task still works
+    let after_removed: GetTasksResponse = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: None,
+                start_after: Some(removed_hash),
                 limit: None,
             },
         )
         .unwrap();
-    assert_eq!(new_tasks, all_tasks);
+    assert_eq!(after_removed.tasks, all_tasks[removed_index..]);
 }
 
 #[test]
@@ -320,6 +504,12 @@ fn check_task_create_fail_cases() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     // let task_id_str = "95c916a53fa9d26deef094f7e1ee31c00a2d47b8bf474b2e06d39aebfb1fecc7".to_string();
@@ -351,12 +541,18 @@ fn check_task_create_fail_cases() -> StdResult<()> {
         agents_eject_threshold: None,
         gas_price: None,
         proxy_callback_gas: None,
+        ibc_transfer_timeout: None,
+        gov_msgs_enabled: None,
+        stargate_type_urls: None,
+        admin_allowlist: None,
         slot_granularity_time: None,
         min_tasks_per_agent: None,
         gas_base_fee: None,
         gas_action_fee: None,
         gas_query_fee: None,
         gas_wasm_query_fee: None,
+        fee_tokens: None,
+        gas_price_source: None,
     };
     app.execute_contract(
         Addr::unchecked(ADMIN),
@@ -392,12 +588,18 @@ fn check_task_create_fail_cases() -> StdResult<()> {
             agents_eject_threshold: None,
             gas_price: None,
             proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: None,
+            stargate_type_urls: None,
+            admin_allowlist: None,
             slot_granularity_time: None,
             min_tasks_per_agent: None,
             gas_base_fee: None,
             gas_action_fee: None,
             gas_query_fee: None,
             gas_wasm_query_fee: None,
+            fee_tokens: None,
+            gas_price_source: None,
         },
         &vec![],
     )
@@ -424,6 +626,12 @@ fn check_task_create_fail_cases() -> StdResult<()> {
                 queries: None,
                 transforms: None,
                 cw20_coins: vec![],
+                max_executions: None,
+                trigger: None,
+                callback_addr: None,
+                fee_token: None,
+                max_gas_price: None,
+                auto_refill: None,
             },
         },
         &coins(13, NATIVE_DENOM),
@@ -454,6 +662,12 @@ fn check_task_create_fail_cases() -> StdResult<()> {
                 queries: None,
                 transforms: None,
                 cw20_coins: vec![],
+                max_executions: None,
+                trigger: None,
+                callback_addr: None,
+                fee_token: None,
+                max_gas_price: None,
+                auto_refill: None,
             },
         },
         &coins(13, NATIVE_DENOM),
@@ -480,6 +694,12 @@ fn check_task_create_fail_cases() -> StdResult<()> {
                     queries: None,
                     transforms: None,
                     cw20_coins: vec![],
+                    max_executions: None,
+                    trigger: None,
+                    callback_addr: None,
+                    fee_token: None,
+                    max_gas_price: None,
+                    auto_refill: None,
                 },
             },
             &coins(13, NATIVE_DENOM),
@@ -535,6 +755,12 @@ fn check_task_create_fail_cases() -> StdResult<()> {
                     queries: None,
                     transforms: None,
                     cw20_coins: vec![],
+                    max_executions: None,
+                    trigger: None,
+                    callback_addr: None,
+                    fee_token: None,
+                    max_gas_price: None,
+                    auto_refill: None,
                 },
             },
             &coins(315006, NATIVE_DENOM),
@@ -574,6 +800,12 @@ fn check_task_create_success() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -646,6 +878,108 @@ fn check_task_create_success() -> StdResult<()> {
     Ok(())
 }
 
+#[test]
+fn check_task_create_at_and_every_success() -> StdResult<()> {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+    // slot_granularity_time is 10 seconds by default
+    let granularity = 10_000_000_000;
+    let now = app.block_info().time.nanos();
+
+    let validator = String::from("you");
+    let amount = coin(3, NATIVE_DENOM);
+    let stake = StakingMsg::Delegate { validator, amount };
+    let msg: CosmosMsg = stake.into();
+
+    // Runs exactly in two minutes
+    let at = Timestamp::from_nanos(now + 120_000_000_000);
+    // Runs every 90 seconds, counted from the unix epoch
+    let period = 90_000_000_000;
+    let next_period = (now / period + 1) * period;
+
+    for interval in [Interval::At(at), Interval::Every { seconds: 90 }] {
+        let create_task_msg = ExecuteMsg::CreateTask {
+            task: TaskRequest {
+                interval,
+                boundary: None,
+                stop_on_fail: false,
+                actions: vec![Action {
+                    msg: msg.clone(),
+                    gas_limit: Some(150_000),
+                }],
+                queries: None,
+                transforms: None,
+                cw20_coins: vec![],
+                max_executions: None,
+                trigger: None,
+                callback_addr: None,
+                fee_token: None,
+                max_gas_price: None,
+                auto_refill: None,
+            },
+        };
+        app.execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap();
+    }
+
+    // Both go into time slots
+    let slot_ids: GetSlotIdsResponse = app
+        .wrap()
+        .query_wasm_smart(&contract_addr.clone(), &QueryMsg::GetSlotIds {})
+        .unwrap();
+    let mut expected = vec![
+        at.nanos() - at.nanos() % granularity,
+        next_period - next_period % granularity,
+    ];
+    expected.sort_unstable();
+    let empty: Vec<u64> = Vec::new();
+    assert_eq!(expected, slot_ids.time_ids);
+    assert_eq!(empty, slot_ids.block_ids);
+
+    // Zero period is not a valid interval
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Every { seconds: 0 },
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg,
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Interval invalid".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn check_task_with_queries_create_success() -> StdResult<()> {
     let (mut app, cw_template_contract, _) = proper_instantiate();
@@ -671,6 +1005,12 @@ fn check_task_with_queries_create_success() -> StdResult<()> {
             })]),
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -686,25 +1026,27 @@ fn check_task_with_queries_create_success() -> StdResult<()> {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             &contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
 
     let tasks: Vec<TaskResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksResponse>(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
 
     assert_eq!(tasks_with_queries.len(), 1);
     assert_eq!(tasks.len(), 0);
@@ -746,6 +1088,12 @@ fn check_task_with_queries_and_without_create_success() -> StdResult<()> {
             })]),
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -761,6 +1109,12 @@ fn check_task_with_queries_and_without_create_success() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -785,29 +1139,66 @@ fn check_task_with_queries_and_without_create_success() -> StdResult<()> {
 
     let tasks_with_queries: Vec<TaskWithQueriesResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
             &contract_addr.clone(),
             &QueryMsg::GetTasksWithQueries {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
 
     let tasks: Vec<TaskResponse> = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<GetTasksResponse>(
             &contract_addr.clone(),
             &QueryMsg::GetTasks {
-                from_index: None,
+                start_after: None,
                 limit: None,
             },
         )
-        .unwrap();
+        .unwrap()
+        .tasks;
 
     assert_eq!(tasks_with_queries.len(), 1);
     assert_eq!(tasks.len(), 1);
 
+    // Owner view has both, in the order of the hashes
+    let mut expected_hashes = vec![
+        tasks_with_queries[0].task_hash.clone(),
+        tasks[0].task_hash.clone(),
+    ];
+    expected_hashes.sort();
+    let owner_tasks: GetTasksResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTasksByOwner {
+                owner_id: ANYONE.to_string(),
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(owner_tasks.tasks.len(), 1);
+    assert_eq!(owner_tasks.tasks[0].task_hash, expected_hashes[0]);
+    assert_eq!(owner_tasks.next_cursor, Some(expected_hashes[0].clone()));
+    let owner_tasks: GetTasksResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTasksByOwner {
+                owner_id: ANYONE.to_string(),
+                start_after: owner_tasks.next_cursor,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(owner_tasks.tasks.len(), 1);
+    assert_eq!(owner_tasks.tasks[0].task_hash, expected_hashes[1]);
+    assert_eq!(owner_tasks.next_cursor, None);
+
     let mut has_created_hash: bool = false;
     for e in res.events {
         for a in e.attributes {
@@ -848,6 +1239,12 @@ fn check_remove_create() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
 
@@ -946,14 +1343,15 @@ fn check_remove_create() -> StdResult<()> {
 }
 
 #[test]
-fn check_refill_create() -> StdResult<()> {
+fn check_update_task() -> StdResult<()> {
     let (mut app, cw_template_contract, _) = proper_instantiate();
     let contract_addr = cw_template_contract.addr();
+    let now = app.block_info().time.nanos();
 
     let validator = String::from("you");
     let amount = coin(3, NATIVE_DENOM);
     let stake = StakingMsg::Delegate { validator, amount };
-    let msg: CosmosMsg = stake.clone().into();
+    let msg: CosmosMsg = stake.into();
 
     let create_task_msg = ExecuteMsg::CreateTask {
         task: TaskRequest {
@@ -967,10 +1365,14 @@ fn check_refill_create() -> StdResult<()> {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
-
-    // create a task
     let create_task_resp = app
         .execute_contract(
             Addr::unchecked(ANYONE),
@@ -988,30 +1390,75 @@ fn check_refill_create() -> StdResult<()> {
         }
     }
 
-    // refill task
-    let res = app
+    // Only owner can update the task
+    let update_task_msg = ExecuteMsg::UpdateTask {
+        task_hash: task_hash.clone(),
+        interval: Some(Interval::Every { seconds: 90 }),
+        boundary: None,
+        stop_on_fail: Some(true),
+        gas_limits: None,
+    };
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &update_task_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, res_err.downcast().unwrap());
+
+    // Gas limits have to match the actions
+    let res_err = app
         .execute_contract(
             Addr::unchecked(ANYONE),
             contract_addr.clone(),
-            &ExecuteMsg::RefillTaskBalance {
+            &ExecuteMsg::UpdateTask {
                 task_hash: task_hash.clone(),
+                interval: None,
+                boundary: None,
+                stop_on_fail: None,
+                gas_limits: Some(vec![Some(150_000), Some(150_000)]),
             },
-            &coins(3, NATIVE_DENOM),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Gas limits invalid".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+
+    // Move the task from block slots to time slots
+    let update_task_resp = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &update_task_msg,
+            &[],
         )
         .unwrap();
-    // Assert returned event attributes include total
-    let mut matches_new_totals: bool = false;
-    for e in res.events {
-        for a in e.attributes {
-            if a.key == "total_deposit" && a.value == r#"["315009atom"]"#.to_string() {
-                matches_new_totals = true;
-            }
-        }
-    }
-    assert!(matches_new_totals);
+    assert!(update_task_resp.events.iter().any(|e| e
+        .attributes
+        .iter()
+        .any(|a| a.key == "task_hash" && a.value == task_hash)));
 
-    // check the task totals
-    let new_task: Option<TaskResponse> = app
+    let slot_ids: GetSlotIdsResponse = app
+        .wrap()
+        .query_wasm_smart(&contract_addr.clone(), &QueryMsg::GetSlotIds {})
+        .unwrap();
+    let period = 90_000_000_000;
+    let next_period = (now / period + 1) * period;
+    let empty: Vec<u64> = Vec::new();
+    assert_eq!(
+        vec![next_period - next_period % 10_000_000_000],
+        slot_ids.time_ids
+    );
+    assert_eq!(empty, slot_ids.block_ids);
+
+    // Task is still reachable by the original hash
+    let task: Option<TaskResponse> = app
         .wrap()
         .query_wasm_smart(
             &contract_addr.clone(),
@@ -1020,10 +1467,402 @@ fn check_refill_create() -> StdResult<()> {
             },
         )
         .unwrap();
-    assert!(new_task.is_some());
-
-    if let Some(t) = new_task {
-        assert_eq!(Addr::unchecked(ANYONE), t.owner_id);
+    let task = task.unwrap();
+    assert_eq!(task.task_hash, task_hash);
+    assert_eq!(task.interval, Interval::Every { seconds: 90 });
+    assert!(task.stop_on_fail);
+
+    // Raising the gas limit requires more deposit
+    let update_task_msg = ExecuteMsg::UpdateTask {
+        task_hash: task_hash.clone(),
+        interval: None,
+        boundary: None,
+        stop_on_fail: None,
+        gas_limits: Some(vec![Some(3_000_000)]),
+    };
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &update_task_msg,
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        res_err.downcast::<ContractError>().unwrap(),
+        ContractError::CoreError(CoreError::NotEnoughNative { .. })
+    ));
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &update_task_msg,
+        &coins(150_000, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    let task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    let task = task.unwrap();
+    assert_eq!(task.actions[0].gas_limit, Some(3_000_000));
+    assert_eq!(task.total_deposit, coins(465_006, NATIVE_DENOM));
+
+    // Still scheduled only once
+    let slot_hashes: GetSlotHashesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetSlotHashes { slot: None },
+        )
+        .unwrap();
+    assert_eq!(slot_hashes.time_task_hash, vec![task_hash.clone()]);
+
+    // Original parameters hash to the hash the updated task kept
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Task already exists: an updated task keeps this hash".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+
+    // Hash is free again once the updated task is removed
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::RemoveTask { task_hash },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(315006, NATIVE_DENOM),
+    )
+    .unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn check_pause_resume_task() -> StdResult<()> {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let validator = String::from("you");
+    let amount = coin(3, NATIVE_DENOM);
+    let stake = StakingMsg::Delegate { validator, amount };
+    let msg: CosmosMsg = stake.into();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Immediate,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: msg.clone(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let create_task_resp = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap();
+    let mut task_hash: String = String::new();
+    for e in create_task_resp.events {
+        for a in e.attributes {
+            if a.key == "task_hash" && a.value.len() > 0 {
+                task_hash = a.value;
+            }
+        }
+    }
+
+    // Another person can't pause the task
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::PauseTask {
+                task_hash: task_hash.clone(),
+            },
+            &vec![],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, res_err.downcast().unwrap());
+
+    // Can't resume task that is not paused
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &ExecuteMsg::ResumeTask {
+                task_hash: task_hash.clone(),
+            },
+            &vec![],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Task not paused".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+
+    // Pause the task
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::PauseTask {
+            task_hash: task_hash.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+    let res_err = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &ExecuteMsg::PauseTask {
+                task_hash: task_hash.clone(),
+            },
+            &vec![],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CustomError {
+            val: "Task already paused".to_string()
+        },
+        res_err.downcast().unwrap()
+    );
+
+    // Task is not scheduled, but keeps its deposit
+    let slot_ids: GetSlotIdsResponse = app
+        .wrap()
+        .query_wasm_smart(&contract_addr.clone(), &QueryMsg::GetSlotIds {})
+        .unwrap();
+    let empty: Vec<u64> = Vec::new();
+    assert_eq!(empty, slot_ids.time_ids);
+    assert_eq!(empty, slot_ids.block_ids);
+    let task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    let task = task.unwrap();
+    assert!(task.paused);
+    assert_eq!(task.total_deposit, coins(315006, NATIVE_DENOM));
+
+    // Resume puts it back into the next slot
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::ResumeTask {
+            task_hash: task_hash.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+    let slot_ids: GetSlotIdsResponse = app
+        .wrap()
+        .query_wasm_smart(&contract_addr.clone(), &QueryMsg::GetSlotIds {})
+        .unwrap();
+    assert_eq!(empty, slot_ids.time_ids);
+    assert_eq!(vec![12346], slot_ids.block_ids);
+    let task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert!(!task.unwrap().paused);
+
+    // Paused task with queries shows up for agents with its status
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Immediate,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg,
+                gas_limit: Some(150_000),
+            }],
+            queries: Some(vec![CroncatQuery::HasBalanceGte(HasBalanceGte {
+                address: "foo".to_string(),
+                required_balance: coins(5, "bar").into(),
+            })]),
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    let create_task_resp = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap();
+    let mut task_hash: String = String::new();
+    for e in create_task_resp.events {
+        for a in e.attributes {
+            if a.key == "task_hash" && a.value.len() > 0 {
+                task_hash = a.value;
+            }
+        }
+    }
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::PauseTask {
+            task_hash: task_hash.clone(),
+        },
+        &vec![],
+    )
+    .unwrap();
+    let tasks: Vec<TaskWithQueriesResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksWithQueriesResponse>(
+            &contract_addr.clone(),
+            &QueryMsg::GetTasksWithQueries {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].task_hash, task_hash);
+    assert!(tasks[0].paused);
+
+    Ok(())
+}
+
+#[test]
+fn check_refill_create() -> StdResult<()> {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let validator = String::from("you");
+    let amount = coin(3, NATIVE_DENOM);
+    let stake = StakingMsg::Delegate { validator, amount };
+    let msg: CosmosMsg = stake.clone().into();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Immediate,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg,
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    // create a task
+    let create_task_resp = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap();
+    let mut task_hash: String = String::new();
+    for e in create_task_resp.events {
+        for a in e.attributes {
+            if a.key == "task_hash" && a.value.len() > 0 {
+                task_hash = a.value;
+            }
+        }
+    }
+
+    // refill task
+    let res = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &ExecuteMsg::RefillTaskBalance {
+                task_hash: task_hash.clone(),
+            },
+            &coins(3, NATIVE_DENOM),
+        )
+        .unwrap();
+    // Assert returned event attributes include total
+    let mut matches_new_totals: bool = false;
+    for e in res.events {
+        for a in e.attributes {
+            if a.key == "total_deposit" && a.value == r#"["315009atom"]"#.to_string() {
+                matches_new_totals = true;
+            }
+        }
+    }
+    assert!(matches_new_totals);
+
+    // check the task totals
+    let new_task: Option<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart(
+            &contract_addr.clone(),
+            &QueryMsg::GetTask {
+                task_hash: task_hash.clone(),
+            },
+        )
+        .unwrap();
+    assert!(new_task.is_some());
+
+    if let Some(t) = new_task {
+        assert_eq!(Addr::unchecked(ANYONE), t.owner_id);
         assert_eq!(coins(315009, NATIVE_DENOM), t.total_deposit);
     }
 
@@ -1033,73 +1872,558 @@ fn check_refill_create() -> StdResult<()> {
         .query_wasm_smart(&contract_addr.clone(), &QueryMsg::GetBalances {})
         .unwrap();
     assert_eq!(
-        coins(315010, NATIVE_DENOM),
-        balances.available_balance.native
+        coins(315010, NATIVE_DENOM),
+        balances.available_balance.native
+    );
+
+    Ok(())
+}
+
+#[test]
+fn check_gas_minimum() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let validator = String::from("you");
+    let amount = coin(3, NATIVE_DENOM);
+    let stake = StakingMsg::Delegate { validator, amount };
+    let msg: CosmosMsg = stake.clone().into();
+    let gas_limit = 150_000;
+    let base_gas = GAS_BASE_FEE;
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Immediate,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg,
+                gas_limit: Some(gas_limit),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    // create 1 token off task
+    let gas_for_two = (base_gas + gas_limit) * 2;
+    let enough_for_two = u128::from(
+        (gas_for_two + gas_for_two * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
+            * GAS_NUMERATOR_DEFAULT
+            / GAS_DENOMINATOR
+            + 3 * 2,
+    );
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(enough_for_two - 1, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CoreError(CoreError::NotEnoughNative {
+            denom: NATIVE_DENOM.to_string(),
+            lack: Uint128::from(1u128)
+        })
+    );
+
+    // create a task
+    let res = app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(enough_for_two, NATIVE_DENOM),
+    );
+    assert!(res.is_ok());
+}
+
+#[test]
+fn check_ibc_transfer_create() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let transfer = IbcMsg::Transfer {
+        channel_id: "channel-0".to_string(),
+        to_address: "cosmos1recipient".to_string(),
+        amount: coin(10, NATIVE_DENOM),
+        timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(0)),
+    };
+    let gas_limit = 150_000;
+    let base_gas = GAS_BASE_FEE;
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Block(10),
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: transfer.into(),
+                gas_limit: Some(gas_limit),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+    // Transferred amount is reserved like a bank send
+    let gas = base_gas + gas_limit;
+    let price_for_one = u128::from(
+        (gas + gas * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
+            * GAS_NUMERATOR_DEFAULT
+            / GAS_DENOMINATOR,
+    );
+    let enough_for_two = (price_for_one + 10) * 2;
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(enough_for_two - 1, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CoreError(CoreError::NotEnoughNative {
+            denom: NATIVE_DENOM.to_string(),
+            lack: Uint128::from(1u128)
+        })
+    );
+
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &create_task_msg,
+        &coins(enough_for_two, NATIVE_DENOM),
+    )
+    .unwrap();
+    let tasks: Vec<TaskResponse> = app
+        .wrap()
+        .query_wasm_smart::<GetTasksResponse>(
+            contract_addr,
+            &QueryMsg::GetTasksByOwner {
+                owner_id: ANYONE.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .tasks;
+    assert_eq!(
+        tasks[0].amount_for_one_task_native,
+        coins(price_for_one + 10, NATIVE_DENOM)
+    );
+}
+
+#[test]
+fn check_gov_vote_create() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: GovMsg::Vote {
+                    proposal_id: 1,
+                    vote: VoteOption::Yes,
+                }
+                .into(),
+                gas_limit: None,
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    // Governance actions are disabled by default
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CustomError {
+            val: "Governance actions disabled".to_string()
+        }
+    );
+
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &ExecuteMsg::UpdateSettings {
+            owner_id: None,
+            slot_granularity_time: None,
+            paused: None,
+            agent_fee: None,
+            gas_base_fee: None,
+            gas_action_fee: None,
+            gas_query_fee: None,
+            gas_wasm_query_fee: None,
+            gas_price: None,
+            proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: Some(true),
+            stargate_type_urls: None,
+            admin_allowlist: None,
+            min_tasks_per_agent: None,
+            agents_eject_threshold: None,
+            chain_name: None,
+            fee_tokens: None,
+            gas_price_source: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr,
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+}
+
+#[test]
+fn check_stargate_create() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let type_url = "/osmosis.tokenfactory.v1beta1.MsgMint".to_string();
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: CosmosMsg::Stargate {
+                    type_url: type_url.clone(),
+                    value: Binary::from(b"mint".to_vec()),
+                },
+                gas_limit: None,
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    // Type url is not in the allowlist
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ANYONE),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CustomError {
+            val: "Stargate type url not allowed".to_string()
+        }
+    );
+
+    // Authz messages can't be allowlisted
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &ExecuteMsg::UpdateSettings {
+                owner_id: None,
+                slot_granularity_time: None,
+                paused: None,
+                agent_fee: None,
+                gas_base_fee: None,
+                gas_action_fee: None,
+                gas_query_fee: None,
+                gas_wasm_query_fee: None,
+                gas_price: None,
+                proxy_callback_gas: None,
+                ibc_transfer_timeout: None,
+                gov_msgs_enabled: None,
+                stargate_type_urls: Some(vec!["/cosmos.authz.v1beta1.MsgExec".to_string()]),
+                admin_allowlist: None,
+                min_tasks_per_agent: None,
+                agents_eject_threshold: None,
+                chain_name: None,
+                fee_tokens: None,
+                gas_price_source: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CustomError {
+            val: "Stargate type url not allowed".to_string()
+        }
     );
 
-    Ok(())
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
+        contract_addr.clone(),
+        &ExecuteMsg::UpdateSettings {
+            owner_id: None,
+            slot_granularity_time: None,
+            paused: None,
+            agent_fee: None,
+            gas_base_fee: None,
+            gas_action_fee: None,
+            gas_query_fee: None,
+            gas_wasm_query_fee: None,
+            gas_price: None,
+            proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: None,
+            stargate_type_urls: Some(vec![type_url]),
+            admin_allowlist: None,
+            min_tasks_per_agent: None,
+            agents_eject_threshold: None,
+            chain_name: None,
+            fee_tokens: None,
+            gas_price_source: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr,
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
 }
 
 #[test]
-fn check_gas_minimum() {
+fn check_nft_transfer_create() {
     let (mut app, cw_template_contract, _) = proper_instantiate();
     let contract_addr = cw_template_contract.addr();
 
-    let validator = String::from("you");
-    let amount = coin(3, NATIVE_DENOM);
-    let stake = StakingMsg::Delegate { validator, amount };
-    let msg: CosmosMsg = stake.clone().into();
-    let gas_limit = 150_000;
-    let base_gas = GAS_BASE_FEE;
+    let cw721_id = app.store_code(cw721_template());
+    let cw721_addr = app
+        .instantiate_contract(
+            cw721_id,
+            Addr::unchecked(ADMIN),
+            &cw721_base::msg::InstantiateMsg {
+                name: "Name".to_string(),
+                symbol: "Symbol".to_string(),
+                minter: ADMIN.to_string(),
+            },
+            &[],
+            "cw721",
+            None,
+        )
+        .unwrap();
+    let mint_msg: cw721_base::ExecuteMsg<Option<Empty>, Empty> =
+        cw721_base::ExecuteMsg::Mint(cw721_base::MintMsg {
+            token_id: "vested".to_string(),
+            owner: ANYONE.to_string(),
+            token_uri: None,
+            extension: None,
+        });
+    app.execute_contract(Addr::unchecked(ADMIN), cw721_addr.clone(), &mint_msg, &[])
+        .unwrap();
 
     let create_task_msg = ExecuteMsg::CreateTask {
         task: TaskRequest {
-            interval: Interval::Immediate,
+            interval: Interval::Once,
             boundary: None,
             stop_on_fail: false,
             actions: vec![Action {
-                msg,
-                gas_limit: Some(gas_limit),
+                msg: WasmMsg::Execute {
+                    contract_addr: cw721_addr.to_string(),
+                    msg: to_binary(&cw721::Cw721ExecuteMsg::TransferNft {
+                        recipient: "beneficiary".to_string(),
+                        token_id: "vested".to_string(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+                .into(),
+                gas_limit: Some(150_000),
             }],
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
-    // create 1 token off task
-    let gas_for_two = (base_gas + gas_limit) * 2;
-    let enough_for_two = u128::from(
-        (gas_for_two + gas_for_two * 5 / 100) * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
-            * GAS_NUMERATOR_DEFAULT
-            / GAS_DENOMINATOR
-            + 3 * 2,
-    );
+
+    // Can't schedule the transfer of someone else's NFT
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr,
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
+}
+
+#[test]
+fn check_admin_actions_create() {
+    let (mut app, cw_template_contract, cw20_addr) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let create_task_msg = ExecuteMsg::CreateTask {
+        task: TaskRequest {
+            interval: Interval::Once,
+            boundary: None,
+            stop_on_fail: false,
+            actions: vec![Action {
+                msg: WasmMsg::Migrate {
+                    contract_addr: cw20_addr.to_string(),
+                    new_code_id: 1,
+                    msg: to_binary(&Empty {}).unwrap(),
+                }
+                .into(),
+                gas_limit: Some(150_000),
+            }],
+            queries: None,
+            transforms: None,
+            cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
+        },
+    };
+
+    // Contract is not in the allowlist
     let res: ContractError = app
         .execute_contract(
             Addr::unchecked(ANYONE),
             contract_addr.clone(),
             &create_task_msg,
-            &coins(enough_for_two - 1, NATIVE_DENOM),
+            &coins(300_000, NATIVE_DENOM),
         )
         .unwrap_err()
         .downcast()
         .unwrap();
     assert_eq!(
         res,
-        ContractError::CoreError(CoreError::NotEnoughNative {
-            denom: NATIVE_DENOM.to_string(),
-            lack: Uint128::from(1u128)
-        })
+        ContractError::CustomError {
+            val: "Admin action not allowed".to_string()
+        }
     );
 
-    // create a task
-    let res = app.execute_contract(
-        Addr::unchecked(ANYONE),
+    app.execute_contract(
+        Addr::unchecked(ADMIN),
         contract_addr.clone(),
-        &create_task_msg,
-        &coins(enough_for_two, NATIVE_DENOM),
+        &ExecuteMsg::UpdateSettings {
+            owner_id: None,
+            slot_granularity_time: None,
+            paused: None,
+            agent_fee: None,
+            gas_base_fee: None,
+            gas_action_fee: None,
+            gas_query_fee: None,
+            gas_wasm_query_fee: None,
+            gas_price: None,
+            proxy_callback_gas: None,
+            ibc_transfer_timeout: None,
+            gov_msgs_enabled: None,
+            stargate_type_urls: None,
+            admin_allowlist: Some(vec![AdminAllowance {
+                contract_addr: cw20_addr,
+                task_owner: Addr::unchecked(ANYONE),
+            }]),
+            min_tasks_per_agent: None,
+            agents_eject_threshold: None,
+            chain_name: None,
+            fee_tokens: None,
+            gas_price_source: None,
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Only the allowed task owner can manage the contract
+    let res: ContractError = app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            contract_addr.clone(),
+            &create_task_msg,
+            &coins(300_000, NATIVE_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        res,
+        ContractError::CustomError {
+            val: "Admin action not allowed".to_string()
+        }
     );
-    assert!(res.is_ok());
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr,
+        &create_task_msg,
+        &coins(300_000, NATIVE_DENOM),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -1130,6 +2454,12 @@ fn check_gas_default() {
             queries: None,
             transforms: None,
             cw20_coins: vec![],
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         },
     };
     // create 1 token off task
@@ -1172,3 +2502,219 @@ fn check_gas_default() {
     );
     assert!(res.is_ok());
 }
+
+#[test]
+fn query_simulate_task() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let validator = String::from("you");
+    let amount = coin(3, NATIVE_DENOM);
+    let stake = StakingMsg::Delegate { validator, amount };
+    let msg: CosmosMsg = stake.into();
+    let task = TaskRequest {
+        interval: Interval::Immediate,
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg,
+            gas_limit: None,
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+
+    let gas_for_one = GAS_BASE_FEE + GAS_ACTION_FEE;
+    let gas_for_one_with_fee = gas_for_one + gas_for_one * 5 / 100;
+    let amount_for_one = u128::from(
+        gas_for_one_with_fee * GAS_ADJUSTMENT_NUMERATOR_DEFAULT / GAS_DENOMINATOR
+            * GAS_NUMERATOR_DEFAULT
+            / GAS_DENOMINATOR
+            + 3,
+    );
+
+    let res: SimulateTaskResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::SimulateTask {
+                task: Box::new(task.clone()),
+                owner_id: ANYONE.to_string(),
+            },
+        )
+        .unwrap();
+    let task_hash: String = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::GetTaskHash {
+                task: Box::new(task.clone()),
+                owner_id: ANYONE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.error, None);
+    assert_eq!(res.task_hash, Some(task_hash));
+    assert_eq!(res.gas_amount, gas_for_one);
+    assert_eq!(
+        res.amount_for_one_task.native,
+        coins(amount_for_one, NATIVE_DENOM)
+    );
+    assert_eq!(res.min_deposit.native, coins(amount_for_one, NATIVE_DENOM));
+    assert_eq!(
+        res.min_recurring_deposit.native,
+        coins(amount_for_one * 2, NATIVE_DENOM)
+    );
+
+    // The simulated minimum is enough to create the task
+    app.execute_contract(
+        Addr::unchecked(ANYONE),
+        contract_addr.clone(),
+        &ExecuteMsg::CreateTask { task: task.clone() },
+        &res.min_recurring_deposit.native,
+    )
+    .unwrap();
+
+    // Same task can't be created twice
+    let res: SimulateTaskResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::SimulateTask {
+                task: Box::new(task.clone()),
+                owner_id: ANYONE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.error, Some("Task already exists".to_string()));
+    assert_eq!(res.task_hash, None);
+
+    // Rejected request returns the error of the creation
+    let action_self = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: contract_addr.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::Tick {}).unwrap(),
+    });
+    let res: SimulateTaskResponse = app
+        .wrap()
+        .query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::SimulateTask {
+                task: Box::new(TaskRequest {
+                    actions: vec![Action {
+                        msg: action_self,
+                        gas_limit: Some(150_000),
+                    }],
+                    ..task
+                }),
+                owner_id: ANYONE.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        SimulateTaskResponse {
+            error: Some(CoreError::InvalidAction {}.to_string()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn query_get_tasks_by_target() {
+    let (mut app, cw_template_contract, _) = proper_instantiate();
+    let contract_addr = cw_template_contract.addr();
+
+    let send = |to_address: &str| Action {
+        msg: BankMsg::Send {
+            to_address: to_address.to_string(),
+            amount: coins(1015, NATIVE_DENOM),
+        }
+        .into(),
+        gas_limit: None,
+    };
+    let new_task = |actions| TaskRequest {
+        interval: Interval::Immediate,
+        boundary: None,
+        stop_on_fail: false,
+        actions,
+        queries: None,
+        transforms: None,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let tasks_by_target = |app: &App,
+                           address: &str,
+                           start_after: Option<String>,
+                           limit: Option<u64>|
+     -> GetTasksResponse {
+        app.wrap()
+            .query_wasm_smart(
+                contract_addr.clone(),
+                &QueryMsg::GetTasksByTarget {
+                    address: address.to_string(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+    };
+
+    // Task calling "you" twice is listed once
+    for actions in [
+        vec![send("you")],
+        vec![send("you"), send("alice"), send("you")],
+    ] {
+        app.execute_contract(
+            Addr::unchecked(VERY_RICH),
+            contract_addr.clone(),
+            &ExecuteMsg::CreateTask {
+                task: new_task(actions),
+            },
+            &coins(315006, NATIVE_DENOM),
+        )
+        .unwrap();
+    }
+    let alice_tasks = tasks_by_target(&app, "alice", None, None);
+    assert_eq!(alice_tasks.tasks.len(), 1);
+    assert_eq!(alice_tasks.next_cursor, None);
+    let alice_task_hash = alice_tasks.tasks[0].task_hash.clone();
+
+    let you_tasks = tasks_by_target(&app, "you", None, None);
+    assert_eq!(you_tasks.tasks.len(), 2);
+    assert!(tasks_by_target(&app, "bob", None, None).tasks.is_empty());
+
+    // Paginated
+    let page = tasks_by_target(&app, "you", None, Some(1));
+    assert_eq!(page.tasks, you_tasks.tasks[..1]);
+    let page = tasks_by_target(&app, "you", page.next_cursor, Some(1));
+    assert_eq!(page.tasks, you_tasks.tasks[1..]);
+    assert_eq!(page.next_cursor, None);
+
+    // Removed task leaves the index
+    app.execute_contract(
+        Addr::unchecked(VERY_RICH),
+        contract_addr.clone(),
+        &ExecuteMsg::RemoveTask {
+            task_hash: alice_task_hash.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert!(tasks_by_target(&app, "alice", None, None).tasks.is_empty());
+    let you_tasks = tasks_by_target(&app, "you", None, None);
+    assert_eq!(you_tasks.tasks.len(), 1);
+    assert_ne!(you_tasks.tasks[0].task_hash, alice_task_hash);
+}
@@ -1,6 +1,7 @@
 use cw_rules_core::msg::{QueryConstruct, QueryConstructResponse};
 use cw_rules_core::types::{
     CheckOwnerOfNft, CheckPassedProposals, CheckProposalStatus, CroncatQuery, HasBalanceGte,
+    MAX_QUERY_DEPTH,
 };
 // use schemars::JsonSchema;
 // use serde::{Deserialize, Serialize};
@@ -24,7 +25,7 @@ use cw_rules_core::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, QueryResponse};
 //use cosmwasm_std::from_binary;
 //use crate::msg::QueryMultiResponse;
 use crate::types::dao::{ProposalListResponse, ProposalResponse, QueryDao, Status};
-use generic_query::GenericQuery;
+use generic_query::{GenericQuery, PathToValue, ValueOrdering};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw-rules";
@@ -94,6 +95,20 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         }
         QueryMsg::GenericQuery(query) => to_binary(&generic_query(deps, query)?),
         QueryMsg::SmartQuery(query) => to_binary(&smart_query(deps, query)?),
+        QueryMsg::RawQuery {
+            contract_addr,
+            key,
+            path_to_value,
+            ordering,
+            value,
+        } => to_binary(&raw_query(
+            deps,
+            contract_addr,
+            key,
+            path_to_value,
+            ordering,
+            value,
+        )?),
         QueryMsg::QueryConstruct(QueryConstruct { queries }) => {
             to_binary(&query_construct(deps, queries)?)
         }
@@ -270,44 +285,75 @@ fn query_dao_proposals(deps: Deps, dao_address: String) -> StdResult<QueryRespon
 
 // create a smart query into binary
 fn query_construct(deps: Deps, queries: Vec<CroncatQuery>) -> StdResult<QueryConstructResponse> {
+    if queries.iter().any(|query| query.depth() > MAX_QUERY_DEPTH) {
+        return Err(StdError::generic_err("Query nested too deep"));
+    }
     let mut data = Vec::with_capacity(queries.len());
     for (idx, query) in queries.into_iter().enumerate() {
-        let res = match query {
-            CroncatQuery::Query { contract_addr, msg } => Ok(QueryResponse {
-                result: true,
-                data: query_wasm_smart_raw(deps, contract_addr, msg)?,
-            }),
-            CroncatQuery::HasBalanceGte(HasBalanceGte {
-                address,
-                required_balance,
-            }) => query_has_balance_gte(deps, address, required_balance),
-            CroncatQuery::CheckOwnerOfNft(CheckOwnerOfNft {
-                address,
-                nft_address,
-                token_id,
-            }) => query_check_owner_nft(deps, address, nft_address, token_id),
-            CroncatQuery::CheckProposalStatus(CheckProposalStatus {
-                dao_address,
-                proposal_id,
-                status,
-            }) => query_dao_proposal_status(deps, dao_address, proposal_id, status),
-            CroncatQuery::CheckPassedProposals(CheckPassedProposals { dao_address }) => {
-                query_dao_proposals(deps, dao_address)
-            }
-            CroncatQuery::GenericQuery(query) => generic_query(deps, query),
-            CroncatQuery::SmartQuery(query) => smart_query(deps, query),
-        }?;
-        if !res.result {
+        if !evaluate_query(deps, query, &mut data)? {
             return Ok(QueryConstructResponse {
-                result: res.result,
+                result: false,
                 data: vec![to_binary(&(idx as u64))?],
             });
         }
-        data.push(res.data);
     }
     Ok(QueryConstructResponse { result: true, data })
 }
 
+/// Evaluates the query and pushes the data of its leaf queries
+/// `And` and `Or` evaluate all of their queries, so every leaf has its data for the transforms
+fn evaluate_query(deps: Deps, query: CroncatQuery, data: &mut Vec<Binary>) -> StdResult<bool> {
+    let res = match query {
+        CroncatQuery::And(queries) => {
+            let mut result = true;
+            for query in queries {
+                result &= evaluate_query(deps, query, data)?;
+            }
+            return Ok(result);
+        }
+        CroncatQuery::Or(queries) => {
+            let mut result = false;
+            for query in queries {
+                result |= evaluate_query(deps, query, data)?;
+            }
+            return Ok(result);
+        }
+        CroncatQuery::Not(query) => return evaluate_query(deps, *query, data).map(|res| !res),
+        CroncatQuery::Query { contract_addr, msg } => Ok(QueryResponse {
+            result: true,
+            data: query_wasm_smart_raw(deps, contract_addr, msg)?,
+        }),
+        CroncatQuery::HasBalanceGte(HasBalanceGte {
+            address,
+            required_balance,
+        }) => query_has_balance_gte(deps, address, required_balance),
+        CroncatQuery::CheckOwnerOfNft(CheckOwnerOfNft {
+            address,
+            nft_address,
+            token_id,
+        }) => query_check_owner_nft(deps, address, nft_address, token_id),
+        CroncatQuery::CheckProposalStatus(CheckProposalStatus {
+            dao_address,
+            proposal_id,
+            status,
+        }) => query_dao_proposal_status(deps, dao_address, proposal_id, status),
+        CroncatQuery::CheckPassedProposals(CheckPassedProposals { dao_address }) => {
+            query_dao_proposals(deps, dao_address)
+        }
+        CroncatQuery::GenericQuery(query) => generic_query(deps, query),
+        CroncatQuery::SmartQuery(query) => smart_query(deps, query),
+        CroncatQuery::RawQuery {
+            contract_addr,
+            key,
+            path_to_value,
+            ordering,
+            value,
+        } => raw_query(deps, contract_addr, key, path_to_value, ordering, value),
+    }?;
+    data.push(res.data);
+    Ok(res.result)
+}
+
 fn smart_query(deps: Deps, query: SmartQueryHead) -> StdResult<QueryResponse> {
     let mut json_val = query_wasm_smart_raw(deps, query.contract_addr, query.msg)
         .and_then(|bin| bin_to_value(bin.as_slice()))?;
@@ -351,3 +397,27 @@ fn generic_query(deps: Deps, query: GenericQuery) -> StdResult<QueryResponse> {
         data: to_binary(&value)?,
     })
 }
+
+fn raw_query(
+    deps: Deps,
+    contract_addr: String,
+    key: Binary,
+    path_to_value: PathToValue,
+    ordering: ValueOrdering,
+    value: Binary,
+) -> StdResult<QueryResponse> {
+    let bin = deps
+        .querier
+        .query_wasm_raw(&contract_addr, key)?
+        .ok_or_else(|| StdError::not_found(format!("Raw value of {}", contract_addr)))?;
+    let mut json_val = bin_to_value(&bin)?;
+    let json_rhs = cosmwasm_std::from_slice(value.as_slice())
+        .map_err(|e| StdError::parse_err(std::any::type_name::<serde_cw_value::Value>(), e))?;
+    let value = path_to_value.find_value(&mut json_val)?;
+
+    let result = ordering.val_cmp(value, &json_rhs)?;
+    Ok(QueryResponse {
+        result,
+        data: to_binary(&value)?,
+    })
+}
@@ -0,0 +1,80 @@
+use cosmwasm_std::{coins, to_binary, StdResult};
+use cw20::Balance;
+use cw_rules_core::msg::{QueryConstruct, QueryConstructResponse, QueryMsg};
+use cw_rules_core::types::{CroncatQuery, HasBalanceGte, MAX_QUERY_DEPTH};
+use cw_utils::NativeBalance;
+
+use crate::tests::helpers::{proper_instantiate, ANYONE, NATIVE_DENOM};
+
+fn has_balance_gte(amount: u128) -> CroncatQuery {
+    CroncatQuery::HasBalanceGte(HasBalanceGte {
+        address: ANYONE.to_string(),
+        required_balance: Balance::Native(NativeBalance(coins(amount, NATIVE_DENOM))),
+    })
+}
+
+#[test]
+fn test_query_construct_combinators() -> StdResult<()> {
+    let (app, contract_addr, _) = proper_instantiate();
+    let balance = to_binary(&Balance::from(coins(1_000_000u128, NATIVE_DENOM)))?;
+
+    // Or is true if any of the queries is, all of them get evaluated
+    let msg = QueryMsg::QueryConstruct(QueryConstruct {
+        queries: vec![CroncatQuery::Or(vec![
+            has_balance_gte(2_000_000),
+            has_balance_gte(500_000),
+        ])],
+    });
+    let res: QueryConstructResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &msg)
+        .unwrap();
+    assert!(res.result);
+    assert_eq!(res.data, vec![balance.clone(), balance.clone()]);
+
+    // Leaf data is in depth-first order
+    let msg = QueryMsg::QueryConstruct(QueryConstruct {
+        queries: vec![
+            has_balance_gte(1),
+            CroncatQuery::And(vec![
+                CroncatQuery::Not(Box::new(has_balance_gte(2_000_000))),
+                CroncatQuery::Or(vec![has_balance_gte(3_000_000), has_balance_gte(1)]),
+            ]),
+        ],
+    });
+    let res: QueryConstructResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &msg)
+        .unwrap();
+    assert!(res.result);
+    assert_eq!(res.data, vec![balance; 4]);
+
+    // Index of the first false top level query
+    let msg = QueryMsg::QueryConstruct(QueryConstruct {
+        queries: vec![
+            has_balance_gte(1),
+            CroncatQuery::Not(Box::new(has_balance_gte(1))),
+            has_balance_gte(1),
+        ],
+    });
+    let res: QueryConstructResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &msg)
+        .unwrap();
+    assert!(!res.result);
+    assert_eq!(res.data, vec![to_binary(&1u64)?]);
+
+    // Nested too deep
+    let mut query = has_balance_gte(1);
+    for _ in 0..MAX_QUERY_DEPTH {
+        query = CroncatQuery::Not(Box::new(query));
+    }
+    assert_eq!(query.depth(), MAX_QUERY_DEPTH + 1);
+    let msg = QueryMsg::QueryConstruct(QueryConstruct {
+        queries: vec![query],
+    });
+    let res: StdResult<QueryConstructResponse> = app.wrap().query_wasm_smart(contract_addr, &msg);
+    assert!(res.is_err());
+
+    Ok(())
+}
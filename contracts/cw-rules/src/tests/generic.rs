@@ -1,13 +1,18 @@
-use cosmwasm_std::{to_binary, Addr, Binary, WasmQuery};
+use cosmwasm_std::{to_binary, Addr, Binary, StdResult, Uint128, WasmQuery};
 use cw20::Cw20Coin;
 use cw4::Member;
 use cw_multi_test::{App, Executor};
 use serde_json::json;
 
-use cw_rules_core::msg::{InstantiateMsg, QueryMsg, QueryResponse};
+use cw_rules_core::msg::{
+    InstantiateMsg, QueryConstruct, QueryConstructResponse, QueryMsg, QueryResponse,
+};
+use cw_rules_core::types::CroncatQuery;
 use generic_query::{GenericQuery, ValueIndex, ValueOrdering};
 
-use crate::tests::helpers::{cw20_template, cw4_contract, cw_rules_contract, CREATOR_ADDR};
+use crate::tests::helpers::{
+    cw20_template, cw4_contract, cw_rules_contract, proper_instantiate, CREATOR_ADDR,
+};
 
 #[test]
 fn test_generic() {
@@ -201,3 +206,53 @@ fn test_generic_bigint() {
     let res: QueryResponse = app.wrap().query_wasm_smart(contract_addr, &msg).unwrap();
     assert!(res.result);
 }
+
+#[test]
+fn test_raw_query() -> StdResult<()> {
+    let (app, contract_addr, cw20_addr) = proper_instantiate();
+
+    // `TOKEN_INFO` item of cw20-base
+    let msg = QueryMsg::RawQuery {
+        contract_addr: cw20_addr.to_string(),
+        key: Binary::from(b"token_info"),
+        path_to_value: vec![ValueIndex::Key("total_supply".to_string())].into(),
+        ordering: ValueOrdering::UnitAboveEqual,
+        value: to_binary(&Uint128::new(15))?,
+    };
+    let res: QueryResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &msg)
+        .unwrap();
+    assert!(res.result);
+    assert_eq!(res.data, to_binary("15")?);
+
+    let query = CroncatQuery::RawQuery {
+        contract_addr: cw20_addr.to_string(),
+        key: Binary::from(b"token_info"),
+        path_to_value: vec![ValueIndex::Key("symbol".to_string())].into(),
+        ordering: ValueOrdering::Equal,
+        value: to_binary("Other")?,
+    };
+    let msg = QueryMsg::QueryConstruct(QueryConstruct {
+        queries: vec![CroncatQuery::Not(Box::new(query))],
+    });
+    let res: QueryConstructResponse = app
+        .wrap()
+        .query_wasm_smart(contract_addr.clone(), &msg)
+        .unwrap();
+    assert!(res.result);
+    assert_eq!(res.data, vec![to_binary("Test")?]);
+
+    // Nothing stored under the key
+    let msg = QueryMsg::RawQuery {
+        contract_addr: cw20_addr.to_string(),
+        key: Binary::from(b"unknown"),
+        path_to_value: vec![].into(),
+        ordering: ValueOrdering::Equal,
+        value: to_binary("Test")?,
+    };
+    let res: StdResult<QueryResponse> = app.wrap().query_wasm_smart(contract_addr, &msg);
+    assert!(res.is_err());
+
+    Ok(())
+}
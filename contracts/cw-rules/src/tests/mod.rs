@@ -1,4 +1,5 @@
 mod balance;
+mod construct;
 #[cfg(test)]
 mod daodao;
 mod generic;
@@ -3,9 +3,12 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 use cw_croncat_core::msg::{
-    AgentResponse, Croncat, ExecuteMsg, InstantiateMsg, QueryMsg, TaskResponse,
-    TaskWithQueriesResponse,
+    AgentResponse, Croncat, CroncatTaskCallback, ExecuteMsg, GasPriceOracleQueryMsg,
+    GasPriceOracleResponse, GetTaskDependenciesResponse, GetTasksResponse,
+    GetTasksWithQueriesResponse, InstantiateMsg, MigrateMsg, PreviewScheduleResponse, QueryMsg,
+    SimulateTaskResponse, TaskResponse, TaskWithQueriesResponse,
 };
+use cw_croncat_core::types::ExecutionRecord;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -18,34 +21,38 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(Croncat), &out_dir);
+    export_schema(&schema_for!(CroncatTaskCallback), &out_dir);
+    export_schema(&schema_for!(GasPriceOracleQueryMsg), &out_dir);
+    export_schema(&schema_for!(GasPriceOracleResponse), &out_dir);
+    export_schema(&schema_for!(SimulateTaskResponse), &out_dir);
+    export_schema(&schema_for!(PreviewScheduleResponse), &out_dir);
     export_schema_with_title(&schema_for!(bool), &out_dir, "ValidateIntervalResponse");
     export_schema_with_title(
         &schema_for!(Option<AgentResponse>),
         &out_dir,
         "GetAgentResponse",
     );
+    export_schema(&schema_for!(GetTasksResponse), &out_dir);
     export_schema_with_title(
-        &schema_for!(Vec<TaskResponse>),
-        &out_dir,
-        "GetTasksResponse",
-    );
-    export_schema_with_title(
-        &schema_for!(Vec<TaskResponse>),
+        &schema_for!(GetTasksResponse),
         &out_dir,
         "GetTasksByOwnerResponse",
     );
-    export_schema_with_title(
-        &schema_for!(Vec<TaskWithQueriesResponse>),
-        &out_dir,
-        "GetTasksWithQueriesResponse",
-    );
+    export_schema(&schema_for!(GetTasksWithQueriesResponse), &out_dir);
     export_schema_with_title(
         &schema_for!(Option<TaskResponse>),
         &out_dir,
         "GetTaskResponse",
     );
     export_schema_with_title(&schema_for!(String), &out_dir, "GetTaskHashResponse");
+    export_schema(&schema_for!(GetTaskDependenciesResponse), &out_dir);
+    export_schema_with_title(
+        &schema_for!(Vec<ExecutionRecord>),
+        &out_dir,
+        "GetTaskHistoryResponse",
+    );
     export_schema_with_title(
         &schema_for!(Option<TaskResponse>),
         &out_dir,
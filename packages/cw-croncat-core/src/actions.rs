@@ -0,0 +1,101 @@
+use cosmwasm_std::{from_binary, Addr, Api, BankMsg, Coin, CosmosMsg, IbcMsg, StakingMsg, WasmMsg};
+use cw20::{Cw20CoinVerified, Cw20ExecuteMsg};
+use cw721::Cw721ExecuteMsg;
+
+use crate::{
+    error::CoreError,
+    types::{Action, GenericBalance},
+};
+
+/// Funds the action spends once executed, besides the gas
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionUsage {
+    /// Paid from the task deposit
+    pub deposit: GenericBalance,
+    /// Taken with the allowances other accounts gave to the croncat contract
+    pub allowances: Vec<AllowanceUsage>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllowanceUsage {
+    /// cw20 tokens pulled from the `owner` with `TransferFrom` or `SendFrom`
+    Cw20 { owner: Addr, coin: Cw20CoinVerified },
+    /// NFT moved with `TransferNft` or `SendNft`, its owner has to approve croncat
+    Cw721 {
+        contract_addr: Addr,
+        token_id: String,
+    },
+}
+
+impl<T> Action<T> {
+    /// Classify the action by the funds it spends
+    /// Errors on the messages croncat can't account for
+    pub fn usage(&self, api: &dyn Api) -> Result<ActionUsage, CoreError> {
+        let mut usage = ActionUsage::default();
+        match &self.msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+                usage.deposit.native = non_zero_coins(amount)?;
+            }
+            CosmosMsg::Staking(StakingMsg::Delegate { amount, .. })
+            | CosmosMsg::Ibc(IbcMsg::Transfer { amount, .. }) => {
+                usage.deposit.native = non_zero_coins(std::slice::from_ref(amount))?;
+            }
+            CosmosMsg::Wasm(WasmMsg::Instantiate { funds, .. }) => {
+                usage.deposit.native = non_zero_coins(funds)?;
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                usage.deposit.native = non_zero_coins(funds)?;
+                if let Ok(cw20_msg) = from_binary(msg) {
+                    let address = api.addr_validate(contract_addr)?;
+                    match cw20_msg {
+                        // Allowance is reserved as if the spender takes all of it
+                        Cw20ExecuteMsg::Send { amount, .. }
+                        | Cw20ExecuteMsg::Transfer { amount, .. }
+                        | Cw20ExecuteMsg::IncreaseAllowance { amount, .. }
+                            if !amount.is_zero() =>
+                        {
+                            usage
+                                .deposit
+                                .cw20
+                                .push(Cw20CoinVerified { address, amount });
+                        }
+                        Cw20ExecuteMsg::TransferFrom { owner, amount, .. }
+                        | Cw20ExecuteMsg::SendFrom { owner, amount, .. }
+                            if !amount.is_zero() =>
+                        {
+                            usage.allowances.push(AllowanceUsage::Cw20 {
+                                owner: api.addr_validate(&owner)?,
+                                coin: Cw20CoinVerified { address, amount },
+                            });
+                        }
+                        _ => return Err(CoreError::InvalidAction {}),
+                    }
+                } else if let Ok(
+                    Cw721ExecuteMsg::TransferNft { token_id, .. }
+                    | Cw721ExecuteMsg::SendNft { token_id, .. },
+                ) = from_binary(msg)
+                {
+                    // Only transfers are classified, other cw721 messages like `approve`
+                    // or `burn` share their shape with messages of non-cw721 contracts
+                    usage.allowances.push(AllowanceUsage::Cw721 {
+                        contract_addr: api.addr_validate(contract_addr)?,
+                        token_id,
+                    });
+                }
+            }
+            _ => (),
+        }
+        Ok(usage)
+    }
+}
+
+fn non_zero_coins(coins: &[Coin]) -> Result<Vec<Coin>, CoreError> {
+    if coins.iter().any(|coin| coin.amount.is_zero()) {
+        return Err(CoreError::InvalidAction {});
+    }
+    Ok(coins.to_vec())
+}
@@ -0,0 +1,407 @@
+//! Events of the manager contract lifecycle transitions
+//!
+//! Every event is a struct with its attribute keys as fields, the chain prefixes its type with `wasm-`,
+//! so `TaskCreated` gets indexed as `wasm-croncat-task-created`.
+//! Attributes of unset options and empty lists are left out,
+//! lists are comma separated and balances are encoded as `<amount><denom or cw20 address>`
+use cosmwasm_std::{Addr, Attribute, Coin, Event, StdError, StdResult, Uint128};
+use cw20::Cw20CoinVerified;
+use std::str::FromStr;
+
+use crate::types::{AgentStatus, SlotType};
+
+/// Typed event, converted from and into the attributes of a contract event
+pub trait CroncatEvent: Sized {
+    /// Type of the event, without the `wasm-` prefix
+    const TYPE: &'static str;
+
+    fn attributes(&self) -> Vec<Attribute>;
+
+    fn from_attributes(attributes: &[Attribute]) -> StdResult<Self>;
+
+    fn into_event(self) -> Event {
+        Event::new(Self::TYPE).add_attributes(self.attributes())
+    }
+
+    /// Parses the event emitted by the contract, with or without the `wasm-` prefix
+    fn from_event(event: &Event) -> StdResult<Self> {
+        if event.ty != Self::TYPE && event.ty.strip_prefix("wasm-") != Some(Self::TYPE) {
+            return Err(StdError::parse_err(
+                Self::TYPE,
+                format!("Unexpected event type {}", event.ty),
+            ));
+        }
+        Self::from_attributes(&event.attributes)
+    }
+}
+
+/// Value of a single event attribute
+pub trait EventValue: Sized {
+    /// Attribute value, the attribute is left out if there is none
+    fn to_value(&self) -> Option<String>;
+
+    fn from_value(value: &str) -> StdResult<Self>;
+
+    /// Value of the attribute left out of the event
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+impl EventValue for String {
+    fn to_value(&self) -> Option<String> {
+        Some(self.clone())
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        Ok(value.to_string())
+    }
+}
+
+impl EventValue for Addr {
+    fn to_value(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        Ok(Addr::unchecked(value))
+    }
+}
+
+impl EventValue for u64 {
+    fn to_value(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        value
+            .parse()
+            .map_err(|_| StdError::parse_err("u64", format!("Invalid number {value}")))
+    }
+}
+
+impl EventValue for bool {
+    fn to_value(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        value
+            .parse()
+            .map_err(|_| StdError::parse_err("bool", format!("Invalid bool {value}")))
+    }
+}
+
+impl EventValue for SlotType {
+    fn to_value(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        match value {
+            "Block" => Ok(SlotType::Block),
+            "Cron" => Ok(SlotType::Cron),
+            _ => Err(StdError::parse_err(
+                "SlotType",
+                format!("Unknown slot type {value}"),
+            )),
+        }
+    }
+}
+
+impl EventValue for AgentStatus {
+    fn to_value(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        match value {
+            "Active" => Ok(AgentStatus::Active),
+            "Pending" => Ok(AgentStatus::Pending),
+            "Nominated" => Ok(AgentStatus::Nominated),
+            _ => Err(StdError::parse_err(
+                "AgentStatus",
+                format!("Unknown agent status {value}"),
+            )),
+        }
+    }
+}
+
+impl<T: EventValue> EventValue for Option<T> {
+    fn to_value(&self) -> Option<String> {
+        self.as_ref().and_then(EventValue::to_value)
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        T::from_value(value).map(Some)
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl EventValue for Vec<String> {
+    fn to_value(&self) -> Option<String> {
+        (!self.is_empty()).then(|| self.join(","))
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        Ok(value.split(',').map(ToString::to_string).collect())
+    }
+
+    fn missing() -> Option<Self> {
+        Some(vec![])
+    }
+}
+
+impl EventValue for Vec<Coin> {
+    fn to_value(&self) -> Option<String> {
+        (!self.is_empty()).then(|| {
+            self.iter()
+                .map(|coin| format!("{}{}", coin.amount, coin.denom))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        value
+            .split(',')
+            .map(|coin| {
+                let (amount, denom) = split_amount(coin)?;
+                Ok(Coin::new(amount.u128(), denom))
+            })
+            .collect()
+    }
+
+    fn missing() -> Option<Self> {
+        Some(vec![])
+    }
+}
+
+impl EventValue for Vec<Cw20CoinVerified> {
+    fn to_value(&self) -> Option<String> {
+        (!self.is_empty()).then(|| {
+            self.iter()
+                .map(|coin| format!("{}{}", coin.amount, coin.address))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        value
+            .split(',')
+            .map(|coin| {
+                let (amount, address) = split_amount(coin)?;
+                Ok(Cw20CoinVerified {
+                    address: Addr::unchecked(address),
+                    amount,
+                })
+            })
+            .collect()
+    }
+
+    fn missing() -> Option<Self> {
+        Some(vec![])
+    }
+}
+
+/// Splits `<amount><denom>` into the amount and the denom
+fn split_amount(value: &str) -> StdResult<(Uint128, &str)> {
+    let idx = value
+        .find(|c: char| !c.is_ascii_digit())
+        .filter(|idx| *idx > 0)
+        .ok_or_else(|| StdError::parse_err("Coin", format!("Invalid coin {value}")))?;
+    let (amount, denom) = value.split_at(idx);
+    Ok((Uint128::from_str(amount)?, denom))
+}
+
+/// Why the task got removed without the owner asking for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskEndReason {
+    /// `Once` and `At` tasks after their execution
+    Completed,
+    /// Reached `max_executions`
+    ExecutionLimit,
+    /// Failed with `stop_on_fail` set
+    Failure,
+    /// Boundary or schedule has no slots left
+    ScheduleEnded,
+    /// Deposit doesn't cover the next execution
+    OutOfFunds,
+    /// Query results couldn't be inserted into the actions
+    InvalidActions,
+    /// Task owner no longer owns an NFT the actions move
+    NftOwnerChanged,
+    /// Fee token of the task got removed from the registry
+    FeeTokenRemoved,
+    /// Task it waits for ended without triggering it
+    ParentEnded,
+}
+
+impl EventValue for TaskEndReason {
+    fn to_value(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
+
+    fn from_value(value: &str) -> StdResult<Self> {
+        match value {
+            "Completed" => Ok(TaskEndReason::Completed),
+            "ExecutionLimit" => Ok(TaskEndReason::ExecutionLimit),
+            "Failure" => Ok(TaskEndReason::Failure),
+            "ScheduleEnded" => Ok(TaskEndReason::ScheduleEnded),
+            "OutOfFunds" => Ok(TaskEndReason::OutOfFunds),
+            "InvalidActions" => Ok(TaskEndReason::InvalidActions),
+            "NftOwnerChanged" => Ok(TaskEndReason::NftOwnerChanged),
+            "FeeTokenRemoved" => Ok(TaskEndReason::FeeTokenRemoved),
+            "ParentEnded" => Ok(TaskEndReason::ParentEnded),
+            _ => Err(StdError::parse_err(
+                "TaskEndReason",
+                format!("Unknown reason {value}"),
+            )),
+        }
+    }
+}
+
+/// Defines the event structs, their fields are the attribute keys
+macro_rules! croncat_events {
+    ($(
+        $(#[$meta:meta])*
+        $name:ident = $ty:literal {
+            $($(#[$field_meta:meta])* $field:ident: $field_ty:ty,)*
+        }
+    )*) => {$(
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: $field_ty,)*
+        }
+
+        impl CroncatEvent for $name {
+            const TYPE: &'static str = $ty;
+
+            fn attributes(&self) -> Vec<Attribute> {
+                let mut attributes = vec![];
+                $(
+                    if let Some(value) = self.$field.to_value() {
+                        attributes.push(Attribute::new(stringify!($field), value));
+                    }
+                )*
+                attributes
+            }
+
+            fn from_attributes(attributes: &[Attribute]) -> StdResult<Self> {
+                Ok(Self {
+                    $($field: attribute_value(attributes, stringify!($field))?,)*
+                })
+            }
+        }
+
+        impl From<$name> for Event {
+            fn from(event: $name) -> Self {
+                event.into_event()
+            }
+        }
+    )*};
+}
+
+fn attribute_value<T: EventValue>(attributes: &[Attribute], key: &str) -> StdResult<T> {
+    match attributes.iter().find(|attr| attr.key == key) {
+        Some(attr) => T::from_value(&attr.value),
+        None => T::missing().ok_or_else(|| StdError::not_found(format!("attribute {key}"))),
+    }
+}
+
+croncat_events! {
+    /// Task got created, scheduled unless it waits for the parent task
+    TaskCreated = "croncat-task-created" {
+        task_hash: String,
+        owner: Addr,
+        with_queries: bool,
+        slot_id: Option<u64>,
+        slot_kind: Option<SlotType>,
+        trigger_task_hash: Option<String>,
+    }
+
+    /// Deposit of the task got topped up by the owner or from the owner wallet balances
+    TaskRefilled = "croncat-task-refilled" {
+        task_hash: String,
+        owner: Addr,
+        native: Vec<Coin>,
+        cw20: Vec<Cw20CoinVerified>,
+        auto_refill: bool,
+    }
+
+    /// Actions of the task got executed by the agent
+    TaskExecuted = "croncat-task-executed" {
+        task_hash: String,
+        owner: Addr,
+        agent: Addr,
+        /// Number of the execution, starting from 1
+        execution: u64,
+        executed_actions: u64,
+        failed_actions: u64,
+        /// Error of the first failed action
+        failure: Option<String>,
+        fee_native: Vec<Coin>,
+        fee_cw20: Vec<Cw20CoinVerified>,
+    }
+
+    /// Task got put into its next slot
+    TaskRescheduled = "croncat-task-rescheduled" {
+        task_hash: String,
+        owner: Addr,
+        slot_id: u64,
+        slot_kind: SlotType,
+    }
+
+    /// Task won't execute anymore, followed by `TaskRemoved`
+    TaskEnded = "croncat-task-ended" {
+        task_hash: String,
+        owner: Addr,
+        reason: TaskEndReason,
+        /// Error of the first failed action, or why the actions couldn't be built
+        error: Option<String>,
+    }
+
+    /// Task got deleted, with the rest of the deposit returned to the owner
+    TaskRemoved = "croncat-task-removed" {
+        task_hash: String,
+        owner: Addr,
+        refund_native: Vec<Coin>,
+        refund_cw20: Vec<Cw20CoinVerified>,
+    }
+
+    AgentRegistered = "croncat-agent-registered" {
+        agent: Addr,
+        payable_account_id: Addr,
+        status: AgentStatus,
+    }
+
+    /// Agent first in the pending queue can accept the nomination from now on
+    AgentNominated = "croncat-agent-nominated" {
+        agent: Addr,
+    }
+
+    /// Agent joined the active queue
+    AgentActivated = "croncat-agent-activated" {
+        agent: Addr,
+    }
+
+    /// Agent got removed from the active queue for missing slots,
+    /// or from the pending queue when an agent behind it got activated
+    AgentEjected = "croncat-agent-ejected" {
+        agent: Addr,
+        status: AgentStatus,
+    }
+
+    /// Owner updated the config
+    SettingsChanged = "croncat-settings-changed" {
+        owner_id: Addr,
+        paused: bool,
+        /// Names of the config fields that got a new value
+        changed: Vec<String>,
+    }
+}
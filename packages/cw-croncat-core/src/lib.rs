@@ -1,7 +1,10 @@
+pub mod actions;
 pub mod error;
+pub mod events;
 pub mod msg;
 pub mod query;
 #[cfg(test)]
 pub mod tests;
+pub mod timezone;
 pub mod traits;
 pub mod types;
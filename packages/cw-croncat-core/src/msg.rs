@@ -1,11 +1,14 @@
 use crate::error::CoreError;
 use crate::traits::Intervals;
 use crate::types::{
-    Action, AgentStatus, Boundary, CheckedBoundary, GasPrice, GenericBalance, Interval, Task,
-    Transform,
+    Action, ActionFailure, AdminAllowance, AgentStatus, AutoRefill, Boundary, CheckedBoundary,
+    FeeToken, FeeTokenRate, GasPrice, GasPriceSource, GenericBalance, Interval, Task, Transform,
+    Trigger,
 };
 use crate::types::{Agent, SlotType};
-use cosmwasm_std::{Addr, Coin, Timestamp, Uint64};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, CosmosMsg, Empty, StdResult, Timestamp, Uint64, WasmMsg,
+};
 use cw20::{Balance, Cw20Coin, Cw20CoinVerified};
 use cw_rules_core::types::CroncatQuery;
 use schemars::JsonSchema;
@@ -47,8 +50,8 @@ pub struct Croncat {
     pub(crate) task_response: Option<TaskResponse>,
     pub(crate) validate_interval_response: Option<bool>,
     pub(crate) get_agent_response: Option<Option<AgentResponse>>,
-    pub(crate) get_tasks_response: Option<Vec<TaskResponse>>,
-    pub(crate) get_tasks_by_owner_response: Option<Vec<TaskResponse>>,
+    pub(crate) get_tasks_response: Option<GetTasksResponse>,
+    pub(crate) get_tasks_by_owner_response: Option<GetTasksResponse>,
     pub(crate) get_task_response: Option<Option<TaskResponse>>,
     pub(crate) get_task_hash_response: Option<String>,
     pub(crate) get_slot_hashes_response: Option<GetSlotHashesResponse>,
@@ -69,11 +72,24 @@ pub struct InstantiateMsg {
     pub gas_wasm_query_fee: Option<Uint64>,
     pub gas_price: Option<GasPrice>,
     pub agent_nomination_duration: Option<u16>,
+    /// Seconds an IBC transfer action stays valid after the execution block
+    pub ibc_transfer_timeout: Option<u64>,
+    /// Whether tasks can have governance actions, disabled by default
+    pub gov_msgs_enabled: Option<bool>,
 }
 
+/// Migrating from a contract with an older `TASK_HASH_VERSION` pauses the contract
+/// until `ExecuteMsg::MigrateTasks` re-keys every stored task
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrateMsg {}
+
+pub type ExecuteMsg = GenericExecuteMsg<Empty>;
+
+// Execute message with `T` as the `CosmosMsg::Custom` type of the task actions
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum ExecuteMsg {
+#[schemars(rename = "ExecuteMsg")]
+pub enum GenericExecuteMsg<T> {
     UpdateSettings {
         owner_id: Option<String>,
         chain_name: Option<String>,
@@ -86,6 +102,15 @@ pub enum ExecuteMsg {
         gas_wasm_query_fee: Option<Uint64>,
         gas_price: Option<GasPrice>,
         proxy_callback_gas: Option<u32>,
+        ibc_transfer_timeout: Option<u64>,
+        gov_msgs_enabled: Option<bool>,
+        /// Type urls of the stargate messages tasks can have. Croncat signs them,
+        /// so the allowed messages must not be able to move the contract funds,
+        /// which hold the deposits of every task. Authz messages are rejected
+        stargate_type_urls: Option<Vec<String>>,
+        admin_allowlist: Option<Vec<AdminAllowance>>,
+        fee_tokens: Option<Vec<FeeTokenRate>>,
+        gas_price_source: Option<GasPriceSource>,
         min_tasks_per_agent: Option<u64>,
         agents_eject_threshold: Option<u64>,
         // treasury_id: Option<String>,
@@ -108,7 +133,26 @@ pub enum ExecuteMsg {
     WithdrawReward {},
 
     CreateTask {
-        task: TaskRequest,
+        task: GenericTaskRequest<T>,
+    },
+    /// Update scheduling and gas limits of the task, the task keeps its hash
+    /// Updated task is validated like a new one. A new task with the original
+    /// parameters can't be created until the updated task is removed
+    UpdateTask {
+        task_hash: String,
+        interval: Option<Interval>,
+        boundary: Option<Boundary>,
+        stop_on_fail: Option<bool>,
+        /// Gas limit for each of the task actions, in the same order
+        gas_limits: Option<Vec<Option<u64>>>,
+    },
+    /// Take the task out of the schedule, keeping its deposit and hash
+    PauseTask {
+        task_hash: String,
+    },
+    /// Schedule the paused task at the next valid slot
+    ResumeTask {
+        task_hash: String,
     },
     RemoveTask {
         task_hash: String,
@@ -125,44 +169,100 @@ pub enum ExecuteMsg {
     },
     /// Receive cw20 token
     Receive(cw20::Cw20ReceiveMsg),
+    /// Add the attached native coins to the sender wallet balance, used by task auto refills
+    DepositWalletBalance {},
     WithdrawWalletBalance {
         cw20_amounts: Vec<Cw20Coin>,
+        #[serde(default)]
+        native_amounts: Vec<Coin>,
     },
     Tick {},
+    /// Re-key the next `limit` tasks, slots or renames of the task migration, anyone can call it
+    MigrateTasks {
+        limit: Option<u64>,
+    },
+    /// Remove the last execution kept for the ended tasks, restricted to the owner
+    PruneTaskHistory {
+        task_hashes: Vec<String>,
+    },
 }
 
+pub type QueryMsg = GenericQueryMsg<Empty>;
+
+// Query message with `T` as the `CosmosMsg::Custom` type of the task actions
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum QueryMsg {
+#[schemars(rename = "QueryMsg")]
+pub enum GenericQueryMsg<T> {
     GetConfig {},
     GetBalances {},
     GetAgent {
         account_id: String,
     },
-    GetAgentIds {},
+    /// Active agents first, then the pending ones in the queue order
+    /// `start_after` is the position of the last agent of the previous page
+    GetAgentIds {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
     GetAgentTasks {
         account_id: String,
     },
     GetTasks {
-        from_index: Option<u64>,
+        start_after: Option<String>,
         limit: Option<u64>,
     },
     GetTasksWithQueries {
-        from_index: Option<u64>,
+        start_after: Option<String>,
         limit: Option<u64>,
     },
+    /// Tasks of the owner, with and without queries
     GetTasksByOwner {
         owner_id: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
+    /// Tasks with actions calling or sending funds to the address
+    GetTasksByTarget {
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
     },
     GetTask {
         task_hash: String,
     },
+    /// Triggers between the tasks of the owner
+    /// Pages go through the owner tasks by hash, like `GetTasksByOwner`
+    GetTaskDependencies {
+        owner_id: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
+    /// Last executions of the task, newest first
+    /// Only the last one is kept once the task ends, until the owner prunes it
+    /// with `PruneTaskHistory` or a task with the same hash is created
+    GetTaskHistory {
+        task_hash: String,
+    },
+    /// Hash the task will have once created by `owner_id`
     GetTaskHash {
-        task: Box<Task>,
+        task: Box<GenericTaskRequest<T>>,
+        owner_id: String,
+    },
+    /// Costs of the task if `owner_id` created it, or the reason it would be rejected
+    SimulateTask {
+        task: Box<GenericTaskRequest<T>>,
+        owner_id: String,
     },
     ValidateInterval {
         interval: Interval,
     },
+    /// Next `count` slots the schedule would execute in, under the current slot granularity
+    PreviewSchedule {
+        interval: Interval,
+        boundary: Option<Boundary>,
+        count: u64,
+    },
     GetSlotHashes {
         slot: Option<u64>,
     },
@@ -209,15 +309,31 @@ impl AgentTaskResponse {
     }
 }
 
+pub type TaskRequest = GenericTaskRequest<Empty>;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TaskRequest {
+#[schemars(rename = "TaskRequest")]
+pub struct GenericTaskRequest<T> {
     pub interval: Interval,
     pub boundary: Option<Boundary>,
     pub stop_on_fail: bool,
-    pub actions: Vec<Action>,
+    pub actions: Vec<Action<T>>,
     pub queries: Option<Vec<CroncatQuery>>,
     pub transforms: Option<Vec<Transform>>,
     pub cw20_coins: Vec<Cw20Coin>,
+    /// See `Task::max_executions`
+    pub max_executions: Option<u64>,
+    /// Run the task on completion of another task, instead of scheduling it by the interval
+    /// Only `Once` and `Immediate` intervals can be triggered
+    pub trigger: Option<Trigger>,
+    /// Validated into `Task::callback_addr`
+    pub callback_addr: Option<String>,
+    /// Token the agent fee is paid in, has to be one of the config `fee_tokens`
+    pub fee_token: Option<FeeToken>,
+    /// Highest gas price numerator the task pays, the agent fee is capped by it
+    pub max_gas_price: Option<u64>,
+    /// Top up the deposit from the owner wallet balances when it runs low
+    pub auto_refill: Option<AutoRefill>,
 }
 pub struct TaskRequestBuilder {
     interval: Interval,
@@ -227,6 +343,12 @@ pub struct TaskRequestBuilder {
     queries: Option<Option<Vec<CroncatQuery>>>,
     transforms: Option<Option<Vec<Transform>>>,
     cw20_coins: Option<Vec<Cw20Coin>>,
+    max_executions: Option<u64>,
+    trigger: Option<Trigger>,
+    callback_addr: Option<String>,
+    fee_token: Option<FeeToken>,
+    max_gas_price: Option<u64>,
+    auto_refill: Option<AutoRefill>,
 }
 #[allow(dead_code)]
 impl TaskRequestBuilder {
@@ -239,6 +361,12 @@ impl TaskRequestBuilder {
             queries: None,
             transforms: None,
             cw20_coins: None,
+            max_executions: None,
+            trigger: None,
+            callback_addr: None,
+            fee_token: None,
+            max_gas_price: None,
+            auto_refill: None,
         }
     }
     pub fn with_interval(&mut self, interval: Interval) -> &mut Self {
@@ -254,6 +382,15 @@ impl TaskRequestBuilder {
     pub fn cron(&mut self, crontab: String) -> &mut Self {
         self.with_interval(Interval::Cron(crontab))
     }
+    pub fn cron_tz(&mut self, crontab: String, timezone: String) -> &mut Self {
+        self.with_interval(Interval::CronTz { crontab, timezone })
+    }
+    pub fn at(&mut self, timestamp: Timestamp) -> &mut Self {
+        self.with_interval(Interval::At(timestamp))
+    }
+    pub fn every(&mut self, seconds: u64) -> &mut Self {
+        self.with_interval(Interval::Every { seconds })
+    }
     pub fn immediate(&mut self) -> &mut Self {
         self.with_interval(Interval::Immediate)
     }
@@ -277,6 +414,30 @@ impl TaskRequestBuilder {
         self.stop_on_fail = stop_on_fail;
         self
     }
+    pub fn with_max_executions(&mut self, max_executions: u64) -> &mut Self {
+        self.max_executions = Some(max_executions);
+        self
+    }
+    pub fn with_trigger(&mut self, trigger: Trigger) -> &mut Self {
+        self.trigger = Some(trigger);
+        self
+    }
+    pub fn with_callback_addr(&mut self, callback_addr: String) -> &mut Self {
+        self.callback_addr = Some(callback_addr);
+        self
+    }
+    pub fn with_fee_token(&mut self, fee_token: FeeToken) -> &mut Self {
+        self.fee_token = Some(fee_token);
+        self
+    }
+    pub fn with_max_gas_price(&mut self, max_gas_price: u64) -> &mut Self {
+        self.max_gas_price = Some(max_gas_price);
+        self
+    }
+    pub fn with_auto_refill(&mut self, auto_refill: AutoRefill) -> &mut Self {
+        self.auto_refill = Some(auto_refill);
+        self
+    }
 
     pub fn with_action(&mut self, action: Action) -> &mut Self {
         self.actions = Some(vec![action]);
@@ -324,6 +485,12 @@ impl TaskRequestBuilder {
             queries: self.queries.clone().unwrap_or_default(),
             transforms: self.transforms.clone().unwrap_or_default(),
             cw20_coins: self.cw20_coins.clone().unwrap_or_default(),
+            max_executions: self.max_executions,
+            trigger: self.trigger.clone(),
+            callback_addr: self.callback_addr.clone(),
+            fee_token: self.fee_token.clone(),
+            max_gas_price: self.max_gas_price,
+            auto_refill: self.auto_refill.clone(),
         })
     }
 }
@@ -332,8 +499,11 @@ impl Default for TaskRequestBuilder {
         Self::new()
     }
 }
+pub type TaskResponse = GenericTaskResponse<Empty>;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TaskResponse {
+#[schemars(rename = "TaskResponse")]
+pub struct GenericTaskResponse<T> {
     pub task_hash: String,
 
     pub owner_id: Addr,
@@ -347,9 +517,130 @@ pub struct TaskResponse {
     pub amount_for_one_task_native: Vec<Coin>,
     pub amount_for_one_task_cw20: Vec<Cw20CoinVerified>,
 
-    pub actions: Vec<Action>,
+    pub actions: Vec<Action<T>>,
     pub queries: Option<Vec<CroncatQuery>>,
     pub transforms: Option<Vec<Transform>>,
+
+    /// Executions left before the task ends, unlimited if not set
+    pub remaining_executions: Option<u64>,
+    pub executions: u64,
+    /// Counted in `executions` as well
+    pub failed_executions: u64,
+    pub paused: bool,
+    /// Parent task this task waits for
+    pub trigger: Option<Trigger>,
+    pub callback_addr: Option<Addr>,
+    pub fee_token: Option<FeeToken>,
+    pub max_gas_price: Option<u64>,
+    pub auto_refill: Option<AutoRefill>,
+}
+
+/// Sent to the task `callback_addr` after every execution of the task
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CroncatTaskCallback {
+    pub task_hash: String,
+    /// Actions that ran in this execution, failed ones included
+    pub executed_actions: u64,
+    pub failures: Vec<ActionFailure>,
+    /// Task got removed after this execution
+    pub task_ended: bool,
+}
+
+impl CroncatTaskCallback {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = CallbackExecuteMsg::CroncatTaskCallback(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above message
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum CallbackExecuteMsg {
+    CroncatTaskCallback(CroncatTaskCallback),
+}
+
+/// Query croncat sends to the `GasPriceSource::Oracle` contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GasPriceOracleQueryMsg {
+    GasPrice {},
+}
+
+/// Gas price numerator, over the denominator of the croncat `gas_price`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GasPriceOracleResponse {
+    pub numerator: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PreviewScheduleResponse {
+    pub slot_type: SlotType,
+    /// Block heights or timestamps (in nanos) of the slots
+    pub slots: Vec<u64>,
+    /// Schedule has no slots after the returned ones
+    pub exhausted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct SimulateTaskResponse {
+    /// Error the task creation would fail with, other fields are empty if set
+    pub error: Option<String>,
+    pub task_hash: Option<String>,
+    pub boundary: Option<Boundary>,
+    /// Deposit spent by every execution, agent fee included
+    pub amount_for_one_task: GenericBalance,
+    /// Total gas of one execution
+    pub gas_amount: u64,
+    /// Minimum deposit for a single execution
+    pub min_deposit: GenericBalance,
+    /// Minimum deposit for a recurring task
+    pub min_recurring_deposit: GenericBalance,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TaskDependencyResponse {
+    /// Dependent task
+    pub task_hash: String,
+    /// Parent task and the condition to trigger the dependent task
+    pub trigger: Trigger,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GetTaskDependenciesResponse {
+    /// Dependent tasks among the owner tasks of the page
+    pub dependencies: Vec<TaskDependencyResponse>,
+    /// Hash of the last owner task of the page, unset on the last page
+    pub next_cursor: Option<String>,
+}
+
+pub type GetTasksResponse = GenericGetTasksResponse<Empty>;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[schemars(rename = "GetTasksResponse")]
+pub struct GenericGetTasksResponse<T> {
+    pub tasks: Vec<GenericTaskResponse<T>>,
+    /// Hash of the last returned task, unset on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTasksWithQueriesResponse {
+    pub tasks: Vec<TaskWithQueriesResponse>,
+    /// Pass as `start_after` to `GetTasksWithQueries`, unset on the last page
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -358,6 +649,7 @@ pub struct TaskWithQueriesResponse {
     pub interval: Interval,
     pub boundary: Option<Boundary>,
     pub queries: Option<Vec<CroncatQuery>>,
+    pub paused: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -371,11 +663,14 @@ pub struct GetBalancesResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetWalletBalancesResponse {
     pub cw20_balances: Vec<Cw20CoinVerified>,
+    pub native_balances: Vec<Coin>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct GetAgentIdsResponse {
     pub active: Vec<Addr>,
     pub pending: Vec<Addr>,
+    /// Position of the last returned agent, active ones are listed before the pending ones
+    pub next_cursor: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -428,6 +723,12 @@ pub struct GetConfigResponse {
     pub gas_action_fee: u64,
     pub proxy_callback_gas: u32,
     pub slot_granularity_time: u64,
+    pub ibc_transfer_timeout: u64,
+    pub gov_msgs_enabled: bool,
+    pub stargate_type_urls: Vec<String>,
+    pub admin_allowlist: Vec<AdminAllowance>,
+    pub fee_tokens: Vec<FeeTokenRate>,
+    pub gas_price_source: GasPriceSource,
 
     pub cw20_whitelist: Vec<Addr>,
     pub native_denom: String,
@@ -0,0 +1,197 @@
+use cosmwasm_std::{
+    coins, testing::mock_dependencies, to_binary, Addr, CosmosMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20CoinVerified, Cw20ExecuteMsg};
+use cw721::Cw721ExecuteMsg;
+
+use crate::{
+    actions::{ActionUsage, AllowanceUsage},
+    error::CoreError,
+    msg::TaskRequest,
+    types::{Action, GenericBalance, Interval},
+};
+
+fn wasm_action<T: serde::Serialize>(contract_addr: &str, msg: &T) -> Action {
+    Action {
+        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(msg).unwrap(),
+            funds: vec![],
+        }),
+        gas_limit: Some(150_000),
+    }
+}
+
+#[test]
+fn cw20_usage() {
+    let deps = mock_dependencies();
+    let cw20 = Cw20CoinVerified {
+        address: Addr::unchecked("cw20"),
+        amount: Uint128::new(10),
+    };
+
+    // Allowance is paid from the deposit, like a transfer
+    let action = wasm_action(
+        "cw20",
+        &Cw20ExecuteMsg::IncreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(10),
+            expires: None,
+        },
+    );
+    assert_eq!(
+        action.usage(&deps.api).unwrap(),
+        ActionUsage {
+            deposit: GenericBalance {
+                native: vec![],
+                cw20: vec![cw20.clone()],
+            },
+            allowances: vec![],
+        }
+    );
+
+    // Pulled from the approved wallet
+    let action = wasm_action(
+        "cw20",
+        &Cw20ExecuteMsg::TransferFrom {
+            owner: "alice".to_string(),
+            recipient: "bob".to_string(),
+            amount: Uint128::new(10),
+        },
+    );
+    assert_eq!(
+        action.usage(&deps.api).unwrap(),
+        ActionUsage {
+            deposit: GenericBalance::default(),
+            allowances: vec![AllowanceUsage::Cw20 {
+                owner: Addr::unchecked("alice"),
+                coin: cw20,
+            }],
+        }
+    );
+
+    // Croncat can't account for burned tokens
+    let action = wasm_action(
+        "cw20",
+        &Cw20ExecuteMsg::Burn {
+            amount: Uint128::new(10),
+        },
+    );
+    assert_eq!(
+        action.usage(&deps.api).unwrap_err(),
+        CoreError::InvalidAction {}
+    );
+}
+
+#[test]
+fn cw721_usage() {
+    let deps = mock_dependencies();
+
+    let action = wasm_action(
+        "nft",
+        &Cw721ExecuteMsg::TransferNft {
+            recipient: "bob".to_string(),
+            token_id: "1".to_string(),
+        },
+    );
+    assert_eq!(
+        action.usage(&deps.api).unwrap(),
+        ActionUsage {
+            deposit: GenericBalance::default(),
+            allowances: vec![AllowanceUsage::Cw721 {
+                contract_addr: Addr::unchecked("nft"),
+                token_id: "1".to_string(),
+            }],
+        }
+    );
+
+    // Same shapes are used by non-cw721 contracts
+    let action = wasm_action(
+        "dao",
+        &Cw721ExecuteMsg::ApproveAll {
+            operator: "bob".to_string(),
+            expires: None,
+        },
+    );
+    assert_eq!(action.usage(&deps.api).unwrap(), ActionUsage::default());
+    let action = wasm_action(
+        "dao",
+        &Cw721ExecuteMsg::Burn {
+            token_id: "1".to_string(),
+        },
+    );
+    assert_eq!(action.usage(&deps.api).unwrap(), ActionUsage::default());
+}
+
+#[test]
+fn allowance_of_task_owner_only() {
+    let deps = mock_dependencies();
+    let mut task = TaskRequest {
+        interval: Interval::Once,
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![wasm_action(
+            "cw20",
+            &Cw20ExecuteMsg::TransferFrom {
+                owner: "alice".to_string(),
+                recipient: "bob".to_string(),
+                amount: Uint128::new(10),
+            },
+        )],
+        queries: None,
+        transforms: None,
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let (amount_for_one_task, _) = task
+        .is_valid_msg_calculate_usage(
+            &deps.api,
+            &Addr::unchecked("croncat"),
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("owner"),
+            5,
+            5,
+            5,
+            5,
+        )
+        .unwrap();
+    // Nothing is taken from the deposit
+    assert_eq!(amount_for_one_task, GenericBalance::default());
+
+    let err = task
+        .is_valid_msg_calculate_usage(
+            &deps.api,
+            &Addr::unchecked("croncat"),
+            &Addr::unchecked("mallory"),
+            &Addr::unchecked("owner"),
+            5,
+            5,
+            5,
+            5,
+        )
+        .unwrap_err();
+    assert_eq!(err, CoreError::InvalidAction {});
+
+    // Attached funds are still paid from the deposit
+    if let CosmosMsg::Wasm(WasmMsg::Execute { funds, .. }) = &mut task.actions[0].msg {
+        *funds = coins(5, "atom");
+    }
+    let (amount_for_one_task, _) = task
+        .is_valid_msg_calculate_usage(
+            &deps.api,
+            &Addr::unchecked("croncat"),
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("owner"),
+            5,
+            5,
+            5,
+            5,
+        )
+        .unwrap();
+    assert_eq!(amount_for_one_task.native, coins(5, "atom"));
+}
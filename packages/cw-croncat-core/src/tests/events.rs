@@ -0,0 +1,135 @@
+use cosmwasm_std::{coin, Addr, Attribute, Event, StdError, Uint128};
+use cw20::Cw20CoinVerified;
+
+use crate::{
+    events::{
+        CroncatEvent, SettingsChanged, TaskCreated, TaskEndReason, TaskEnded, TaskExecuted,
+        TaskRemoved,
+    },
+    types::SlotType,
+};
+
+#[test]
+fn events_round_trip() {
+    let created = TaskCreated {
+        task_hash: "atom:1234".to_string(),
+        owner: Addr::unchecked("alice"),
+        with_queries: false,
+        slot_id: Some(12345),
+        slot_kind: Some(SlotType::Block),
+        trigger_task_hash: None,
+    };
+    let event: Event = created.clone().into();
+    assert_eq!(event.ty, "croncat-task-created");
+    assert_eq!(
+        event.attributes,
+        vec![
+            Attribute::new("task_hash", "atom:1234"),
+            Attribute::new("owner", "alice"),
+            Attribute::new("with_queries", "false"),
+            Attribute::new("slot_id", "12345"),
+            Attribute::new("slot_kind", "Block"),
+        ]
+    );
+    assert_eq!(TaskCreated::from_event(&event).unwrap(), created);
+
+    let executed = TaskExecuted {
+        task_hash: "atom:1234".to_string(),
+        owner: Addr::unchecked("alice"),
+        agent: Addr::unchecked("bob"),
+        execution: 3,
+        executed_actions: 2,
+        failed_actions: 1,
+        failure: Some("Out of gas".to_string()),
+        fee_native: vec![coin(100, "atom"), coin(5, "ibc/ABC")],
+        fee_cw20: vec![Cw20CoinVerified {
+            address: Addr::unchecked("juno1token"),
+            amount: Uint128::new(7),
+        }],
+    };
+    let event = executed.clone().into_event();
+    assert_eq!(
+        event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "fee_native")
+            .unwrap()
+            .value,
+        "100atom,5ibc/ABC"
+    );
+    assert_eq!(
+        event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "fee_cw20")
+            .unwrap()
+            .value,
+        "7juno1token"
+    );
+    // Chain adds the prefix to the event type
+    let indexed = Event::new("wasm-croncat-task-executed").add_attributes(event.attributes);
+    assert_eq!(TaskExecuted::from_event(&indexed).unwrap(), executed);
+
+    // Empty lists and unset options are left out
+    let removed = TaskRemoved {
+        task_hash: "atom:1234".to_string(),
+        owner: Addr::unchecked("alice"),
+        refund_native: vec![],
+        refund_cw20: vec![],
+    };
+    let event = removed.clone().into_event();
+    assert_eq!(event.attributes.len(), 2);
+    assert_eq!(TaskRemoved::from_event(&event).unwrap(), removed);
+
+    let ended = TaskEnded {
+        task_hash: "atom:1234".to_string(),
+        owner: Addr::unchecked("alice"),
+        reason: TaskEndReason::OutOfFunds,
+        error: None,
+    };
+    assert_eq!(
+        TaskEnded::from_event(&ended.clone().into_event()).unwrap(),
+        ended
+    );
+
+    let changed = SettingsChanged {
+        owner_id: Addr::unchecked("alice"),
+        paused: true,
+        changed: vec!["paused".to_string(), "agent_fee".to_string()],
+    };
+    assert_eq!(
+        SettingsChanged::from_event(&changed.clone().into_event()).unwrap(),
+        changed
+    );
+}
+
+#[test]
+fn events_parse_errors() {
+    // Different event type
+    let event = Event::new("wasm-croncat-task-removed")
+        .add_attribute("task_hash", "atom:1234")
+        .add_attribute("owner", "alice");
+    assert!(matches!(
+        TaskCreated::from_event(&event),
+        Err(StdError::ParseErr { .. })
+    ));
+
+    // Required attribute missing
+    let event = Event::new("croncat-task-created").add_attribute("task_hash", "atom:1234");
+    assert!(matches!(
+        TaskCreated::from_event(&event),
+        Err(StdError::NotFound { .. })
+    ));
+
+    // Invalid values
+    let event = Event::new("croncat-task-removed")
+        .add_attribute("task_hash", "atom:1234")
+        .add_attribute("owner", "alice")
+        .add_attribute("refund_native", "atom");
+    assert!(TaskRemoved::from_event(&event).is_err());
+    let event = Event::new("croncat-task-ended")
+        .add_attribute("task_hash", "atom:1234")
+        .add_attribute("owner", "alice")
+        .add_attribute("reason", "Unknown");
+    assert!(TaskEnded::from_event(&event).is_err());
+}
@@ -1,2 +1,5 @@
+mod actions;
+mod events;
 mod msg;
+mod timezone;
 mod types;
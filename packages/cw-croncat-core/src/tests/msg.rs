@@ -1,16 +1,16 @@
-use cosmwasm_std::{coin, coins, Addr, BankMsg, CosmosMsg, Timestamp, Uint64};
+use cosmwasm_std::{coin, coins, Addr, BankMsg, CosmosMsg, Timestamp, Uint64, WasmMsg};
 use cw20::Cw20CoinVerified;
 
 use crate::{
     error::CoreError,
     msg::{
-        AgentResponse, AgentTaskResponse, Croncat, GetAgentIdsResponse, GetBalancesResponse,
-        GetConfigResponse, GetSlotHashesResponse, GetSlotIdsResponse, GetWalletBalancesResponse,
-        TaskRequest, TaskRequestBuilder, TaskResponse,
+        AgentResponse, AgentTaskResponse, Croncat, CroncatTaskCallback, GetAgentIdsResponse,
+        GetBalancesResponse, GetConfigResponse, GetSlotHashesResponse, GetSlotIdsResponse,
+        GetTasksResponse, GetWalletBalancesResponse, TaskRequest, TaskRequestBuilder, TaskResponse,
     },
     types::{
-        Action, Agent, AgentStatus, Boundary, CheckedBoundary, GasPrice, GenericBalance, Interval,
-        SlotType, Task,
+        Action, ActionFailure, Agent, AgentStatus, Boundary, CheckedBoundary, GasPrice,
+        GasPriceSource, GenericBalance, Interval, SlotType, Task,
     },
 };
 
@@ -56,6 +56,16 @@ fn everything_can_be_de_serialized() {
         queries: None,
         transforms: None,
         version: String::from(""),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
     .into();
 
@@ -73,6 +83,10 @@ fn everything_can_be_de_serialized() {
             gas_adjustment_numerator: 3,
         },
         proxy_callback_gas: 3,
+        ibc_transfer_timeout: 600,
+        gov_msgs_enabled: false,
+        stargate_type_urls: vec![],
+        admin_allowlist: vec![],
         slot_granularity_time: 60_000_000,
         native_denom: "juno".to_string(),
         cw_rules_addr: Addr::unchecked("bob"),
@@ -83,6 +97,8 @@ fn everything_can_be_de_serialized() {
         available_balance: GenericBalance::default(),
         staked_balance: GenericBalance::default(),
         limit: 100,
+        fee_tokens: vec![],
+        gas_price_source: GasPriceSource::Fixed,
     }
     .into();
     let balance_response = GetBalancesResponse {
@@ -95,6 +111,7 @@ fn everything_can_be_de_serialized() {
     let get_agent_ids_response = GetAgentIdsResponse {
         active: vec![Addr::unchecked("bob")],
         pending: vec![Addr::unchecked("bob")],
+        next_cursor: None,
     }
     .into();
     let get_agent_tasks_response = AgentTaskResponse {
@@ -115,6 +132,12 @@ fn everything_can_be_de_serialized() {
         queries: None,
         transforms: None, // TODO
         cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     }
     .into();
     let task_response_raw = TaskResponse {
@@ -133,6 +156,15 @@ fn everything_can_be_de_serialized() {
         actions: vec![],
         queries: None,
         transforms: None,
+        remaining_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     let task_response = task_response_raw.clone().into();
     let validate_interval_response = false.into();
@@ -145,8 +177,16 @@ fn everything_can_be_de_serialized() {
         register_start: Timestamp::from_nanos(5),
     })
     .into();
-    let get_tasks_response = vec![task_response_raw.clone()].into();
-    let get_tasks_by_owner_response = vec![task_response_raw.clone()].into();
+    let get_tasks_response = GetTasksResponse {
+        tasks: vec![task_response_raw.clone()],
+        next_cursor: None,
+    }
+    .into();
+    let get_tasks_by_owner_response = GetTasksResponse {
+        tasks: vec![task_response_raw.clone()],
+        next_cursor: Some("asd".to_string()),
+    }
+    .into();
     let get_task_response = Some(task_response_raw).into();
     let get_task_hash_response = ("asd".to_string()).into();
     let get_slot_hashes_response = GetSlotHashesResponse {
@@ -166,6 +206,7 @@ fn everything_can_be_de_serialized() {
             address: Addr::unchecked("Bob"),
             amount: 5u128.into(),
         }],
+        native_balances: vec![],
     }
     .into();
     let croncat = Croncat {
@@ -235,3 +276,32 @@ fn test_task_request_builder() {
 
     assert!(matches!(invalid_result, CoreError::InvalidBoundary {}));
 }
+
+#[test]
+fn task_callback_serialization() {
+    let callback = CroncatTaskCallback {
+        task_hash: "atom:hash".to_string(),
+        executed_actions: 2,
+        failures: vec![ActionFailure {
+            action_idx: 1,
+            error: "Generic error".to_string(),
+        }],
+        task_ended: true,
+    };
+
+    let binary = callback.clone().into_binary().unwrap();
+    assert_eq!(
+        String::from_utf8(binary.to_vec()).unwrap(),
+        r#"{"croncat_task_callback":{"task_hash":"atom:hash","executed_actions":2,"failures":[{"action_idx":1,"error":"Generic error"}],"task_ended":true}}"#
+    );
+
+    let msg = callback.into_cosmos_msg("callback_contract").unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "callback_contract".to_string(),
+            msg: binary,
+            funds: vec![],
+        })
+    );
+}
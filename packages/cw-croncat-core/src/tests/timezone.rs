@@ -0,0 +1,92 @@
+use cron_schedule::Schedule;
+use std::str::FromStr;
+
+use crate::{timezone::TimeZone, traits::Intervals, types::Interval};
+
+const HOUR: i64 = 3_600;
+const NANOS: u64 = 1_000_000_000;
+
+#[test]
+fn unknown_timezone_is_invalid() {
+    assert!(TimeZone::from_name("Mars/Olympus_Mons").is_none());
+    assert!(TimeZone::from_name("europe/berlin").is_none());
+
+    let valid = Interval::CronTz {
+        crontab: "0 0 9 * * *".to_string(),
+        timezone: "Europe/Berlin".to_string(),
+    };
+    assert!(valid.is_valid());
+
+    let unknown_zone = Interval::CronTz {
+        crontab: "0 0 9 * * *".to_string(),
+        timezone: "Mars/Olympus_Mons".to_string(),
+    };
+    assert!(!unknown_zone.is_valid());
+
+    let invalid_crontab = Interval::CronTz {
+        crontab: "every morning".to_string(),
+        timezone: "Europe/Berlin".to_string(),
+    };
+    assert!(!invalid_crontab.is_valid());
+}
+
+#[test]
+fn dst_transitions() {
+    // 2023-03-26 01:00 UTC and 2023-10-29 01:00 UTC
+    let berlin = TimeZone::from_name("Europe/Berlin").unwrap();
+    assert_eq!(berlin.offset_at(1679792400 - 1), HOUR);
+    assert_eq!(berlin.offset_at(1679792400), 2 * HOUR);
+    assert_eq!(berlin.offset_at(1698541200 - 1), 2 * HOUR);
+    assert_eq!(berlin.offset_at(1698541200), HOUR);
+
+    // 2023-03-12 02:00 EST and 2023-11-05 02:00 EDT
+    let new_york = TimeZone::from_name("America/New_York").unwrap();
+    assert_eq!(new_york.offset_at(1678604400 - 1), -5 * HOUR);
+    assert_eq!(new_york.offset_at(1678604400), -4 * HOUR);
+    assert_eq!(new_york.offset_at(1699164000 - 1), -4 * HOUR);
+    assert_eq!(new_york.offset_at(1699164000), -5 * HOUR);
+
+    // Southern hemisphere: 2023-04-02 03:00 AEDT and 2023-10-01 02:00 AEST
+    let sydney = TimeZone::from_name("Australia/Sydney").unwrap();
+    assert_eq!(sydney.offset_at(1680364800 - 1), 11 * HOUR);
+    assert_eq!(sydney.offset_at(1680364800), 10 * HOUR);
+    assert_eq!(sydney.offset_at(1696089600 - 1), 10 * HOUR);
+    assert_eq!(sydney.offset_at(1696089600), 11 * HOUR);
+
+    let tokyo = TimeZone::from_name("Asia/Tokyo").unwrap();
+    assert_eq!(tokyo.offset_at(1679792400), 9 * HOUR);
+    assert_eq!(tokyo.offset_at(1698541200), 9 * HOUR);
+}
+
+#[test]
+fn next_after_follows_local_time() {
+    let berlin = TimeZone::from_name("Europe/Berlin").unwrap();
+    let every_morning = Schedule::from_str("0 0 9 * * *").unwrap();
+
+    // 2023-03-24 12:00 UTC -> 2023-03-25 09:00 CET
+    assert_eq!(
+        berlin.next_after(&every_morning, 1679659200 * NANOS),
+        Some(1679731200 * NANOS)
+    );
+    // 2023-03-25 12:00 UTC -> 2023-03-26 09:00 CEST
+    assert_eq!(
+        berlin.next_after(&every_morning, 1679745600 * NANOS),
+        Some(1679814000 * NANOS)
+    );
+
+    let at_half_past_two = Schedule::from_str("0 30 2 * * *").unwrap();
+    // 02:30 is skipped on 2023-03-26, runs at 03:30 CEST instead
+    assert_eq!(
+        berlin.next_after(&at_half_past_two, 1679745600 * NANOS),
+        Some(1679794200 * NANOS)
+    );
+    // 02:30 happens twice on 2023-10-29, runs only the first time
+    let first = berlin
+        .next_after(&at_half_past_two, (1698539400 - HOUR as u64) * NANOS)
+        .unwrap();
+    assert_eq!(first, 1698539400 * NANOS);
+    assert_eq!(
+        berlin.next_after(&at_half_past_two, first + 60 * NANOS),
+        Some(1698629400 * NANOS)
+    );
+}
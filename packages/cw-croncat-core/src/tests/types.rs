@@ -1,16 +1,25 @@
 use crate::types::get_next_block_by_offset;
 use crate::{
     error::CoreError,
-    msg::TaskRequest,
-    types::{Action, Boundary, CheckedBoundary, GenericBalance, Interval, Task, Transform},
+    msg::{GenericTaskRequest, TaskRequest},
+    traits::Intervals,
+    types::{
+        canonical_json, Action, Boundary, CheckedBoundary, FeeToken, FeeTokenRate, GasPrice,
+        GasPriceSource, GenericBalance, Interval, SlotType, Task, Transform, TASK_HASH_VERSION,
+    },
 };
 use cosmwasm_std::{
-    coins, testing::mock_dependencies, Addr, BankMsg, Binary, Coin, CosmosMsg, GovMsg, IbcMsg,
-    IbcTimeout, StdError, Timestamp, Uint64, VoteOption, WasmMsg,
+    coins,
+    testing::{mock_dependencies, mock_env},
+    Addr, BankMsg, Binary, Coin, CosmosMsg, CustomMsg, GovMsg, IbcMsg, IbcTimeout, StdError,
+    Timestamp, Uint128, Uint64, VoteOption, WasmMsg,
 };
 use cw20::Cw20CoinVerified;
 use cw_rules_core::types::{CroncatQuery, HasBalanceGte};
+use generic_query::ValueIndex;
 use hex::ToHex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 #[test]
 fn is_valid_msg_once_block_based() {
@@ -32,6 +41,12 @@ fn is_valid_msg_once_block_based() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert!(task
         .is_valid_msg_calculate_usage(
@@ -67,6 +82,12 @@ fn is_valid_msg_once_time_based() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert!(task
         .is_valid_msg_calculate_usage(
@@ -99,6 +120,12 @@ fn is_valid_msg_recurring() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert!(task
         .is_valid_msg_calculate_usage(
@@ -135,6 +162,12 @@ fn is_valid_msg_wrong_account() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert_eq!(
         CoreError::InvalidAction {},
@@ -154,7 +187,7 @@ fn is_valid_msg_wrong_account() {
 
 #[test]
 fn is_valid_msg_vote() {
-    // A task with CosmosMsg::Gov Vote should return false
+    // A task with CosmosMsg::Gov Vote only pays for the gas
     let task = TaskRequest {
         interval: Interval::Block(5),
         boundary: Some(Boundary::Height {
@@ -172,10 +205,15 @@ fn is_valid_msg_vote() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
-    assert_eq!(
-        CoreError::InvalidAction {},
-        task.is_valid_msg_calculate_usage(
+    let (amount_for_one_task, gas_amount) = task
+        .is_valid_msg_calculate_usage(
             &mock_dependencies().api,
             &Addr::unchecked("alice"),
             &Addr::unchecked("sender"),
@@ -183,16 +221,136 @@ fn is_valid_msg_vote() {
             5,
             5,
             5,
-            5
+            5,
         )
-        .unwrap_err()
+        .unwrap();
+    assert!(amount_for_one_task.native.is_empty());
+    assert_eq!(gas_amount, 10);
+}
+
+#[test]
+fn is_valid_msg_stargate() {
+    // A task with CosmosMsg::Stargate uses the default action gas
+    let mut task = TaskRequest {
+        interval: Interval::Block(5),
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: CosmosMsg::Stargate {
+                type_url: "/osmosis.tokenfactory.v1beta1.MsgMint".to_string(),
+                value: Binary::from(b"mint".to_vec()),
+            },
+            gas_limit: None,
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let (amount_for_one_task, gas_amount) = task
+        .is_valid_msg_calculate_usage(
+            &mock_dependencies().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            7,
+            5,
+            5,
+        )
+        .unwrap();
+    assert!(amount_for_one_task.native.is_empty());
+    assert_eq!(gas_amount, 12);
+
+    for type_url in ["", "/cosmos.authz.v1beta1.MsgExec"] {
+        task.actions[0].msg = CosmosMsg::Stargate {
+            type_url: type_url.to_string(),
+            value: Binary::from(b"mint".to_vec()),
+        };
+        assert_eq!(
+            CoreError::InvalidAction {},
+            task.is_valid_msg_calculate_usage(
+                &mock_dependencies().api,
+                &Addr::unchecked("alice"),
+                &Addr::unchecked("sender"),
+                &Addr::unchecked("bob"),
+                5,
+                5,
+                5,
+                5
+            )
+            .unwrap_err()
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum TokenFactoryMsg {
+    MintTokens {
+        denom: String,
+        amount: Uint128,
+        mint_to_address: String,
+    },
+}
+
+impl CustomMsg for TokenFactoryMsg {}
+
+#[test]
+fn is_valid_msg_custom() {
+    // A task with CosmosMsg::Custom uses the default action gas
+    // and is only accepted with the `custom-msg` feature
+    let task = GenericTaskRequest {
+        interval: Interval::Block(5),
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: CosmosMsg::Custom(TokenFactoryMsg::MintTokens {
+                denom: "factory/alice/coin".to_string(),
+                amount: Uint128::new(10),
+                mint_to_address: "bob".to_string(),
+            }),
+            gas_limit: None,
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let res = task.is_valid_msg_calculate_usage(
+        &mock_dependencies().api,
+        &Addr::unchecked("alice"),
+        &Addr::unchecked("sender"),
+        &Addr::unchecked("bob"),
+        5,
+        7,
+        5,
+        5,
     );
+    #[cfg(feature = "custom-msg")]
+    {
+        let (amount_for_one_task, gas_amount) = res.unwrap();
+        assert!(amount_for_one_task.native.is_empty());
+        assert_eq!(gas_amount, 12);
+    }
+    #[cfg(not(feature = "custom-msg"))]
+    assert_eq!(CoreError::InvalidAction {}, res.unwrap_err());
 }
 
 #[test]
 fn is_valid_msg_transfer() {
-    // A task with CosmosMsg::Ibc Transfer should return false
-    let task = TaskRequest {
+    // A task with CosmosMsg::Ibc Transfer should reserve the transferred amount
+    let mut task = TaskRequest {
         interval: Interval::Block(5),
         boundary: Some(Boundary::Height {
             start: Some(Uint64::from(4u64)),
@@ -211,7 +369,43 @@ fn is_valid_msg_transfer() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
+    let (amount_for_one_task, gas_amount) = task
+        .is_valid_msg_calculate_usage(
+            &mock_dependencies().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5,
+        )
+        .unwrap();
+    assert_eq!(amount_for_one_task.native, vec![Coin::new(10, "coin")]);
+    assert_eq!(gas_amount, 10);
+    assert_eq!(
+        task.actions[0]
+            .usage(&mock_dependencies().api)
+            .unwrap()
+            .deposit
+            .native,
+        vec![Coin::new(10, "coin")]
+    );
+
+    // Zero transfer is not allowed
+    task.actions[0].msg = CosmosMsg::Ibc(IbcMsg::Transfer {
+        channel_id: "id".to_string(),
+        to_address: "address".to_string(),
+        amount: Coin::new(0, "coin"),
+        timeout: IbcTimeout::with_timestamp(Timestamp::from_nanos(1_000_000_000)),
+    });
     assert_eq!(
         CoreError::InvalidAction {},
         task.is_valid_msg_calculate_usage(
@@ -247,6 +441,12 @@ fn is_valid_msg_burn() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert_eq!(
         CoreError::InvalidAction {},
@@ -284,6 +484,12 @@ fn is_valid_msg_send_doesnt_fail() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert!(task
         .is_valid_msg_calculate_usage(
@@ -319,6 +525,12 @@ fn is_valid_msg_send_should_success() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert!(task
         .is_valid_msg_calculate_usage(
@@ -334,6 +546,168 @@ fn is_valid_msg_send_should_success() {
         .is_ok());
 }
 
+#[test]
+fn is_valid_msg_wasm_funds() {
+    // Funds attached to WasmMsg::Execute are reserved like a bank send
+    let mut task = TaskRequest {
+        interval: Interval::Block(1),
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "contract".to_string(),
+                msg: Binary::from(br#"{"do_something":{}}"#.to_vec()),
+                funds: vec![Coin::new(10, "atom")],
+            }),
+            gas_limit: Some(5),
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    let (amount_for_one_task, _) = task
+        .is_valid_msg_calculate_usage(
+            mock_dependencies().as_ref().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5,
+        )
+        .unwrap();
+    assert_eq!(amount_for_one_task.native, vec![Coin::new(10, "atom")]);
+    assert_eq!(
+        task.actions[0]
+            .usage(mock_dependencies().as_ref().api)
+            .unwrap()
+            .deposit
+            .native,
+        vec![Coin::new(10, "atom")]
+    );
+
+    task.actions[0].msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: "contract".to_string(),
+        msg: Binary::from(br#"{"do_something":{}}"#.to_vec()),
+        funds: vec![Coin::new(0, "atom")],
+    });
+    assert_eq!(
+        CoreError::InvalidAction {},
+        task.is_valid_msg_calculate_usage(
+            mock_dependencies().as_ref().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5
+        )
+        .unwrap_err()
+    );
+}
+
+#[test]
+fn is_valid_msg_instantiate_migrate() {
+    // Instantiate has to set the gas limit and pays the funds from the deposit
+    let mut task = TaskRequest {
+        interval: Interval::Block(1),
+        boundary: None,
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: Some("alice".to_string()),
+                code_id: 1,
+                msg: Binary::from(br#"{"vesting":{}}"#.to_vec()),
+                funds: vec![Coin::new(10, "atom")],
+                label: "vesting".to_string(),
+            }),
+            gas_limit: None,
+        }],
+        queries: None,
+        transforms: None,
+        cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    assert_eq!(
+        CoreError::NoGasLimit {},
+        task.is_valid_msg_calculate_usage(
+            mock_dependencies().as_ref().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5
+        )
+        .unwrap_err()
+    );
+    task.actions[0].gas_limit = Some(5);
+    let (amount_for_one_task, _) = task
+        .is_valid_msg_calculate_usage(
+            mock_dependencies().as_ref().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5,
+        )
+        .unwrap();
+    assert_eq!(amount_for_one_task.native, vec![Coin::new(10, "atom")]);
+
+    // Migrate has to set the gas limit too
+    task.actions[0] = Action {
+        msg: CosmosMsg::Wasm(WasmMsg::Migrate {
+            contract_addr: "vesting".to_string(),
+            new_code_id: 2,
+            msg: Binary::from(br#"{}"#.to_vec()),
+        }),
+        gas_limit: None,
+    };
+    assert_eq!(
+        CoreError::NoGasLimit {},
+        task.is_valid_msg_calculate_usage(
+            mock_dependencies().as_ref().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5
+        )
+        .unwrap_err()
+    );
+    task.actions[0].gas_limit = Some(5);
+    assert!(task
+        .is_valid_msg_calculate_usage(
+            mock_dependencies().as_ref().api,
+            &Addr::unchecked("alice"),
+            &Addr::unchecked("sender"),
+            &Addr::unchecked("bob"),
+            5,
+            5,
+            5,
+            5
+        )
+        .is_ok());
+}
+
 #[test]
 fn is_valid_empty_actions() {
     let task = TaskRequest {
@@ -344,6 +718,12 @@ fn is_valid_empty_actions() {
         queries: None,
         transforms: None,
         cw20_coins: Default::default(),
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
     assert_eq!(
         task.is_valid_msg_calculate_usage(
@@ -550,24 +930,91 @@ fn hashing() {
             query_response_path: vec![].into(),
         }]),
         version: String::from(""),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
     };
 
-    let message = format!(
-        "{:?}{:?}{:?}{:?}{:?}{:?}",
-        task.owner_id, task.interval, task.boundary, task.actions, task.queries, task.transforms
-    );
+    // Golden vector, clients in other languages have to produce exactly these bytes
+    let fields: [&[u8]; 16] = [
+        b"croncat:task:v1",
+        b"bob",
+        br#"{"Block":5}"#,
+        &[0, 0, 0, 0, 0, 0, 0, 4],
+        b"",
+        &[1],
+        &[0, 0, 0, 1],
+        br#"{"wasm":{"clear_admin":{"contract_addr":"alice"}}}"#,
+        &[0, 0, 0, 0, 0, 0, 0, 5],
+        &[0, 0, 0, 1],
+        br#"{"has_balance_gte":{"address":"foo","required_balance":{"native":[{"amount":"5","denom":"atom"}]}}}"#,
+        &[0, 0, 0, 1],
+        &[0, 0, 0, 0, 0, 0, 0, 0],
+        &[0, 0, 0, 0, 0, 0, 0, 0],
+        b"[]",
+        b"[]",
+    ];
+    assert_eq!(fields[0], TASK_HASH_VERSION.as_bytes());
+    let mut message = Vec::new();
+    for field in fields {
+        message.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        message.extend_from_slice(field);
+    }
 
-    let hash = Sha256::digest(message.as_bytes());
+    let hash = Sha256::digest(&message);
 
     let encode: String = hash.encode_hex();
     let prefix = "atom";
     let (_, l) = encode.split_at(prefix.len() + 1);
     let encoded = format!("{}:{}", prefix, l);
+    assert_eq!(
+        encoded,
+        "atom:ae21fde665b020d510d82bc2bd5c2a3b5f1fa0112b785665b1b76134121"
+    );
     let bytes = encoded.clone().into_bytes();
 
     // Tests
     assert_eq!(encoded, task.to_hash(prefix.clone()));
     assert_eq!(bytes, task.to_hash_vec(prefix));
+
+    // Same hash can be computed from the request
+    let task_request = TaskRequest {
+        interval: task.interval.clone(),
+        boundary: Some(Boundary::Height {
+            start: Some(4u64.into()),
+            end: None,
+        }),
+        stop_on_fail: false,
+        actions: task.actions.clone(),
+        queries: task.queries.clone(),
+        transforms: task.transforms.clone(),
+        cw20_coins: vec![],
+        max_executions: None,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    assert_eq!(
+        Ok(encoded.clone()),
+        task_request.to_hash(&Addr::unchecked("bob"), prefix)
+    );
+
+    // Updated task keeps its original hash
+    let updated_task = Task {
+        interval: Interval::Block(10),
+        task_hash: Some(encoded.clone()),
+        ..task
+    };
+    assert_eq!(encoded, updated_task.to_hash(prefix));
 }
 
 #[test]
@@ -624,3 +1071,323 @@ fn test_get_next_block_by_offset() {
     let result = get_next_block_by_offset(block_height, boundary_with_end, interval);
     assert_eq!(boundary_with_end.end.unwrap(), result.0);
 }
+
+#[test]
+fn test_next_at_and_every() {
+    let slot_granularity_time = 10_000_000_000;
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(1_000_000);
+    let no_boundary = CheckedBoundary {
+        start: None,
+        end: None,
+        is_block_boundary: Some(false),
+    };
+
+    // Exact timestamp goes into its slot
+    let at = Interval::At(Timestamp::from_seconds(1_000_125));
+    assert_eq!(
+        at.next(&env, no_boundary, slot_granularity_time),
+        (1_000_120_000_000_000, SlotType::Cron)
+    );
+    // Passed timestamp goes into the next slot
+    let at = Interval::At(Timestamp::from_seconds(999_000));
+    assert_eq!(
+        at.next(&env, no_boundary, slot_granularity_time),
+        (1_000_010_000_000_000, SlotType::Cron)
+    );
+
+    // Periods are counted from the unix epoch without a start boundary
+    let every = Interval::Every { seconds: 90 };
+    assert_eq!(
+        every.next(&env, no_boundary, slot_granularity_time),
+        (1_000_080_000_000_000, SlotType::Cron)
+    );
+
+    // And from the start boundary otherwise
+    let boundary = CheckedBoundary {
+        start: Some(1_000_005_000_000_000),
+        end: Some(1_000_200_000_000_000),
+        is_block_boundary: Some(false),
+    };
+    assert_eq!(
+        every.next(&env, boundary, slot_granularity_time),
+        (
+            1_000_000_000_000_000 + slot_granularity_time,
+            SlotType::Cron
+        )
+    );
+    env.block.time = Timestamp::from_seconds(1_000_010);
+    assert_eq!(
+        every.next(&env, boundary, slot_granularity_time),
+        (1_000_090_000_000_000, SlotType::Cron)
+    );
+    // Doesn't go past the end boundary
+    env.block.time = Timestamp::from_seconds(1_000_190);
+    assert_eq!(
+        every.next(&env, boundary, slot_granularity_time),
+        (1_000_200_000_000_000, SlotType::Cron)
+    );
+    env.block.time = Timestamp::from_seconds(1_000_201);
+    assert_eq!(
+        every.next(&env, boundary, slot_granularity_time),
+        (0, SlotType::Cron)
+    );
+
+    assert!(!Interval::Every { seconds: 0 }.is_valid());
+}
+
+#[test]
+fn test_next_exhausted_schedule() {
+    let env = mock_env();
+    let no_boundary = CheckedBoundary {
+        start: None,
+        end: None,
+        is_block_boundary: Some(false),
+    };
+
+    // No future occurrence of the crontab
+    let past_year = Interval::Cron("0 0 0 1 1 * 2020".to_string());
+    assert!(past_year.is_valid());
+    assert_eq!(
+        past_year.next(&env, no_boundary, 10_000_000_000),
+        (0, SlotType::Cron)
+    );
+    let past_year = Interval::CronTz {
+        crontab: "0 0 0 1 1 * 2020".to_string(),
+        timezone: "Europe/Berlin".to_string(),
+    };
+    assert_eq!(
+        past_year.next(&env, no_boundary, 10_000_000_000),
+        (0, SlotType::Cron)
+    );
+
+    assert!(!Interval::Block(0).is_valid());
+}
+
+#[test]
+fn fee_token_rate_fee() {
+    let native_rate = FeeTokenRate {
+        token: FeeToken::Native("ujuno".to_string()),
+        numerator: 3,
+        denominator: 2,
+    };
+    assert!(native_rate.is_valid());
+    assert_eq!(
+        native_rate.fee(1001).unwrap(),
+        GenericBalance {
+            native: coins(1501, "ujuno"),
+            cw20: vec![],
+        }
+    );
+
+    let cw20_rate = FeeTokenRate {
+        token: FeeToken::Cw20(Addr::unchecked("cw20")),
+        numerator: 1,
+        denominator: 100,
+    };
+    assert_eq!(
+        cw20_rate.fee(1001).unwrap(),
+        GenericBalance {
+            native: vec![],
+            cw20: vec![Cw20CoinVerified {
+                address: Addr::unchecked("cw20"),
+                amount: 10u128.into(),
+            }],
+        }
+    );
+
+    let invalid_rate = FeeTokenRate {
+        denominator: 0,
+        ..cw20_rate
+    };
+    assert!(!invalid_rate.is_valid());
+    assert_eq!(
+        invalid_rate.fee(1001).unwrap_err(),
+        CoreError::InvalidGas {}
+    );
+}
+
+#[test]
+fn gas_price_source_bounds() {
+    let gas_price = GasPrice {
+        numerator: 4,
+        denominator: 100,
+        gas_adjustment_numerator: 150,
+    };
+    assert!(GasPriceSource::Fixed.is_valid());
+    assert_eq!(GasPriceSource::Fixed.numerator_bounds(&gas_price), (4, 4));
+
+    let oracle = GasPriceSource::Oracle {
+        contract_addr: Addr::unchecked("oracle"),
+        min_numerator: 2,
+        max_numerator: 10,
+    };
+    assert!(oracle.is_valid());
+    assert_eq!(oracle.numerator_bounds(&gas_price), (2, 10));
+
+    let reversed = GasPriceSource::Oracle {
+        contract_addr: Addr::unchecked("oracle"),
+        min_numerator: 10,
+        max_numerator: 2,
+    };
+    assert!(!reversed.is_valid());
+    let zero = GasPriceSource::Oracle {
+        contract_addr: Addr::unchecked("oracle"),
+        min_numerator: 0,
+        max_numerator: 2,
+    };
+    assert!(!zero.is_valid());
+}
+
+#[test]
+fn covered_executions() {
+    let mut task = Task {
+        owner_id: Addr::unchecked("bob"),
+        interval: Interval::Block(5),
+        boundary: CheckedBoundary {
+            start: None,
+            end: None,
+            is_block_boundary: Some(true),
+        },
+        stop_on_fail: false,
+        total_deposit: GenericBalance {
+            native: coins(35, "atom"),
+            cw20: vec![Cw20CoinVerified {
+                address: Addr::unchecked("cw20"),
+                amount: 7u128.into(),
+            }],
+        },
+        amount_for_one_task: GenericBalance {
+            native: coins(10, "atom"),
+            cw20: vec![],
+        },
+        actions: vec![],
+        queries: None,
+        transforms: None,
+        version: String::from(""),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    assert_eq!(task.covered_executions(), 3);
+
+    // The scarcest token decides
+    task.amount_for_one_task.cw20 = vec![Cw20CoinVerified {
+        address: Addr::unchecked("cw20"),
+        amount: 5u128.into(),
+    }];
+    assert_eq!(task.covered_executions(), 1);
+    assert_eq!(
+        task.amount_for_executions(3).unwrap(),
+        GenericBalance {
+            native: coins(30, "atom"),
+            cw20: vec![Cw20CoinVerified {
+                address: Addr::unchecked("cw20"),
+                amount: 15u128.into(),
+            }],
+        }
+    );
+
+    // Nothing to pay for
+    task.amount_for_one_task = GenericBalance::default();
+    assert_eq!(task.covered_executions(), u128::MAX);
+}
+
+#[test]
+fn task_targets() {
+    let send = |to_address: &str| Action {
+        msg: BankMsg::Send {
+            to_address: to_address.to_string(),
+            amount: coins(5, "atom"),
+        }
+        .into(),
+        gas_limit: None,
+    };
+    let execute = |contract_addr: &str| Action {
+        msg: WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: Binary::default(),
+            funds: vec![],
+        }
+        .into(),
+        gas_limit: Some(150_000),
+    };
+    let task = Task {
+        owner_id: Addr::unchecked("bob"),
+        interval: Interval::Once,
+        boundary: CheckedBoundary {
+            start: None,
+            end: None,
+            is_block_boundary: Some(true),
+        },
+        stop_on_fail: false,
+        total_deposit: GenericBalance::default(),
+        amount_for_one_task: GenericBalance::default(),
+        actions: vec![
+            execute("contract"),
+            send("alice"),
+            execute("contract"),
+            Action {
+                msg: GovMsg::Vote {
+                    proposal_id: 1,
+                    vote: VoteOption::Yes,
+                }
+                .into(),
+                gas_limit: None,
+            },
+        ],
+        queries: None,
+        transforms: None,
+        version: String::from(""),
+        max_executions: None,
+        executions: 0,
+        failed_executions: 0,
+        task_hash: None,
+        paused: false,
+        trigger: None,
+        callback_addr: None,
+        fee_token: None,
+        max_gas_price: None,
+        auto_refill: None,
+    };
+    assert_eq!(
+        task.targets(),
+        vec![Addr::unchecked("alice"), Addr::unchecked("contract")]
+    );
+}
+
+#[test]
+fn canonical_json_sorts_keys_and_drops_nulls() {
+    let transform = Transform {
+        action_idx: 1,
+        query_idx: 2,
+        action_path: vec![ValueIndex::Key("msg".to_string()), ValueIndex::Index(0)].into(),
+        query_response_path: vec![].into(),
+    };
+    assert_eq!(
+        canonical_json(&transform),
+        br#"{"action_idx":1,"action_path":[{"key":"msg"},{"index":0}],"query_idx":2,"query_response_path":[]}"#
+    );
+
+    let boundary = CheckedBoundary {
+        start: Some(4),
+        end: None,
+        is_block_boundary: Some(true),
+    };
+    assert_eq!(
+        canonical_json(&boundary),
+        br#"{"is_block_boundary":true,"start":4}"#
+    );
+
+    assert_eq!(
+        canonical_json(&"quote \" and \\ \n\u{1}"),
+        br#""quote \" and \\ \n\u0001""#
+    );
+}
@@ -0,0 +1,306 @@
+use cron_schedule::Schedule;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+const HOUR: i64 = 3_600;
+
+/// Which sunday of the month a daylight saving transition happens on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sunday {
+    Nth(u32),
+    Last,
+}
+
+/// Moment of a daylight saving transition within a year
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub month: u32,
+    pub sunday: Sunday,
+    /// Seconds after midnight
+    pub at: i64,
+    /// If true `at` is in UTC, otherwise it's in the local standard time
+    pub utc: bool,
+}
+
+/// Daylight saving time rule, as it's currently observed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DstRule {
+    pub start: Transition,
+    pub end: Transition,
+    /// Shift applied to the standard offset while DST is in effect, in seconds
+    pub save: i64,
+}
+
+/// European Union: last sunday of March until last sunday of October, 01:00 UTC
+pub const DST_EU: DstRule = DstRule {
+    start: Transition {
+        month: 3,
+        sunday: Sunday::Last,
+        at: HOUR,
+        utc: true,
+    },
+    end: Transition {
+        month: 10,
+        sunday: Sunday::Last,
+        at: HOUR,
+        utc: true,
+    },
+    save: HOUR,
+};
+
+/// United States and Canada: second sunday of March until first sunday of November, 02:00 local time
+pub const DST_US: DstRule = DstRule {
+    start: Transition {
+        month: 3,
+        sunday: Sunday::Nth(2),
+        at: 2 * HOUR,
+        utc: false,
+    },
+    end: Transition {
+        month: 11,
+        sunday: Sunday::Nth(1),
+        at: HOUR,
+        utc: false,
+    },
+    save: HOUR,
+};
+
+/// South-eastern Australia: first sunday of October until first sunday of April, 02:00 standard time
+pub const DST_AU: DstRule = DstRule {
+    start: Transition {
+        month: 10,
+        sunday: Sunday::Nth(1),
+        at: 2 * HOUR,
+        utc: false,
+    },
+    end: Transition {
+        month: 4,
+        sunday: Sunday::Nth(1),
+        at: 2 * HOUR,
+        utc: false,
+    },
+    save: HOUR,
+};
+
+/// New Zealand: last sunday of September until first sunday of April, 02:00 standard time
+pub const DST_NZ: DstRule = DstRule {
+    start: Transition {
+        month: 9,
+        sunday: Sunday::Last,
+        at: 2 * HOUR,
+        utc: false,
+    },
+    end: Transition {
+        month: 4,
+        sunday: Sunday::Nth(1),
+        at: 2 * HOUR,
+        utc: false,
+    },
+    save: HOUR,
+};
+
+/// Time zone with a fixed standard offset and an optional daylight saving rule
+/// There is no tz database available inside of the contract,
+/// so only zones from the `TIMEZONES` table are supported
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeZone {
+    pub name: &'static str,
+    /// Standard offset from UTC, in seconds
+    pub offset: i64,
+    pub dst: Option<DstRule>,
+}
+
+const fn tz(name: &'static str, offset: i64, dst: Option<DstRule>) -> TimeZone {
+    TimeZone { name, offset, dst }
+}
+
+/// IANA time zones supported by `Interval::CronTz`
+pub const TIMEZONES: &[TimeZone] = &[
+    tz("UTC", 0, None),
+    tz("Etc/UTC", 0, None),
+    // Europe
+    tz("Europe/London", 0, Some(DST_EU)),
+    tz("Europe/Dublin", 0, Some(DST_EU)),
+    tz("Europe/Lisbon", 0, Some(DST_EU)),
+    tz("Europe/Amsterdam", HOUR, Some(DST_EU)),
+    tz("Europe/Berlin", HOUR, Some(DST_EU)),
+    tz("Europe/Brussels", HOUR, Some(DST_EU)),
+    tz("Europe/Copenhagen", HOUR, Some(DST_EU)),
+    tz("Europe/Madrid", HOUR, Some(DST_EU)),
+    tz("Europe/Oslo", HOUR, Some(DST_EU)),
+    tz("Europe/Paris", HOUR, Some(DST_EU)),
+    tz("Europe/Prague", HOUR, Some(DST_EU)),
+    tz("Europe/Rome", HOUR, Some(DST_EU)),
+    tz("Europe/Stockholm", HOUR, Some(DST_EU)),
+    tz("Europe/Vienna", HOUR, Some(DST_EU)),
+    tz("Europe/Warsaw", HOUR, Some(DST_EU)),
+    tz("Europe/Zurich", HOUR, Some(DST_EU)),
+    tz("Europe/Athens", 2 * HOUR, Some(DST_EU)),
+    tz("Europe/Bucharest", 2 * HOUR, Some(DST_EU)),
+    tz("Europe/Helsinki", 2 * HOUR, Some(DST_EU)),
+    tz("Europe/Kyiv", 2 * HOUR, Some(DST_EU)),
+    tz("Europe/Istanbul", 3 * HOUR, None),
+    tz("Europe/Moscow", 3 * HOUR, None),
+    // Africa
+    tz("Africa/Lagos", HOUR, None),
+    tz("Africa/Johannesburg", 2 * HOUR, None),
+    tz("Africa/Nairobi", 3 * HOUR, None),
+    // Americas
+    tz("America/Sao_Paulo", -3 * HOUR, None),
+    tz("America/Argentina/Buenos_Aires", -3 * HOUR, None),
+    tz("America/New_York", -5 * HOUR, Some(DST_US)),
+    tz("America/Toronto", -5 * HOUR, Some(DST_US)),
+    tz("America/Chicago", -6 * HOUR, Some(DST_US)),
+    tz("America/Mexico_City", -6 * HOUR, None),
+    tz("America/Denver", -7 * HOUR, Some(DST_US)),
+    tz("America/Phoenix", -7 * HOUR, None),
+    tz("America/Los_Angeles", -8 * HOUR, Some(DST_US)),
+    tz("America/Vancouver", -8 * HOUR, Some(DST_US)),
+    tz("America/Anchorage", -9 * HOUR, Some(DST_US)),
+    tz("Pacific/Honolulu", -10 * HOUR, None),
+    // Asia
+    tz("Asia/Dubai", 4 * HOUR, None),
+    tz("Asia/Kolkata", 5 * HOUR + 30 * 60, None),
+    tz("Asia/Kathmandu", 5 * HOUR + 45 * 60, None),
+    tz("Asia/Bangkok", 7 * HOUR, None),
+    tz("Asia/Jakarta", 7 * HOUR, None),
+    tz("Asia/Hong_Kong", 8 * HOUR, None),
+    tz("Asia/Manila", 8 * HOUR, None),
+    tz("Asia/Shanghai", 8 * HOUR, None),
+    tz("Asia/Singapore", 8 * HOUR, None),
+    tz("Asia/Taipei", 8 * HOUR, None),
+    tz("Asia/Seoul", 9 * HOUR, None),
+    tz("Asia/Tokyo", 9 * HOUR, None),
+    // Oceania
+    tz("Australia/Perth", 8 * HOUR, None),
+    tz("Australia/Adelaide", 9 * HOUR + 30 * 60, Some(DST_AU)),
+    tz("Australia/Brisbane", 10 * HOUR, None),
+    tz("Australia/Melbourne", 10 * HOUR, Some(DST_AU)),
+    tz("Australia/Sydney", 10 * HOUR, Some(DST_AU)),
+    tz("Pacific/Auckland", 12 * HOUR, Some(DST_NZ)),
+];
+
+impl TimeZone {
+    /// Find supported time zone by its IANA name
+    pub fn from_name(name: &str) -> Option<TimeZone> {
+        TIMEZONES.iter().find(|tz| tz.name == name).copied()
+    }
+
+    /// Offset from UTC at the given UTC timestamp, in seconds
+    pub fn offset_at(&self, utc_seconds: i64) -> i64 {
+        match self.dst {
+            Some(dst) if self.is_dst(&dst, utc_seconds) => self.offset + dst.save,
+            _ => self.offset,
+        }
+    }
+
+    fn is_dst(&self, dst: &DstRule, utc_seconds: i64) -> bool {
+        // Transitions never happen around new year, so UTC year is fine here
+        let year = year_from_days(utc_seconds.div_euclid(SECONDS_PER_DAY));
+        let start = self.transition_utc(&dst.start, year);
+        let end = self.transition_utc(&dst.end, year);
+        if start < end {
+            // Northern hemisphere
+            start <= utc_seconds && utc_seconds < end
+        } else {
+            // Southern hemisphere, DST goes over the new year
+            utc_seconds >= start || utc_seconds < end
+        }
+    }
+
+    fn transition_utc(&self, transition: &Transition, year: i64) -> i64 {
+        let day = sunday_of_month(year, transition.month, transition.sunday);
+        let local = day * SECONDS_PER_DAY + transition.at;
+        if transition.utc {
+            local
+        } else {
+            local - self.offset
+        }
+    }
+
+    /// Convert local wall clock time to UTC
+    /// Ambiguous times (when clocks go back) resolve to the earlier moment,
+    /// skipped times (when clocks go forward) are shifted forward
+    pub fn local_to_utc(&self, local_seconds: i64) -> i64 {
+        if let Some(dst) = self.dst {
+            let dst_offset = self.offset + dst.save;
+            let as_dst = local_seconds - dst_offset;
+            if self.offset_at(as_dst) == dst_offset {
+                return as_dst;
+            }
+        }
+        local_seconds - self.offset
+    }
+
+    /// Next time (in nanos) after the given UTC timestamp (in nanos)
+    /// this schedule fires in the local time of this zone
+    pub fn next_after(&self, schedule: &Schedule, utc_nanos: u64) -> Option<u64> {
+        let utc_seconds = (utc_nanos as i128 / NANOS_PER_SECOND) as i64;
+        let offset = self.offset_at(utc_seconds) as i128 * NANOS_PER_SECOND;
+        let mut local_nanos = u64::try_from(utc_nanos as i128 + offset).ok()?;
+        // Times repeated when clocks go back may resolve to the past, skip those
+        for _ in 0..3 {
+            let local_next = schedule.next_after(&local_nanos)?;
+            let local_next_seconds = (local_next as i128 / NANOS_PER_SECOND) as i64;
+            let subsec = local_next as i128 % NANOS_PER_SECOND;
+            let utc_next =
+                self.local_to_utc(local_next_seconds) as i128 * NANOS_PER_SECOND + subsec;
+            if utc_next > utc_nanos as i128 {
+                return u64::try_from(utc_next).ok();
+            }
+            local_nanos = local_next;
+        }
+        None
+    }
+}
+
+// Days since 1970-01-01 for the given date of the proleptic Gregorian calendar
+// REF: http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let (month, day) = (month as i64, day as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// REF: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn year_from_days(days: i64) -> i64 {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let year = yoe + era * 400;
+    if mp < 10 {
+        year
+    } else {
+        year + 1
+    }
+}
+
+// 0 is Sunday, 1970-01-01 was Thursday
+fn weekday(days: i64) -> i64 {
+    (days + 4).rem_euclid(7)
+}
+
+fn sunday_of_month(year: i64, month: u32, sunday: Sunday) -> i64 {
+    match sunday {
+        Sunday::Nth(n) => {
+            let first = days_from_civil(year, month, 1);
+            let first_sunday = first + (7 - weekday(first)) % 7;
+            first_sunday + 7 * (n.saturating_sub(1) as i64)
+        }
+        Sunday::Last => {
+            let last = if month == 12 {
+                days_from_civil(year + 1, 1, 1) - 1
+            } else {
+                days_from_civil(year, month + 1, 1) - 1
+            };
+            last - weekday(last)
+        }
+    }
+}
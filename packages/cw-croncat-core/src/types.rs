@@ -1,21 +1,25 @@
 use cosmwasm_std::{
-    Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Empty, Env, GovMsg, IbcMsg, OverflowError,
-    OverflowOperation::Sub, StakingMsg, StdError, SubMsg, SubMsgResult, Timestamp, Uint128, Uint64,
-    WasmMsg,
+    from_slice, to_vec, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, CustomMsg, Empty, Env, GovMsg,
+    IbcMsg, OverflowError, OverflowOperation::Sub, StdError, SubMsg, SubMsgResult, Timestamp,
+    Uint128, Uint64, WasmMsg,
 };
 use cron_schedule::Schedule;
-use cw20::{Cw20CoinVerified, Cw20ExecuteMsg};
-use cw_rules_core::types::CroncatQuery;
+use cw20::Cw20CoinVerified;
+use cw_rules_core::types::{query_leaves, CroncatQuery};
 use generic_query::PathToValue;
 use hex::ToHex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_cw_value::Value;
 use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
 use crate::{
+    actions::AllowanceUsage,
     error::CoreError,
-    msg::{TaskRequest, TaskResponse, TaskWithQueriesResponse},
+    msg::{GenericTaskRequest, GenericTaskResponse, TaskWithQueriesResponse},
+    timezone::TimeZone,
     traits::{BalancesOperations, FindAndMutate, Intervals, ResultFailed},
 };
 
@@ -84,6 +88,104 @@ pub enum Interval {
 
     /// Crontab Spec String
     Cron(String),
+
+    /// Crontab Spec String in the local time of an IANA time zone (see `timezone::TIMEZONES`)
+    CronTz { crontab: String, timezone: String },
+
+    /// For when this is a one-shot task, triggered at the exact timestamp
+    At(Timestamp),
+
+    /// Fixed period in seconds, anchored to the boundary start
+    Every { seconds: u64 },
+}
+
+/// Outcome of the parent task execution that triggers the dependent task
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerCondition {
+    Success,
+    Failure,
+    Any,
+}
+
+impl TriggerCondition {
+    pub fn is_met(&self, success: bool) -> bool {
+        match self {
+            TriggerCondition::Success => success,
+            TriggerCondition::Failure => !success,
+            TriggerCondition::Any => true,
+        }
+    }
+}
+
+/// Schedules the task into the next block once the parent task completes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Trigger {
+    /// Hash of the parent task, has to be owned by the same owner
+    pub task_hash: String,
+    pub condition: TriggerCondition,
+}
+
+/// Tops up the task deposit from the owner wallet balances when it runs low
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AutoRefill {
+    /// Refill once the deposit covers fewer executions than this
+    pub min_executions: u64,
+    /// Executions added to the deposit on every refill
+    pub refill_executions: u64,
+}
+
+impl AutoRefill {
+    pub fn is_valid(&self) -> bool {
+        self.min_executions != 0 && self.refill_executions != 0
+    }
+}
+
+/// Task owner allowed to run admin-only actions on the contract croncat is the admin of
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AdminAllowance {
+    pub contract_addr: Addr,
+    pub task_owner: Addr,
+}
+
+/// Token the agent fee of a task is paid in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeToken {
+    Native(String),
+    Cw20(Addr),
+}
+
+/// Fee token accepted instead of the native denom
+/// The fee in this token is the native fee multiplied by `numerator / denominator`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeTokenRate {
+    pub token: FeeToken,
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl FeeTokenRate {
+    pub fn is_valid(&self) -> bool {
+        self.numerator != 0 && self.denominator != 0
+    }
+
+    /// Convert the fee in the native denom into this token
+    pub fn fee(&self, native_price: u128) -> Result<GenericBalance, CoreError> {
+        let amount = native_price
+            .checked_mul(self.numerator as u128)
+            .and_then(|a| a.checked_div(self.denominator as u128))
+            .ok_or(CoreError::InvalidGas {})?;
+        let mut fee = GenericBalance::default();
+        match &self.token {
+            FeeToken::Native(denom) => fee.native.push(Coin::new(amount, denom)),
+            FeeToken::Cw20(address) => fee.cw20.push(Cw20CoinVerified {
+                address: address.clone(),
+                amount: amount.into(),
+            }),
+        }
+        Ok(fee)
+    }
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -110,28 +212,61 @@ impl CheckedBoundary {
         self.is_block_boundary.is_some() && self.is_block_boundary.unwrap()
     }
 
+    /// Boundary as it was requested, `None` if the task is unbounded
+    pub fn to_boundary(&self, interval: &Interval) -> Option<Boundary> {
+        match (self, interval) {
+            (
+                CheckedBoundary {
+                    start: None,
+                    end: None,
+                    is_block_boundary: None,
+                },
+                _,
+            ) => None,
+            (
+                CheckedBoundary { start, end, .. },
+                Interval::Cron(_)
+                | Interval::CronTz { .. }
+                | Interval::At(_)
+                | Interval::Every { .. },
+            ) => Some(Boundary::Time {
+                start: start.map(Timestamp::from_nanos),
+                end: end.map(Timestamp::from_nanos),
+            }),
+            (CheckedBoundary { start, end, .. }, _) => Some(Boundary::Height {
+                start: start.map(Into::into),
+                end: end.map(Into::into),
+            }),
+        }
+    }
+
     pub fn new(boundary: Option<Boundary>, interval: &Interval) -> Result<Self, CoreError> {
         if let Some(boundary) = boundary {
             match (interval, boundary) {
-                (Interval::Once | Interval::Cron(_), Boundary::Time { start, end }) => {
-                    match (start, end) {
-                        (Some(s), Some(e)) => {
-                            if s.nanos() >= e.nanos() {
-                                return Err(CoreError::InvalidBoundary {});
-                            }
-                            Ok(Self {
-                                start: Some(s.nanos()),
-                                end: Some(e.nanos()),
-                                is_block_boundary: Some(false),
-                            })
+                (
+                    Interval::Once
+                    | Interval::Cron(_)
+                    | Interval::CronTz { .. }
+                    | Interval::At(_)
+                    | Interval::Every { .. },
+                    Boundary::Time { start, end },
+                ) => match (start, end) {
+                    (Some(s), Some(e)) => {
+                        if s.nanos() >= e.nanos() {
+                            return Err(CoreError::InvalidBoundary {});
                         }
-                        _ => Ok(Self {
-                            start: start.map(|start| start.nanos()),
-                            end: end.map(|end| end.nanos()),
+                        Ok(Self {
+                            start: Some(s.nanos()),
+                            end: Some(e.nanos()),
                             is_block_boundary: Some(false),
-                        }),
+                        })
                     }
-                }
+                    _ => Ok(Self {
+                        start: start.map(|start| start.nanos()),
+                        end: end.map(|end| end.nanos()),
+                        is_block_boundary: Some(false),
+                    }),
+                },
                 (
                     Interval::Once | Interval::Immediate | Interval::Block(_),
                     Boundary::Height { start, end },
@@ -158,7 +293,13 @@ impl CheckedBoundary {
             Ok(Self {
                 start: None,
                 end: None,
-                is_block_boundary: Some(!matches!(interval, Interval::Cron(_))), //Boundary isnt provided, so default is block
+                is_block_boundary: Some(!matches!(
+                    interval,
+                    Interval::Cron(_)
+                        | Interval::CronTz { .. }
+                        | Interval::At(_)
+                        | Interval::Every { .. }
+                )), //Boundary isnt provided, so default is block
             })
         }
     }
@@ -194,45 +335,24 @@ pub struct Action<T = Empty> {
     pub gas_limit: Option<u64>,
 }
 
-impl Action {
-    // Checking how much native coins sent in this action
-    pub fn bank_sent(&self) -> Option<&[Coin]> {
-        if let CosmosMsg::Bank(BankMsg::Send { amount, .. }) = &self.msg {
-            Some(amount)
-        } else {
-            None
-        }
-    }
-
-    // Checking how much cw20 coins sent in this action
-    pub fn cw20_sent(&self, api: &dyn Api) -> Option<Cw20CoinVerified> {
-        if let CosmosMsg::Wasm(WasmMsg::Execute {
-            msg, contract_addr, ..
-        }) = &self.msg
-        {
-            if let Ok(cw20_msg) = cosmwasm_std::from_binary(msg) {
-                return match cw20_msg {
-                    Cw20ExecuteMsg::Send { amount, .. } => Some(Cw20CoinVerified {
-                        // unwraping safe here because we checked it at `is_valid_msg_calculate_usage`
-                        address: api.addr_validate(contract_addr).unwrap(),
-                        amount,
-                    }),
-                    Cw20ExecuteMsg::Transfer { amount, .. } => Some(Cw20CoinVerified {
-                        address: api.addr_validate(contract_addr).unwrap(),
-                        amount,
-                    }),
-                    _ => None,
-                };
-            }
-        }
-        None
-    }
-}
-
 /// The response required by all rule queries. Bool is needed for croncat, T allows flexible rule engine
 pub type RuleResponse<T> = (bool, T);
 
-impl TaskRequest {
+impl<T: CustomMsg> GenericTaskRequest<T> {
+    /// Get the hash this task will have once created by the owner
+    pub fn to_hash(&self, owner_id: &Addr, prefix: &str) -> Result<String, CoreError> {
+        let boundary = CheckedBoundary::new(self.boundary, &self.interval)?;
+        Ok(task_hash(
+            prefix,
+            owner_id,
+            &self.interval,
+            &boundary,
+            &self.actions,
+            &self.queries,
+            &self.transforms,
+        ))
+    }
+
     /// Validate the task actions only use the supported messages
     /// We're iterating over all actions
     /// so it's a great place for calculaing balance usages
@@ -261,11 +381,7 @@ impl TaskRequest {
                 .checked_add(action.gas_limit.unwrap_or(action_gas))
                 .ok_or(CoreError::InvalidWasmMsg {})?;
             match &action.msg {
-                CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr,
-                    funds: _,
-                    msg,
-                }) => {
+                CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
                     // TODO: Is there any way sender can be "self" creating a malicious task?
                     // cannot be THIS contract id, unless predecessor is owner of THIS contract
                     if contract_addr == self_addr && sender != owner_id {
@@ -274,71 +390,60 @@ impl TaskRequest {
                     if action.gas_limit.is_none() {
                         return Err(CoreError::NoGasLimit {});
                     }
-                    if let Ok(cw20_msg) = cosmwasm_std::from_binary(msg) {
-                        match cw20_msg {
-                            Cw20ExecuteMsg::Send { amount, .. } if !amount.is_zero() => {
-                                amount_for_one_task
-                                    .cw20
-                                    .find_checked_add(&Cw20CoinVerified {
-                                        address: api.addr_validate(contract_addr)?,
-                                        amount,
-                                    })?
-                            }
-                            Cw20ExecuteMsg::Transfer { amount, .. } if !amount.is_zero() => {
-                                amount_for_one_task
-                                    .cw20
-                                    .find_checked_add(&Cw20CoinVerified {
-                                        address: api.addr_validate(contract_addr)?,
-                                        amount,
-                                    })?
-                            }
-                            _ => {
-                                return Err(CoreError::InvalidAction {});
-                            }
-                        }
-                    }
                 }
-                CosmosMsg::Staking(StakingMsg::Delegate {
-                    validator: _,
-                    amount,
-                }) => {
-                    // Must attach enough balance for staking
-                    if amount.amount.is_zero() {
-                        return Err(CoreError::InvalidAction {});
+                CosmosMsg::Wasm(WasmMsg::Instantiate { .. }) => {
+                    if action.gas_limit.is_none() {
+                        return Err(CoreError::NoGasLimit {});
                     }
-                    amount_for_one_task.native.find_checked_add(amount)?;
                 }
-                // TODO: Allow send, as long as coverage of assets is correctly handled
-                CosmosMsg::Bank(BankMsg::Send {
-                    to_address: _,
-                    amount,
-                }) => {
-                    // Restrict bank msg for time being, so contract doesnt get drained, however could allow an escrow type setup
-                    // Do something silly to keep it simple. Ensure they only sent one kind of native token and it's testnet Juno
-                    // Remember total_deposit is set in tasks.rs when a task is created, and assigned to info.funds
-                    // which is however much was passed in, like 1000000ujunox below:
-                    // junod tx wasm execute … … --amount 1000000ujunox
-                    if amount.iter().any(|coin| coin.amount.is_zero()) {
+                // Allowed contracts are checked against the contract config
+                CosmosMsg::Wasm(
+                    WasmMsg::Migrate { contract_addr, .. }
+                    | WasmMsg::UpdateAdmin { contract_addr, .. }
+                    | WasmMsg::ClearAdmin { contract_addr },
+                ) => {
+                    if contract_addr == self_addr {
                         return Err(CoreError::InvalidAction {});
                     }
-                    amount_for_one_task.checked_add_native(amount)?;
+                    if action.gas_limit.is_none() {
+                        return Err(CoreError::NoGasLimit {});
+                    }
                 }
+                CosmosMsg::Bank(BankMsg::Send { .. }) => (),
                 CosmosMsg::Bank(_) => {
                     // Restrict bank msg for time being, so contract doesnt get drained, however could allow an escrow type setup
                     return Err(CoreError::InvalidAction {});
                 }
-                CosmosMsg::Gov(GovMsg::Vote { .. }) => {
-                    // Restrict bank msg for time being, so contract doesnt get drained, however could allow an escrow type setup
-                    return Err(CoreError::InvalidAction {});
-                }
-                // TODO: Setup better support for IBC
-                CosmosMsg::Ibc(IbcMsg::Transfer { .. }) => {
-                    // Restrict bank msg for time being, so contract doesnt get drained, however could allow an escrow type setup
-                    return Err(CoreError::InvalidAction {});
+                // Allowed type urls are checked against the contract config
+                // Without gas_limit it's paid as a regular action
+                CosmosMsg::Stargate { type_url, .. } => {
+                    if !is_valid_stargate_type_url(type_url) {
+                        return Err(CoreError::InvalidAction {});
+                    }
                 }
+                // Votes don't move funds, only the gas is paid
+                // Deployment can disable them, see `Config::gov_msgs_enabled`
+                // NOTE: weighted votes aren't supported, `GovMsg::VoteWeighted` needs
+                // cosmwasm-std 1.2 with `cosmwasm_1_2` while Cargo.lock resolves 1.1.5
+                CosmosMsg::Gov(GovMsg::Vote { .. }) => (),
+                // Chain specific messages, only for the contracts embedding `CwCroncat`
+                // with the custom message type of their chain, which decides what they can do
+                #[cfg(not(feature = "custom-msg"))]
+                CosmosMsg::Custom(_) => return Err(CoreError::InvalidAction {}),
                 // TODO: Check authZ messages
                 _ => (),
             }
+            // Funds are reserved for every execution of the task
+            let usage = action.usage(api)?;
+            amount_for_one_task.checked_add_generic(&usage.deposit)?;
+            // Only the task owner can pull the tokens approved to croncat
+            for allowance in usage.allowances {
+                if let AllowanceUsage::Cw20 { owner, .. } = allowance {
+                    if owner != *sender {
+                        return Err(CoreError::InvalidAction {});
+                    }
+                }
+            }
         }
 
         if let Some(queries) = self.queries.as_ref() {
@@ -346,9 +451,9 @@ impl TaskRequest {
             gas_amount = gas_amount
                 .checked_add(wasm_query_gas)
                 .ok_or(CoreError::InvalidWasmMsg {})?;
-            for query in queries.iter() {
+            for query in query_leaves(queries) {
                 match query {
-                    CroncatQuery::HasBalanceGte(_) => {
+                    CroncatQuery::HasBalanceGte(_) | CroncatQuery::RawQuery { .. } => {
                         gas_amount = gas_amount
                             .checked_add(query_gas)
                             .ok_or(CoreError::InvalidWasmMsg {})?;
@@ -361,12 +466,57 @@ impl TaskRequest {
                 }
             }
         }
+        if self.callback_addr.is_some() {
+            gas_amount = gas_amount
+                .checked_add(TASK_CALLBACK_GAS_LIMIT)
+                .ok_or(CoreError::InvalidWasmMsg {})?;
+        }
         Ok((amount_for_one_task, gas_amount))
     }
 }
 
+/// Gas limit of the `CroncatTaskCallback` message, paid from the task deposit
+pub const TASK_CALLBACK_GAS_LIMIT: u64 = 150_000;
+
+/// Stargate messages are signed by croncat, so any task owner could use the
+/// authz grants given to croncat, including the grants of other task owners
+pub const AUTHZ_TYPE_URL_PREFIX: &str = "/cosmos.authz.";
+
+/// Whether the type url can be allowlisted for the stargate actions
+pub fn is_valid_stargate_type_url(type_url: &str) -> bool {
+    !type_url.is_empty() && !type_url.starts_with(AUTHZ_TYPE_URL_PREFIX)
+}
+
+/// Action of the task execution that failed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ActionFailure {
+    pub action_idx: u64,
+    pub error: String,
+}
+
+/// Single execution of a task, kept in the task history
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExecutionRecord {
+    /// Number of the execution, starting from 1
+    pub execution: u64,
+    pub block_height: u64,
+    pub time: Timestamp,
+    pub agent: Addr,
+    /// Agent fee taken from the task deposit
+    pub fee: GenericBalance,
+    /// Amount of actions executed, including the failed ones
+    pub executed_actions: u64,
+    pub failures: Vec<ActionFailure>,
+    /// Error of the first failed action
+    pub failure: Option<String>,
+}
+
+pub type Task = GenericTask<Empty>;
+
+// Task with `T` as the `CosmosMsg::Custom` type of its actions
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Task {
+#[schemars(rename = "Task")]
+pub struct GenericTask<T> {
     /// Entity responsible for this task, can change task details
     pub owner_id: Addr,
 
@@ -383,7 +533,7 @@ pub struct Task {
     pub amount_for_one_task: GenericBalance,
 
     /// The cosmos message to call, if time or rules are met
-    pub actions: Vec<Action>,
+    pub actions: Vec<Action<T>>,
     /// A prioritized list of messages that can be chained decision matrix
     /// required to complete before task action
     /// Rules MUST return the ResolverResponse type
@@ -391,32 +541,56 @@ pub struct Task {
     pub transforms: Option<Vec<Transform>>,
     // TODO: funds! should we support funds being attached?
     pub version: String,
+
+    /// Task ends after this amount of executions, unlimited if not set
+    pub max_executions: Option<u64>,
+    /// Amount of times this task was executed
+    #[serde(default)]
+    pub executions: u64,
+    /// Amount of executions with a failed action
+    #[serde(default)]
+    pub failed_executions: u64,
+    /// Hash the task was created with, kept once the task gets updated
+    #[serde(default)]
+    pub task_hash: Option<String>,
+    /// Set by `PauseTask`, the task is out of the schedule until `ResumeTask`
+    /// and keeps its deposit and hash meanwhile
+    #[serde(default)]
+    pub paused: bool,
+    /// Task is only scheduled on completion of the parent task
+    #[serde(default)]
+    pub trigger: Option<Trigger>,
+    /// Contract notified with `CroncatTaskCallback` after every execution,
+    /// failed callback doesn't fail the execution
+    #[serde(default)]
+    pub callback_addr: Option<Addr>,
+    /// Token the agent fee is paid in, native denom if not set
+    #[serde(default)]
+    pub fee_token: Option<FeeToken>,
+    /// Highest gas price numerator the task pays
+    #[serde(default)]
+    pub max_gas_price: Option<u64>,
+    /// Policy to top up the deposit from the owner wallet balances
+    #[serde(default)]
+    pub auto_refill: Option<AutoRefill>,
 }
 
-impl Task {
+impl<T: CustomMsg> GenericTask<T> {
     /// Get the hash of a task based on parameters
+    /// See `task_hash` for the encoding details, updated tasks keep their original hash
     pub fn to_hash(&self, prefix: &str) -> String {
-        let message = format!(
-            "{:?}{:?}{:?}{:?}{:?}{:?}",
-            self.owner_id,
-            self.interval,
-            self.boundary,
-            self.actions,
-            self.queries,
-            self.transforms
-        );
-
-        let hash = Sha256::digest(message.as_bytes());
-        let encoded: String = hash.encode_hex();
-
-        // Return prefixed hash, since multi-chain tasks require simpler identification
-        // Using the specified native_denom, if none, no prefix
-        // Example:
-        // No prefix:   fca49b82eb84818215768293c9e57e7d4194a7c862538e1dedb4516bf2dff0ca (No longer used/stored)
-        // with prefix: stars:82eb84818215768293c9e57e7d4194a7c862538e1dedb4516bf2dff0ca
-        // with prefix: longnetwork:818215768293c9e57e7d4194a7c862538e1dedb4516bf2dff0ca
-        let (_, l) = encoded.split_at(prefix.len() + 1);
-        format!("{}:{}", prefix, l)
+        if let Some(hash) = &self.task_hash {
+            return hash.clone();
+        }
+        task_hash(
+            prefix,
+            &self.owner_id,
+            &self.interval,
+            &self.boundary,
+            &self.actions,
+            &self.queries,
+            &self.transforms,
+        )
     }
     /// Get the hash of a task based on parameters
     pub fn to_hash_vec(&self, prefix: &str) -> Vec<u8> {
@@ -479,6 +653,53 @@ impl Task {
         Ok(())
     }
 
+    /// Amount of executions the task deposit still covers
+    pub fn covered_executions(&self) -> u128 {
+        let native = self.amount_for_one_task.native.iter().map(|coin| {
+            let balance = self
+                .total_deposit
+                .native
+                .iter()
+                .find(|balance| balance.denom == coin.denom)
+                .map_or(Uint128::zero(), |balance| balance.amount);
+            (balance, coin.amount)
+        });
+        let cw20 = self.amount_for_one_task.cw20.iter().map(|coin| {
+            let balance = self
+                .total_deposit
+                .cw20
+                .iter()
+                .find(|balance| balance.address == coin.address)
+                .map_or(Uint128::zero(), |balance| balance.amount);
+            (balance, coin.amount)
+        });
+        native
+            .chain(cw20)
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(balance, amount)| (balance / amount).u128())
+            .min()
+            .unwrap_or(u128::MAX)
+    }
+
+    /// Deposit the task needs for this amount of executions
+    pub fn amount_for_executions(&self, executions: u64) -> Result<GenericBalance, CoreError> {
+        let executions = Uint128::from(executions);
+        let mut amount = self.amount_for_one_task.clone();
+        for coin in amount.native.iter_mut() {
+            coin.amount = coin
+                .amount
+                .checked_mul(executions)
+                .map_err(StdError::from)?;
+        }
+        for coin in amount.cw20.iter_mut() {
+            coin.amount = coin
+                .amount
+                .checked_mul(executions)
+                .map_err(StdError::from)?;
+        }
+        Ok(amount)
+    }
+
     /// Get task gas total
     /// helper for getting total configured gas for this tasks actions
     pub fn get_submsgs_with_total_gas(
@@ -488,14 +709,14 @@ impl Task {
         query_gas: u64,
         wasm_query_gas: u64,
         next_idx: u64,
-    ) -> Result<(Vec<SubMsg<Empty>>, u64), CoreError> {
+    ) -> Result<(Vec<SubMsg<T>>, u64), CoreError> {
         let mut gas: u64 = base_gas;
         let mut sub_msgs = Vec::with_capacity(self.actions.len());
         for action in self.actions.iter() {
             gas = gas
                 .checked_add(action.gas_limit.unwrap_or(action_gas))
                 .ok_or(CoreError::InvalidGas {})?;
-            let sub_msg: SubMsg = SubMsg::reply_always(action.msg.clone(), next_idx);
+            let sub_msg = SubMsg::reply_always(action.msg.clone(), next_idx);
             if let Some(gas_limit) = action.gas_limit {
                 sub_msgs.push(sub_msg.with_gas_limit(gas_limit));
             } else {
@@ -508,9 +729,9 @@ impl Task {
             gas = gas
                 .checked_add(wasm_query_gas)
                 .ok_or(CoreError::InvalidGas {})?;
-            for query in queries.iter() {
+            for query in query_leaves(queries) {
                 match query {
-                    CroncatQuery::HasBalanceGte(_) => {
+                    CroncatQuery::HasBalanceGte(_) | CroncatQuery::RawQuery { .. } => {
                         gas = gas.checked_add(query_gas).ok_or(CoreError::InvalidGas {})?;
                     }
                     _ => {
@@ -521,6 +742,11 @@ impl Task {
                 }
             }
         }
+        if self.callback_addr.is_some() {
+            gas = gas
+                .checked_add(TASK_CALLBACK_GAS_LIMIT)
+                .ok_or(CoreError::InvalidGas {})?;
+        }
         Ok((sub_msgs, gas))
     }
 
@@ -551,6 +777,32 @@ impl Task {
             .map_or(false, |queries| !queries.is_empty())
     }
 
+    /// Addresses the actions of the task call or send funds to, without duplicates
+    pub fn targets(&self) -> Vec<Addr> {
+        let targets: BTreeSet<&str> = self
+            .actions
+            .iter()
+            .filter_map(|action| match &action.msg {
+                CosmosMsg::Wasm(
+                    WasmMsg::Execute { contract_addr, .. }
+                    | WasmMsg::Migrate { contract_addr, .. }
+                    | WasmMsg::UpdateAdmin { contract_addr, .. }
+                    | WasmMsg::ClearAdmin { contract_addr },
+                ) => Some(contract_addr.as_str()),
+                CosmosMsg::Bank(BankMsg::Send { to_address, .. })
+                | CosmosMsg::Ibc(IbcMsg::Transfer { to_address, .. }) => Some(to_address.as_str()),
+                _ => None,
+            })
+            .collect();
+        targets.into_iter().map(Addr::unchecked).collect()
+    }
+
+    /// Executions left before the task ends, None if unlimited
+    pub fn remaining_executions(&self) -> Option<u64> {
+        self.max_executions
+            .map(|max| max.saturating_sub(self.executions))
+    }
+
     /// Check if given Addr is the owner
     pub fn is_owner(&self, addr: Addr) -> bool {
         self.owner_id == addr
@@ -566,6 +818,7 @@ impl Task {
         construct_res_data: Vec<cosmwasm_std::Binary>,
     ) -> Result<(), CoreError> {
         if let Some(ref transforms) = self.transforms {
+            let nfts_before = self.cw721_usage(api);
             for transform in transforms {
                 let wasm_msg = self
                     .actions
@@ -601,6 +854,12 @@ impl Task {
                         .map_err(|e| CoreError::Std(StdError::generic_err(e.to_string())))?,
                 );
             }
+            // NFTs were checked on task creation, they can't be replaced
+            if self.cw721_usage(api) != nfts_before {
+                return Err(CoreError::TaskNoLongerValid {
+                    task_hash: task_hash.to_owned(),
+                });
+            }
             let cw20_amount_recalculated =
                 self.recalculate_cw20_usage(api, cron_addr, task_hash)?;
             self.amount_for_one_task.cw20 = cw20_amount_recalculated;
@@ -619,35 +878,23 @@ impl Task {
         cron_addr: &Addr,
         task_hash: &str,
     ) -> Result<Vec<Cw20CoinVerified>, CoreError> {
-        let actions = self.actions.iter();
+        let invalid = || CoreError::TaskNoLongerValid {
+            task_hash: task_hash.to_owned(),
+        };
         let mut cw20_coins = vec![];
-        for action in actions {
-            if let CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr, msg, ..
-            }) = &action.msg
-            {
+        for action in self.actions.iter() {
+            if let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) = &action.msg {
                 if cron_addr.as_str().eq(contract_addr) {
-                    return Err(CoreError::TaskNoLongerValid {
-                        task_hash: task_hash.to_owned(),
-                    });
+                    return Err(invalid());
                 }
-                if let Ok(cw20_msg) = cosmwasm_std::from_binary(msg) {
-                    match cw20_msg {
-                        Cw20ExecuteMsg::Send { amount, .. } if !amount.is_zero() => cw20_coins
-                            .find_checked_add(&Cw20CoinVerified {
-                                address: api.addr_validate(contract_addr)?,
-                                amount,
-                            })?,
-                        Cw20ExecuteMsg::Transfer { amount, .. } if !amount.is_zero() => cw20_coins
-                            .find_checked_add(&Cw20CoinVerified {
-                                address: api.addr_validate(contract_addr)?,
-                                amount,
-                            })?,
-                        _ => {
-                            return Err(CoreError::TaskNoLongerValid {
-                                task_hash: task_hash.to_owned(),
-                            });
-                        }
+            }
+            let usage = action.usage(api).map_err(|_| invalid())?;
+            cw20_coins.checked_add_coins(&usage.deposit.cw20)?;
+            // Replaced values can't redirect the allowance of someone else
+            for allowance in usage.allowances {
+                if let AllowanceUsage::Cw20 { owner, .. } = allowance {
+                    if owner != self.owner_id {
+                        return Err(invalid());
                     }
                 }
             }
@@ -655,40 +902,19 @@ impl Task {
         Ok(cw20_coins)
     }
 
-    pub fn into_response(self, prefix: &str) -> TaskResponse {
-        let boundary = match (self.boundary, self.interval.clone()) {
-            (
-                CheckedBoundary {
-                    start: None,
-                    end: None,
-                    is_block_boundary: None,
-                },
-                _,
-            ) => None,
-            (
-                CheckedBoundary {
-                    start,
-                    end,
-                    is_block_boundary: _,
-                },
-                Interval::Cron(_),
-            ) => Some(Boundary::Time {
-                start: start.map(Timestamp::from_nanos),
-                end: end.map(Timestamp::from_nanos),
-            }),
-            (
-                CheckedBoundary {
-                    start,
-                    end,
-                    is_block_boundary: _,
-                },
-                _,
-            ) => Some(Boundary::Height {
-                start: start.map(Into::into),
-                end: end.map(Into::into),
-            }),
-        };
-        TaskResponse {
+    /// NFTs the task actions move
+    pub fn cw721_usage(&self, api: &dyn Api) -> Vec<AllowanceUsage> {
+        self.actions
+            .iter()
+            .filter_map(|action| action.usage(api).ok())
+            .flat_map(|usage| usage.allowances)
+            .filter(|allowance| matches!(allowance, AllowanceUsage::Cw721 { .. }))
+            .collect()
+    }
+
+    pub fn into_response(self, prefix: &str) -> GenericTaskResponse<T> {
+        let boundary = self.boundary.to_boundary(&self.interval);
+        GenericTaskResponse {
             task_hash: self.to_hash(prefix),
             owner_id: self.owner_id.clone(),
             interval: self.interval.clone(),
@@ -699,50 +925,164 @@ impl Task {
             amount_for_one_task_native: self.amount_for_one_task.native.clone(),
             amount_for_one_task_cw20: self.amount_for_one_task.cw20.clone(),
             actions: self.actions.clone(),
+            remaining_executions: self.remaining_executions(),
+            executions: self.executions,
+            failed_executions: self.failed_executions,
+            paused: self.paused,
+            trigger: self.trigger.clone(),
+            callback_addr: self.callback_addr.clone(),
+            fee_token: self.fee_token.clone(),
+            max_gas_price: self.max_gas_price,
+            auto_refill: self.auto_refill.clone(),
             queries: self.queries.clone(),
             transforms: self.transforms,
         }
     }
 
     pub fn into_response_with_queries(&self, prefix: &str) -> TaskWithQueriesResponse {
-        let boundary = match (self.boundary, &self.interval) {
-            (
-                CheckedBoundary {
-                    start: None,
-                    end: None,
-                    is_block_boundary: None,
-                },
-                _,
-            ) => None,
-            (
-                CheckedBoundary {
-                    start,
-                    end,
-                    is_block_boundary: _,
-                },
-                Interval::Cron(_),
-            ) => Some(Boundary::Time {
-                start: start.map(Timestamp::from_nanos),
-                end: end.map(Timestamp::from_nanos),
-            }),
-            (
-                CheckedBoundary {
-                    start,
-                    end,
-                    is_block_boundary: _,
-                },
-                _,
-            ) => Some(Boundary::Height {
-                start: start.map(Into::into),
-                end: end.map(Into::into),
-            }),
-        };
+        let boundary = self.boundary.to_boundary(&self.interval);
         TaskWithQueriesResponse {
             task_hash: self.to_hash(prefix),
             interval: self.interval.clone(),
             boundary,
             queries: self.queries.clone(),
+            paused: self.paused,
+        }
+    }
+}
+
+/// Tag of the current task hash encoding, changes whenever the encoding changes
+pub const TASK_HASH_VERSION: &str = "croncat:task:v1";
+
+/// Canonical task hash, prefixed with the chain name
+///
+/// The hash is a sha256 of the following fields, each of them prefixed by its length (u32, big-endian):
+/// 1. `TASK_HASH_VERSION` tag
+/// 2. Owner address
+/// 3. Interval
+/// 4. Boundary start, empty if unset
+/// 5. Boundary end, empty if unset
+/// 6. Boundary kind, `0x01` for block heights, `0x00` for timestamps, empty if unbounded
+/// 7. Number of actions (u32), followed by two fields for every action:
+///    the message and the gas limit, empty if unset
+/// 8. Number of queries (u32), empty if there are no queries,
+///    followed by a field for every query
+/// 9. Number of transforms (u32), empty if there are no transforms,
+///    followed by four fields for every transform:
+///    `action_idx`, `query_idx`, `action_path` and `query_response_path`
+///
+/// Numbers are u64, big-endian, unless noted otherwise. The owner is the UTF-8 address.
+/// Every other value is the canonical JSON of its message form, see `canonical_json`.
+/// Hex of the hash is then truncated by the prefix length so hashes have a fixed length
+pub fn task_hash<T: Serialize>(
+    prefix: &str,
+    owner_id: &Addr,
+    interval: &Interval,
+    boundary: &CheckedBoundary,
+    actions: &[Action<T>],
+    queries: &Option<Vec<CroncatQuery>>,
+    transforms: &Option<Vec<Transform>>,
+) -> String {
+    let mut hasher = Sha256::new();
+    let mut write_field = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u32).to_be_bytes());
+        hasher.update(bytes);
+    };
+    let optional = |value: Option<u64>| value.map(|n| n.to_be_bytes().to_vec()).unwrap_or_default();
+    write_field(TASK_HASH_VERSION.as_bytes());
+    write_field(owner_id.as_bytes());
+    write_field(&canonical_json(interval));
+    write_field(&optional(boundary.start));
+    write_field(&optional(boundary.end));
+    write_field(
+        &boundary
+            .is_block_boundary
+            .map(|b| vec![b as u8])
+            .unwrap_or_default(),
+    );
+    write_field(&(actions.len() as u32).to_be_bytes());
+    for action in actions {
+        write_field(&canonical_json(&action.msg));
+        write_field(&optional(action.gas_limit));
+    }
+    match queries {
+        Some(queries) => {
+            write_field(&(queries.len() as u32).to_be_bytes());
+            for query in queries {
+                write_field(&canonical_json(query));
+            }
+        }
+        None => write_field(&[]),
+    }
+    match transforms {
+        Some(transforms) => {
+            write_field(&(transforms.len() as u32).to_be_bytes());
+            for transform in transforms {
+                write_field(&transform.action_idx.to_be_bytes());
+                write_field(&transform.query_idx.to_be_bytes());
+                write_field(&canonical_json(&transform.action_path));
+                write_field(&canonical_json(&transform.query_response_path));
+            }
+        }
+        None => write_field(&[]),
+    }
+    let encoded: String = hasher.finalize().encode_hex();
+
+    // Return prefixed hash, since multi-chain tasks require simpler identification
+    // Using the specified native_denom, if none, no prefix
+    // Example:
+    // No prefix:   fca49b82eb84818215768293c9e57e7d4194a7c862538e1dedb4516bf2dff0ca (No longer used/stored)
+    // with prefix: stars:82eb84818215768293c9e57e7d4194a7c862538e1dedb4516bf2dff0ca
+    // with prefix: longnetwork:818215768293c9e57e7d4194a7c862538e1dedb4516bf2dff0ca
+    let (_, l) = encoded.split_at(prefix.len() + 1);
+    format!("{}:{}", prefix, l)
+}
+
+/// Canonical JSON of the value, in the form it takes in the contract messages:
+/// - no whitespace
+/// - object keys sorted by their UTF-8 bytes, keys with `null` values left out
+/// - integers as plain decimal numbers, `Uint128` and other string encoded numbers stay strings
+/// - strings escape only `"`, `\` and the control characters,
+///   as `\b`, `\t`, `\n`, `\f`, `\r` or upper-case `\u00XX`
+pub fn canonical_json<T: Serialize>(value: &T) -> Vec<u8> {
+    // Serializing plain data types into JSON can't fail, neither parsing it back
+    let value: Value = from_slice(&to_vec(value).unwrap()).unwrap();
+    let mut json = vec![];
+    write_canonical_json(&value, &mut json);
+    json
+}
+
+fn write_canonical_json(value: &Value, json: &mut Vec<u8>) {
+    match value {
+        Value::Map(map) => {
+            json.push(b'{');
+            let fields = map
+                .iter()
+                .filter(|(_, value)| !matches!(value, Value::Unit | Value::Option(None)));
+            for (i, (key, value)) in fields.enumerate() {
+                if i > 0 {
+                    json.push(b',');
+                }
+                write_canonical_json(key, json);
+                json.push(b':');
+                write_canonical_json(value, json);
+            }
+            json.push(b'}');
         }
+        Value::Seq(items) => {
+            json.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    json.push(b',');
+                }
+                write_canonical_json(item, json);
+            }
+            json.push(b']');
+        }
+        Value::Option(Some(value)) | Value::Newtype(value) => write_canonical_json(value, json),
+        Value::Unit | Value::Option(None) => json.extend_from_slice(b"null"),
+        // Strings, numbers and booleans
+        value => json.extend(to_vec(value).unwrap()),
     }
 }
 
@@ -871,6 +1211,11 @@ impl GenericBalance {
         self.cw20.checked_sub_coins(sub)
     }
 
+    pub fn checked_add_generic(&mut self, add: &GenericBalance) -> Result<(), CoreError> {
+        self.checked_add_native(&add.native)?;
+        self.checked_add_cw20(&add.cw20)
+    }
+
     pub fn checked_sub_generic(&mut self, sub: &GenericBalance) -> Result<(), CoreError> {
         self.checked_sub_native(&sub.native)?;
         self.checked_sub_cw20(&sub.cw20)
@@ -966,11 +1311,10 @@ fn get_next_cron_time(
     env: &Env,
     boundary: CheckedBoundary,
     crontab: &str,
+    timezone: Option<&TimeZone>,
     slot_granularity_time: u64,
 ) -> (u64, SlotType) {
     let current_block_ts = env.block.time.nanos();
-    let current_block_slot =
-        current_block_ts.saturating_sub(current_block_ts % slot_granularity_time);
 
     // get earliest possible time
     let current_ts = match boundary.start {
@@ -979,13 +1323,56 @@ fn get_next_cron_time(
     };
 
     // receive time from schedule, calculate slot for this time
-    let schedule = Schedule::from_str(crontab).unwrap();
-    let next_ts = schedule.next_after(&current_ts).unwrap();
+    // Schedule without future occurrences (e.g. a past year) has ended
+    let next_ts = Schedule::from_str(crontab)
+        .ok()
+        .and_then(|schedule| match timezone {
+            Some(tz) => tz.next_after(&schedule, current_ts),
+            None => schedule.next_after(&current_ts),
+        });
+    match next_ts {
+        Some(next_ts) => get_next_time_slot(env, boundary, next_ts, slot_granularity_time),
+        None => (0, SlotType::Cron),
+    }
+}
+
+// Get the next timestamp (in nanos) of the fixed period, counting from the boundary start
+// Without a start boundary periods are counted from the unix epoch
+fn get_next_period_time(
+    env: &Env,
+    boundary: CheckedBoundary,
+    seconds: u64,
+    slot_granularity_time: u64,
+) -> (u64, SlotType) {
+    let current_block_ts = env.block.time.nanos();
+    let anchor = boundary.start.unwrap_or_default();
+    let period = seconds.saturating_mul(1_000_000_000);
+
+    let next_ts = if current_block_ts < anchor {
+        anchor
+    } else {
+        let elapsed_periods = (current_block_ts - anchor) / period;
+        anchor.saturating_add(period.saturating_mul(elapsed_periods + 1))
+    };
+    get_next_time_slot(env, boundary, next_ts, slot_granularity_time)
+}
+
+// Get the slot number (in nanos) for the given timestamp according to boundaries
+// Unless current slot is the end slot, don't put in the current slot
+fn get_next_time_slot(
+    env: &Env,
+    boundary: CheckedBoundary,
+    next_ts: u64,
+    slot_granularity_time: u64,
+) -> (u64, SlotType) {
+    let current_block_ts = env.block.time.nanos();
+    let current_block_slot =
+        current_block_ts.saturating_sub(current_block_ts % slot_granularity_time);
     let next_ts_slot = next_ts.saturating_sub(next_ts % slot_granularity_time);
 
-    // put task in the next slot if next_ts_slot in the current slot
-    let next_slot = if next_ts_slot == current_block_slot {
-        next_ts_slot + slot_granularity_time
+    // put task in the next slot if next_ts_slot in the current slot (or already passed)
+    let next_slot = if next_ts_slot <= current_block_slot {
+        current_block_slot + slot_granularity_time
     } else {
         next_ts_slot
     };
@@ -1014,13 +1401,33 @@ impl Intervals for Interval {
                 if boundary.is_block_boundary() {
                     get_next_block_limited(env, boundary)
                 } else {
-                    get_next_cron_time(env, boundary, "0 0 * * * *", slot_granularity_time)
+                    get_next_cron_time(env, boundary, "0 0 * * * *", None, slot_granularity_time)
                 }
             }
             // return the first block within a specific range that can be triggered 1 or more times based on timestamps.
             // Uses crontab spec
             Interval::Cron(crontab) => {
-                get_next_cron_time(env, boundary, crontab, slot_granularity_time)
+                get_next_cron_time(env, boundary, crontab, None, slot_granularity_time)
+            }
+            // Same as Cron, but the crontab is matched against the wall clock of the time zone
+            Interval::CronTz { crontab, timezone } => {
+                // Validated on task creation
+                match TimeZone::from_name(timezone) {
+                    Some(tz) => {
+                        get_next_cron_time(env, boundary, crontab, Some(&tz), slot_granularity_time)
+                    }
+                    None => (0, SlotType::Cron),
+                }
+            }
+            // return the time slot of the exact timestamp, the task is triggered 1 time
+            // If the timestamp already passed, the task goes into the next slot
+            Interval::At(timestamp) => {
+                get_next_time_slot(env, boundary, timestamp.nanos(), slot_granularity_time)
+            }
+            // return the first time slot within a specific range that can be triggered 1 or more times based on a fixed period.
+            // Periods are counted from the start boundary, so execution time doesn't drift
+            Interval::Every { seconds } => {
+                get_next_period_time(env, boundary, *seconds, slot_granularity_time)
             }
             // return the block within a specific range that can be triggered 1 or more times based on block heights.
             // Uses block offset (Example: Block(100) will trigger every 100 blocks)
@@ -1035,11 +1442,16 @@ impl Intervals for Interval {
         match self {
             Interval::Once => true,
             Interval::Immediate => true,
-            Interval::Block(_) => true,
+            Interval::Block(block) => *block > 0,
             Interval::Cron(crontab) => {
                 let s = Schedule::from_str(crontab);
                 s.is_ok()
             }
+            Interval::CronTz { crontab, timezone } => {
+                Schedule::from_str(crontab).is_ok() && TimeZone::from_name(timezone).is_some()
+            }
+            Interval::At(_) => true,
+            Interval::Every { seconds } => *seconds > 0,
         }
     }
 }
@@ -1072,6 +1484,52 @@ impl GasPrice {
     }
 }
 
+/// Where the numerator of the gas price comes from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GasPriceSource {
+    /// Owner-set `gas_price`
+    Fixed,
+    /// Contract answering `GasPriceOracleQueryMsg::GasPrice` on every proxy call
+    /// Its price is clamped between `min_numerator` and `max_numerator`
+    Oracle {
+        contract_addr: Addr,
+        min_numerator: u64,
+        max_numerator: u64,
+    },
+}
+
+impl Default for GasPriceSource {
+    fn default() -> Self {
+        Self::Fixed
+    }
+}
+
+impl GasPriceSource {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            GasPriceSource::Fixed => true,
+            GasPriceSource::Oracle {
+                min_numerator,
+                max_numerator,
+                ..
+            } => *min_numerator != 0 && min_numerator <= max_numerator,
+        }
+    }
+
+    /// Lowest and highest numerator this source can give
+    pub fn numerator_bounds(&self, gas_price: &GasPrice) -> (u64, u64) {
+        match self {
+            GasPriceSource::Fixed => (gas_price.numerator, gas_price.numerator),
+            GasPriceSource::Oracle {
+                min_numerator,
+                max_numerator,
+                ..
+            } => (*min_numerator, *max_numerator),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Transform {
     pub action_idx: u64,
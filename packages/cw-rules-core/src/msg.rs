@@ -1,7 +1,8 @@
 use crate::types::{
     CheckOwnerOfNft, CheckPassedProposals, CheckProposalStatus, CroncatQuery, HasBalanceGte,
 };
-use generic_query::GenericQuery;
+use cosmwasm_std::Binary;
+use generic_query::{GenericQuery, PathToValue, ValueOrdering};
 //use cw_croncat_core::types::Rule;
 //use cosmwasm_std::Coin;
 use schemars::JsonSchema;
@@ -34,6 +35,13 @@ pub enum QueryMsg {
     CheckProposalStatus(CheckProposalStatus),
     CheckPassedProposals(CheckPassedProposals),
     GenericQuery(GenericQuery),
+    RawQuery {
+        contract_addr: String,
+        key: Binary,
+        path_to_value: PathToValue,
+        ordering: ValueOrdering,
+        value: Binary,
+    },
     // Full evaluations
     QueryConstruct(QueryConstruct),
     SmartQuery(SmartQueryHead),
@@ -54,6 +62,8 @@ pub struct QueryResponse<T = cosmwasm_std::Binary> {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct QueryConstructResponse {
     pub result: bool,
+    /// Data of every leaf query in depth-first order, see `query_leaves`
+    /// If the result is false, it holds the index of the first false query instead
     pub data: Vec<cosmwasm_std::Binary>,
 }
 
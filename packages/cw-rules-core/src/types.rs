@@ -1,5 +1,5 @@
 use cosmwasm_std::Binary;
-use generic_query::GenericQuery;
+use generic_query::{GenericQuery, PathToValue, ValueOrdering};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use smart_query::SmartQueryHead;
@@ -28,13 +28,60 @@ pub enum Status {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CroncatQuery {
-    Query { contract_addr: String, msg: Binary },
+    Query {
+        contract_addr: String,
+        msg: Binary,
+    },
     HasBalanceGte(HasBalanceGte),
     CheckOwnerOfNft(CheckOwnerOfNft),
     CheckProposalStatus(CheckProposalStatus),
     CheckPassedProposals(CheckPassedProposals),
     GenericQuery(GenericQuery),
     SmartQuery(SmartQueryHead),
+    /// Compares the JSON value stored under the `key` of the contract, e.g. a cw-storage-plus `Item`
+    RawQuery {
+        contract_addr: String,
+        key: Binary,
+        path_to_value: PathToValue,
+        ordering: ValueOrdering,
+        value: Binary,
+    },
+    /// True if all of the queries are true
+    And(Vec<CroncatQuery>),
+    /// True if any of the queries is true
+    Or(Vec<CroncatQuery>),
+    /// True if the query is false
+    Not(Box<CroncatQuery>),
+}
+
+/// Deepest nesting of `And`, `Or` and `Not` queries, leaf queries have depth 1
+pub const MAX_QUERY_DEPTH: usize = 5;
+
+impl CroncatQuery {
+    /// Nesting depth of the query, 1 for the leaf queries
+    pub fn depth(&self) -> usize {
+        match self {
+            CroncatQuery::And(queries) | CroncatQuery::Or(queries) => {
+                1 + queries.iter().map(CroncatQuery::depth).max().unwrap_or(0)
+            }
+            CroncatQuery::Not(query) => 1 + query.depth(),
+            _ => 1,
+        }
+    }
+
+    /// Leaf queries in depth-first order, the order of their data in `QueryConstructResponse`
+    pub fn leaves(&self) -> Vec<&CroncatQuery> {
+        match self {
+            CroncatQuery::And(queries) | CroncatQuery::Or(queries) => query_leaves(queries),
+            CroncatQuery::Not(query) => query.leaves(),
+            _ => vec![self],
+        }
+    }
+}
+
+/// Leaf queries of the list, `Transform.query_idx` is the index in this list
+pub fn query_leaves(queries: &[CroncatQuery]) -> Vec<&CroncatQuery> {
+    queries.iter().flat_map(CroncatQuery::leaves).collect()
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]